@@ -0,0 +1,211 @@
+// leitura das tabelas acpi (só o que o kernel usa: achar tabelas pela assinatura e a MADT)
+// o bootloader nao passa o RSDP -> procura do jeito do bios: EBDA e 0xE0000..0xFFFFF
+use crate::memory;
+use core::mem::size_of;
+use spin::Once;
+use x86_64::PhysAddr;
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct Rsdp {
+    signature: [u8; 8], // "RSD PTR "
+    checksum: u8,
+    oem_id: [u8; 6],
+    revision: u8, // 0 -> acpi 1.0 (só RSDT); 2+ -> tem XSDT
+    rsdt_address: u32,
+    // daqui pra baixo só com revision >= 2
+    length: u32,
+    xsdt_address: u64,
+    extended_checksum: u8,
+    reserved: [u8; 3],
+}
+
+const RSDP_V1_SIZE: usize = 20;
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct SdtHeader {
+    pub signature: [u8; 4],
+    pub length: u32,
+    pub revision: u8,
+    pub checksum: u8,
+    pub oem_id: [u8; 6],
+    pub oem_table_id: [u8; 8],
+    pub oem_revision: u32,
+    pub creator_id: u32,
+    pub creator_revision: u32,
+}
+
+// leitura de memoria fisica (as tabelas nao tem alinhamento garantido)
+unsafe fn read_phys<T: Copy>(addr: u64) -> T {
+    let ptr = memory::phys_to_virt(PhysAddr::new(addr)).as_ptr::<T>();
+    core::ptr::read_unaligned(ptr)
+}
+
+// soma de todos os bytes tem que dar 0
+fn checksum_ok(addr: u64, len: usize) -> bool {
+    let sum = (0..len as u64).fold(0u8, |sum, i| sum.wrapping_add(unsafe { read_phys::<u8>(addr + i) }));
+    sum == 0
+}
+
+// o RSDP fica alinhado em 16 bytes
+fn scan_rsdp(start: u64, end: u64) -> Option<u64> {
+    (start..end).step_by(16).find(|&addr| {
+        let signature: [u8; 8] = unsafe { read_phys(addr) };
+        &signature == b"RSD PTR " && checksum_ok(addr, RSDP_V1_SIZE)
+    })
+}
+
+fn find_rsdp() -> Option<Rsdp> {
+    // segmento da EBDA no endereco 0x40E da BDA
+    let ebda = (unsafe { read_phys::<u16>(0x40E) } as u64) << 4;
+    let address = if ebda != 0 {
+        scan_rsdp(ebda, ebda + 1024)
+    } else {
+        None
+    };
+    let address = address.or_else(|| scan_rsdp(0xE0000, 0x100000))?;
+    Some(unsafe { read_phys(address) })
+}
+
+// (endereco da RSDT ou XSDT, tamanho de cada entrada)
+fn root_table() -> Option<(u64, usize)> {
+    static ROOT: Once<Option<(u64, usize)>> = Once::new();
+    *ROOT.call_once(|| {
+        let rsdp = find_rsdp()?;
+        if rsdp.revision >= 2 && rsdp.xsdt_address != 0 {
+            Some((rsdp.xsdt_address, 8))
+        } else {
+            Some((rsdp.rsdt_address as u64, 4))
+        }
+    })
+}
+
+// endereco fisico da tabela com essa assinatura (ex: b"APIC", b"HPET")
+pub fn find_table(signature: &[u8; 4]) -> Option<u64> {
+    let (root, entry_size) = root_table()?;
+    let header: SdtHeader = unsafe { read_phys(root) };
+    let entries = (header.length as usize).saturating_sub(size_of::<SdtHeader>()) / entry_size;
+    let first = root + size_of::<SdtHeader>() as u64;
+    (0..entries as u64)
+        .map(|i| unsafe {
+            if entry_size == 8 {
+                read_phys::<u64>(first + i * 8)
+            } else {
+                read_phys::<u32>(first + i * 4) as u64
+            }
+        })
+        .find(|&table| {
+            let header: SdtHeader = unsafe { read_phys(table) };
+            &header.signature == signature && checksum_ok(table, header.length as usize)
+        })
+}
+
+// header da tabela (pra quem for ler o resto dela)
+pub fn table_header(addr: u64) -> SdtHeader {
+    unsafe { read_phys(addr) }
+}
+
+// o que interessa da MADT
+#[derive(Debug, Clone, Copy)]
+pub struct LocalApicInfo {
+    #[allow(dead_code)]
+    pub processor_id: u8,
+    pub apic_id: u8,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IoApicInfo {
+    #[allow(dead_code)]
+    pub id: u8,
+    pub address: u32,
+    pub gsi_base: u32, // primeira linha global (GSI) que esse I/O APIC atende
+}
+
+// irq isa que nao esta ligada na GSI de mesmo numero (ex: timer irq 0 -> GSI 2)
+#[derive(Debug, Clone, Copy)]
+pub struct InterruptOverride {
+    pub irq: u8,
+    pub gsi: u32,
+    pub flags: u16, // bits 0-1 polaridade, 2-3 trigger
+}
+
+pub struct Madt {
+    #[allow(dead_code)]
+    pub local_apic_address: u32,
+    pub local_apics: heapless::Vec<LocalApicInfo, 64>,
+    pub io_apics: heapless::Vec<IoApicInfo, 8>,
+    pub overrides: heapless::Vec<InterruptOverride, 16>,
+}
+
+impl Madt {
+    // GSI e flags de uma irq isa (sem override: mesma linha, flags padrao do barramento)
+    pub fn isa_irq(&self, irq: u8) -> (u32, u16) {
+        match self.overrides.iter().find(|o| o.irq == irq) {
+            Some(o) => (o.gsi, o.flags),
+            None => (irq as u32, 0),
+        }
+    }
+}
+
+const MADT_LOCAL_APIC: u8 = 0;
+const MADT_IO_APIC: u8 = 1;
+const MADT_OVERRIDE: u8 = 2;
+
+fn parse_madt() -> Option<Madt> {
+    let table = find_table(b"APIC")?;
+    let header = table_header(table);
+    let mut madt = Madt {
+        local_apic_address: unsafe { read_phys(table + size_of::<SdtHeader>() as u64) },
+        local_apics: heapless::Vec::new(),
+        io_apics: heapless::Vec::new(),
+        overrides: heapless::Vec::new(),
+    };
+
+    // depois do header: endereco do lapic (4) + flags (4), e as entradas (tipo, tamanho, ...)
+    let end = table + header.length as u64;
+    let mut entry = table + size_of::<SdtHeader>() as u64 + 8;
+    while entry + 2 <= end {
+        let kind: u8 = unsafe { read_phys(entry) };
+        let len: u8 = unsafe { read_phys(entry + 1) };
+        if len < 2 {
+            break; // tabela quebrada
+        }
+        unsafe {
+            match kind {
+                MADT_LOCAL_APIC => {
+                    let flags: u32 = read_phys(entry + 4);
+                    let _ = madt.local_apics.push(LocalApicInfo {
+                        processor_id: read_phys(entry + 2),
+                        apic_id: read_phys(entry + 3),
+                        enabled: flags & 1 != 0,
+                    });
+                }
+                MADT_IO_APIC => {
+                    let _ = madt.io_apics.push(IoApicInfo {
+                        id: read_phys(entry + 2),
+                        address: read_phys(entry + 4),
+                        gsi_base: read_phys(entry + 8),
+                    });
+                }
+                MADT_OVERRIDE => {
+                    let _ = madt.overrides.push(InterruptOverride {
+                        irq: read_phys(entry + 3),
+                        gsi: read_phys(entry + 4),
+                        flags: read_phys(entry + 8),
+                    });
+                }
+                _ => {}
+            }
+        }
+        entry += len as u64;
+    }
+    Some(madt)
+}
+
+// lida uma vez só; None -> sem acpi (ou sem MADT)
+pub fn madt() -> Option<&'static Madt> {
+    static MADT: Once<Option<Madt>> = Once::new();
+    MADT.call_once(parse_madt).as_ref()
+}
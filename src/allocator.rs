@@ -0,0 +1,170 @@
+// heap do kernel: uma regiao virtual (reservada no vm), mapeada pagina por pagina com frames do alocador fisico
+// o #[global_allocator] daqui é o que faz Box, Vec, String, BTreeMap (crate alloc) funcionarem
+use crate::memory::{self, frame, mapper};
+use crate::vm;
+use core::alloc::{GlobalAlloc, Layout};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use x86_64::structures::paging::{Page, PageSize, PageTableFlags, Size2MiB};
+use x86_64::VirtAddr;
+
+#[cfg(feature = "bump_allocator")]
+pub mod bump;
+#[cfg(feature = "debug_allocator")]
+pub mod debug;
+#[cfg(not(feature = "bump_allocator"))]
+pub mod fixed_size_block;
+#[cfg(not(feature = "bump_allocator"))]
+pub mod linked_list;
+pub mod oom;
+pub mod slab;
+
+// qual alocador atende o heap (feature bump_allocator troca pelo bump)
+#[cfg(not(feature = "bump_allocator"))]
+type Heap = fixed_size_block::FixedSizeBlockAllocator;
+#[cfg(feature = "bump_allocator")]
+type Heap = bump::BumpAllocator;
+
+// 2 MiB: cabe numa pagina grande só
+pub const HEAP_SIZE: usize = 2 * 1024 * 1024;
+// onde o vm colocou o heap (0 antes do init_heap)
+static HEAP_START: AtomicU64 = AtomicU64::new(0);
+
+static ALLOCATOR: Locked<Heap> = Locked::new(Heap::new());
+
+#[global_allocator]
+static KERNEL_HEAP: KernelHeap = KernelHeap;
+
+// bytes pedidos (layout.size) vivos agora e o maximo que ja teve
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+// o que o rust chama: passa pro alocador do heap (pelo debug, com a feature), tenta os hooks de oom
+// quando falta e conta os bytes
+struct KernelHeap;
+
+unsafe fn heap_alloc(layout: Layout) -> *mut u8 {
+    #[cfg(feature = "debug_allocator")]
+    return debug::alloc(&ALLOCATOR, layout);
+    #[cfg(not(feature = "debug_allocator"))]
+    ALLOCATOR.alloc(layout)
+}
+
+unsafe fn heap_dealloc(ptr: *mut u8, layout: Layout) {
+    #[cfg(feature = "debug_allocator")]
+    debug::dealloc(&ALLOCATOR, ptr, layout);
+    #[cfg(not(feature = "debug_allocator"))]
+    ALLOCATOR.dealloc(ptr, layout);
+}
+
+unsafe impl GlobalAlloc for KernelHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut ptr = heap_alloc(layout);
+        if ptr.is_null() {
+            ptr = oom::recover(layout, || heap_alloc(layout));
+        }
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        heap_dealloc(ptr, layout);
+    }
+}
+
+// spin::Mutex em volta do alocador (o GlobalAlloc só recebe &self)
+pub struct Locked<A> {
+    inner: spin::Mutex<A>,
+}
+
+impl<A> Locked<A> {
+    pub const fn new(inner: A) -> Locked<A> {
+        Locked {
+            inner: spin::Mutex::new(inner),
+        }
+    }
+
+    // sem interrupcao enquanto o lock estiver pego -> um handler que aloca nao trava
+    pub fn with<R, F: FnOnce(&mut A) -> R>(&self, f: F) -> R {
+        crate::cpu::without_interrupts(|| f(&mut self.inner.lock()))
+    }
+}
+
+// `addr` arredondado pra cima ate um multiplo de `align` (potencia de 2)
+pub fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+// reserva a regiao no espaco do kernel, mapeia e entrega pro alocador; depois do vm::init
+// com 2 MiB fisicos seguidos (ordem 9 do buddy) o heap inteiro vira uma pagina grande, senao vai de 4 KiB
+pub fn init_heap() -> Result<(), vm::Error> {
+    let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+    let heap_start = vm::with_kernel(|space| {
+        space.reserve(HEAP_SIZE as u64, Size2MiB::SIZE, flags, vm::Backing::Anonymous, "heap")
+    })?;
+    let huge = match frame::allocate_contiguous(9) {
+        Some(block) if mapper::huge_pages_supported() => Some(block),
+        Some(block) => {
+            frame::deallocate_contiguous(block, 9);
+            None
+        }
+        None => None,
+    };
+    match huge {
+        Some(block) => memory::with_mapper(|mapper| unsafe {
+            mapper.map_range(heap_start, block.start_address(), HEAP_SIZE as u64, flags | PageTableFlags::HUGE_PAGE)
+        })?,
+        None => {
+            let start = Page::containing_address(heap_start);
+            let end = Page::containing_address(heap_start + HEAP_SIZE as u64 - 1);
+            for page in Page::range_inclusive(start, end) {
+                let frame = frame::allocate_frame().ok_or(mapper::Error::OutOfFrames)?;
+                memory::with_mapper(|mapper| unsafe { mapper.map_page(page, frame, flags) })?;
+            }
+        }
+    }
+    HEAP_START.store(heap_start.as_u64(), Ordering::Relaxed);
+    ALLOCATOR.with(|allocator| unsafe { allocator.init(heap_start.as_u64() as usize, HEAP_SIZE) });
+    #[cfg(not(feature = "bump_allocator"))]
+    oom::register("blocos livres", |_| ALLOCATOR.with(|allocator| allocator.release_cached()));
+    Ok(())
+}
+
+pub fn heap_start() -> VirtAddr {
+    VirtAddr::new(HEAP_START.load(Ordering::Relaxed))
+}
+
+// bytes livres no heap agora (somando os buracos, nao quer dizer que cabe um bloco desse tamanho)
+pub fn free_bytes() -> usize {
+    ALLOCATOR.with(|allocator| allocator.free_bytes())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeapStats {
+    pub size: usize,
+    pub allocated: usize, // o que foi pedido (sem arredondamento dos blocos)
+    pub free: usize,
+    pub peak: usize,
+}
+
+pub fn stats() -> HeapStats {
+    HeapStats {
+        size: HEAP_SIZE,
+        allocated: ALLOCATED.load(Ordering::Relaxed),
+        free: free_bytes(),
+        peak: PEAK.load(Ordering::Relaxed),
+    }
+}
+
+// nome do alocador em uso (pro log do boot)
+pub fn name() -> &'static str {
+    match (cfg!(feature = "bump_allocator"), cfg!(feature = "debug_allocator")) {
+        (true, false) => "bump",
+        (true, true) => "bump, debug",
+        (false, false) => "blocos fixos",
+        (false, true) => "blocos fixos, debug",
+    }
+}
@@ -0,0 +1,64 @@
+// bump: só anda um ponteiro pra frente, nunca reaproveita nada ate tudo ser liberado
+// (quando o contador de alocacoes volta pra 0 o ponteiro volta pro comeco)
+// serve pra medir quanto o alocador de verdade custa e pra debugar suspeita de bug nele
+use super::{align_up, Locked};
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
+
+pub struct BumpAllocator {
+    heap_start: usize,
+    heap_end: usize,
+    next: usize,
+    allocations: usize, // vivas agora
+}
+
+impl BumpAllocator {
+    pub const fn new() -> BumpAllocator {
+        BumpAllocator {
+            heap_start: 0,
+            heap_end: 0,
+            next: 0,
+            allocations: 0,
+        }
+    }
+
+    // unsafe: a regiao tem que estar mapeada, sem uso e só ser passada uma vez
+    pub unsafe fn init(&mut self, start: usize, size: usize) {
+        self.heap_start = start;
+        self.heap_end = start + size;
+        self.next = start;
+    }
+
+    pub fn free_bytes(&self) -> usize {
+        self.heap_end - self.next
+    }
+
+    #[allow(dead_code)]
+    pub fn allocations(&self) -> usize {
+        self.allocations
+    }
+}
+
+unsafe impl GlobalAlloc for Locked<BumpAllocator> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.with(|bump| {
+            let alloc_start = align_up(bump.next, layout.align());
+            let alloc_end = match alloc_start.checked_add(layout.size()) {
+                Some(end) if end <= bump.heap_end => end,
+                _ => return ptr::null_mut(),
+            };
+            bump.next = alloc_end;
+            bump.allocations += 1;
+            alloc_start as *mut u8
+        })
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
+        self.with(|bump| {
+            bump.allocations -= 1;
+            if bump.allocations == 0 {
+                bump.next = bump.heap_start;
+            }
+        })
+    }
+}
@@ -0,0 +1,97 @@
+// alocador de debug (feature debug_allocator): embrulha o alocador do heap
+// cada alocacao ganha um cabecalho (tamanho + marca) e uma redzone antes e depois, cheias de REDZONE
+// no free as redzones sao conferidas -> quem escreveu fora do proprio bloco vira panic com o endereco e o tamanho
+// e o bloco inteiro é pintado com POISON -> ponteiro lido de memoria liberada vira 0xdede... e estoura logo
+// ex de layout (align 16): [livre do alocador | cabecalho | redzone 16 | dados | redzone 16]
+use crate::allocator::align_up;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
+
+const REDZONE: usize = 16;
+const REDZONE_BYTE: u8 = 0xfd;
+pub const POISON: u8 = 0xde;
+const HEADER: usize = 16;
+// os primeiros 16 bytes do bloco sao do no da lista livre do alocador de baixo -> o cabecalho fica depois
+const RESERVED: usize = 16;
+
+const LIVE: usize = 0xa110_c8ed_a110_c8ed;
+const FREED: usize = 0xdead_f4ee_dead_f4ee;
+
+#[repr(C)]
+struct Header {
+    size: usize,
+    magic: usize,
+}
+
+// quanto vem antes dos dados (multiplo do alinhamento)
+fn front(layout: Layout) -> usize {
+    align_up(RESERVED + HEADER + REDZONE, layout.align())
+}
+
+fn inner_layout(layout: Layout) -> Option<Layout> {
+    let size = front(layout).checked_add(layout.size())?.checked_add(REDZONE)?;
+    Layout::from_size_align(size, layout.align().max(16)).ok()
+}
+
+unsafe fn header(data: *mut u8) -> *mut Header {
+    data.sub(REDZONE + HEADER) as *mut Header
+}
+
+// primeiro byte da redzone que nao tem o padrao
+unsafe fn damaged(start: *const u8) -> Option<usize> {
+    (0..REDZONE).find(|&i| *start.add(i) != REDZONE_BYTE)
+}
+
+pub unsafe fn alloc<A: GlobalAlloc>(inner: &A, layout: Layout) -> *mut u8 {
+    let inner_layout = match inner_layout(layout) {
+        Some(inner_layout) => inner_layout,
+        None => return ptr::null_mut(),
+    };
+    let base = inner.alloc(inner_layout);
+    if base.is_null() {
+        return base;
+    }
+    let data = base.add(front(layout));
+    header(data).write(Header {
+        size: layout.size(),
+        magic: LIVE,
+    });
+    ptr::write_bytes(data.sub(REDZONE), REDZONE_BYTE, REDZONE);
+    ptr::write_bytes(data.add(layout.size()), REDZONE_BYTE, REDZONE);
+    data
+}
+
+pub unsafe fn dealloc<A: GlobalAlloc>(inner: &A, data: *mut u8, layout: Layout) {
+    let header = header(data);
+    let address = data as usize;
+    match (*header).magic {
+        LIVE => {}
+        FREED => panic!("heap: double free de {:#x} ({} bytes)", address, layout.size()),
+        _ => panic!("heap: cabecalho de {:#x} estragado ({} bytes, alguem escreveu antes do bloco)", address, layout.size()),
+    }
+    if (*header).size != layout.size() {
+        panic!("heap: free de {:#x} com {} bytes, mas foram alocados {}", address, layout.size(), (*header).size);
+    }
+    if let Some(offset) = damaged(data.sub(REDZONE)) {
+        panic!(
+            "heap: redzone antes de {:#x} ({} bytes) estragada no byte -{}",
+            address,
+            layout.size(),
+            REDZONE - offset
+        );
+    }
+    if let Some(offset) = damaged(data.add(layout.size())) {
+        panic!(
+            "heap: estouro depois de {:#x} ({} bytes): redzone estragada no byte +{}",
+            address,
+            layout.size(),
+            layout.size() + offset
+        );
+    }
+    (*header).magic = FREED;
+    ptr::write_bytes(data, POISON, layout.size());
+    // inner_layout ja deu certo no alloc
+    if let Some(inner_layout) = inner_layout(layout) {
+        inner.dealloc(data.sub(front(layout)), inner_layout);
+    }
+}
@@ -0,0 +1,118 @@
+// blocos de tamanho fixo: uma lista de blocos livres pra cada tamanho (16, 32, ... 2048)
+// alloc pequeno pega o primeiro da lista do tamanho e o dealloc devolve pra ela -> O(1), sem percorrer nada
+// lista vazia ou pedido maior que 2048 -> lista ligada (que é de onde os blocos saem da primeira vez)
+// obs: bloco que entrou numa lista nunca volta pra lista ligada
+use super::linked_list::LinkedListAllocator;
+use super::Locked;
+use core::alloc::{GlobalAlloc, Layout};
+use core::mem;
+use core::ptr::NonNull;
+
+// cada tamanho tambem é o alinhamento do bloco -> tem que ser potencia de 2
+const BLOCK_SIZES: &[usize] = &[16, 32, 64, 128, 256, 512, 1024, 2048];
+
+struct ListNode {
+    next: Option<&'static mut ListNode>,
+}
+
+pub struct FixedSizeBlockAllocator {
+    list_heads: [Option<&'static mut ListNode>; BLOCK_SIZES.len()],
+    fallback: LinkedListAllocator,
+}
+
+impl FixedSizeBlockAllocator {
+    pub const fn new() -> FixedSizeBlockAllocator {
+        FixedSizeBlockAllocator {
+            list_heads: [const { None }; BLOCK_SIZES.len()],
+            fallback: LinkedListAllocator::new(),
+        }
+    }
+
+    // unsafe: a regiao tem que estar mapeada, sem uso e só ser passada uma vez
+    pub unsafe fn init(&mut self, start: usize, size: usize) {
+        self.fallback.init(start, size);
+    }
+
+    // menor tamanho que atende o pedido (tamanho e alinhamento)
+    fn list_index(layout: &Layout) -> Option<usize> {
+        let required = layout.size().max(layout.align());
+        BLOCK_SIZES.iter().position(|&size| size >= required)
+    }
+
+    pub fn allocate(&mut self, layout: Layout) -> *mut u8 {
+        let Some(index) = Self::list_index(&layout) else {
+            return self.fallback.allocate(layout);
+        };
+        match self.list_heads[index].take() {
+            Some(node) => {
+                self.list_heads[index] = node.next.take();
+                node as *mut ListNode as *mut u8
+            }
+            None => {
+                let size = BLOCK_SIZES[index];
+                let layout = Layout::from_size_align(size, size).unwrap();
+                self.fallback.allocate(layout)
+            }
+        }
+    }
+
+    // unsafe: `ptr` tem que ter vindo do allocate com o mesmo layout
+    pub unsafe fn deallocate(&mut self, ptr: *mut u8, layout: Layout) {
+        let Some(index) = Self::list_index(&layout) else {
+            self.fallback.deallocate(ptr, layout);
+            return;
+        };
+        // o menor bloco (16) cabe um no, e o alinhamento dele é o do bloco
+        debug_assert!(mem::size_of::<ListNode>() <= BLOCK_SIZES[index]);
+        debug_assert!(mem::align_of::<ListNode>() <= BLOCK_SIZES[index]);
+        let mut node_ptr = NonNull::new_unchecked(ptr as *mut ListNode);
+        node_ptr.as_ptr().write(ListNode {
+            next: self.list_heads[index].take(),
+        });
+        self.list_heads[index] = Some(node_ptr.as_mut());
+    }
+
+    // devolve os blocos parados nas listas pro fallback (que junta os vizinhos) -> hook de oom:
+    // uma lista cheia de blocos de 64 nao ajuda um pedido de 4 KiB. devolve os bytes devolvidos
+    pub fn release_cached(&mut self) -> usize {
+        let mut released = 0;
+        for (head, &size) in self.list_heads.iter_mut().zip(BLOCK_SIZES) {
+            while let Some(node) = head.take() {
+                *head = node.next.take();
+                let layout = Layout::from_size_align(size, size).unwrap();
+                unsafe { self.fallback.deallocate(node as *mut ListNode as *mut u8, layout) };
+                released += size;
+            }
+        }
+        released
+    }
+
+    // blocos parados nas listas tambem contam como livres
+    pub fn free_bytes(&self) -> usize {
+        let cached: usize = self
+            .list_heads
+            .iter()
+            .zip(BLOCK_SIZES)
+            .map(|(head, size)| {
+                let mut count = 0;
+                let mut current = head.as_deref();
+                while let Some(node) = current {
+                    count += 1;
+                    current = node.next.as_deref();
+                }
+                count * size
+            })
+            .sum();
+        self.fallback.free_bytes() + cached
+    }
+}
+
+unsafe impl GlobalAlloc for Locked<FixedSizeBlockAllocator> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.with(|allocator| allocator.allocate(layout))
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.with(|allocator| allocator.deallocate(ptr, layout));
+    }
+}
@@ -0,0 +1,169 @@
+// lista dos buracos livres, guardada dentro dos proprios buracos (cada um comeca com um ListNode)
+// a lista fica ordenada por endereco -> no dealloc o bloco volta pro lugar e junta com os vizinhos
+// alloc: primeiro buraco onde cabe (first fit), o que sobra antes/depois continua na lista
+use super::{align_up, Locked};
+use core::alloc::{GlobalAlloc, Layout};
+use core::mem;
+use core::ptr;
+
+struct ListNode {
+    size: usize,
+    next: Option<&'static mut ListNode>,
+}
+
+impl ListNode {
+    const fn new(size: usize) -> ListNode {
+        ListNode { size, next: None }
+    }
+
+    fn start(&self) -> usize {
+        self as *const ListNode as usize
+    }
+
+    fn end(&self) -> usize {
+        self.start() + self.size
+    }
+}
+
+pub struct LinkedListAllocator {
+    head: ListNode, // no falso, só aponta pro primeiro buraco
+}
+
+impl LinkedListAllocator {
+    pub const fn new() -> LinkedListAllocator {
+        LinkedListAllocator { head: ListNode::new(0) }
+    }
+
+    // unsafe: a regiao tem que estar mapeada, sem uso e só ser passada uma vez
+    pub unsafe fn init(&mut self, start: usize, size: usize) {
+        self.add_free_region(start, size);
+    }
+
+    // coloca o buraco na posicao certa da lista e junta com o anterior/proximo se encostarem
+    unsafe fn add_free_region(&mut self, addr: usize, size: usize) {
+        assert_eq!(align_up(addr, mem::align_of::<ListNode>()), addr);
+        assert!(size >= mem::size_of::<ListNode>());
+
+        let mut previous = &mut self.head;
+        while let Some(ref next) = previous.next {
+            if next.start() > addr {
+                break;
+            }
+            previous = previous.next.as_mut().unwrap();
+        }
+
+        // encosta no anterior -> só cresce ele (o head tem tamanho 0, nunca encosta)
+        if previous.size != 0 && previous.end() == addr {
+            previous.size += size;
+            Self::merge_next(previous);
+            return;
+        }
+
+        let mut node = ListNode::new(size);
+        node.next = previous.next.take();
+        let node_ptr = addr as *mut ListNode;
+        node_ptr.write(node);
+        previous.next = Some(&mut *node_ptr);
+        Self::merge_next(previous.next.as_mut().unwrap());
+    }
+
+    fn merge_next(node: &mut ListNode) {
+        let touches = matches!(node.next, Some(ref next) if next.start() == node.end());
+        if touches {
+            let next = node.next.take().unwrap();
+            node.size += next.size;
+            node.next = next.next.take();
+        }
+    }
+
+    // tira da lista o primeiro buraco onde cabe `size` com `align`; devolve o buraco e o endereco do bloco
+    fn find_region(&mut self, size: usize, align: usize) -> Option<(&'static mut ListNode, usize)> {
+        let mut current = &mut self.head;
+        while let Some(ref mut region) = current.next {
+            if let Ok(alloc_start) = Self::alloc_from_region(region, size, align) {
+                let next = region.next.take();
+                let found = current.next.take().unwrap();
+                current.next = next;
+                return Some((found, alloc_start));
+            }
+            current = current.next.as_mut().unwrap();
+        }
+        None
+    }
+
+    // o que sobrar antes e depois tem que caber um ListNode, senao vira memoria perdida
+    fn alloc_from_region(region: &ListNode, size: usize, align: usize) -> Result<usize, ()> {
+        let mut alloc_start = align_up(region.start(), align);
+        // sobra antes do bloco pequena demais pra um no -> empurra pro proximo alinhamento
+        let gap = alloc_start - region.start();
+        if gap > 0 && gap < mem::size_of::<ListNode>() {
+            alloc_start = align_up(region.start() + mem::size_of::<ListNode>(), align);
+        }
+        let alloc_end = alloc_start.checked_add(size).ok_or(())?;
+        if alloc_end > region.end() {
+            return Err(());
+        }
+        let excess = region.end() - alloc_end;
+        if excess > 0 && excess < mem::size_of::<ListNode>() {
+            return Err(());
+        }
+        Ok(alloc_start)
+    }
+
+    // todo bloco tem que poder virar um ListNode quando voltar
+    fn size_align(layout: Layout) -> (usize, usize) {
+        let layout = layout
+            .align_to(mem::align_of::<ListNode>())
+            .expect("alinhamento invalido")
+            .pad_to_align();
+        let size = layout.size().max(mem::size_of::<ListNode>());
+        (size, layout.align())
+    }
+
+    // null se nao tem buraco que caiba
+    pub fn allocate(&mut self, layout: Layout) -> *mut u8 {
+        let (size, align) = Self::size_align(layout);
+        let Some((region, alloc_start)) = self.find_region(size, align) else {
+            return ptr::null_mut();
+        };
+        let region_start = region.start();
+        let region_end = region.end();
+        let alloc_end = alloc_start + size;
+        unsafe {
+            // o pedaco antes do alinhamento volta pra lista (o alloc_from_region garante que cabe um no)
+            if alloc_start > region_start {
+                self.add_free_region(region_start, alloc_start - region_start);
+            }
+            if region_end > alloc_end {
+                self.add_free_region(alloc_end, region_end - alloc_end);
+            }
+        }
+        alloc_start as *mut u8
+    }
+
+    // unsafe: `ptr` tem que ter vindo do allocate com o mesmo layout
+    pub unsafe fn deallocate(&mut self, ptr: *mut u8, layout: Layout) {
+        let (size, _) = Self::size_align(layout);
+        self.add_free_region(ptr as usize, size);
+    }
+
+    pub fn free_bytes(&self) -> usize {
+        let mut total = 0;
+        let mut current = self.head.next.as_deref();
+        while let Some(region) = current {
+            total += region.size;
+            current = region.next.as_deref();
+        }
+        total
+    }
+}
+
+unsafe impl GlobalAlloc for Locked<LinkedListAllocator> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.with(|allocator| allocator.allocate(layout))
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.with(|allocator| allocator.deallocate(ptr, layout));
+    }
+}
@@ -0,0 +1,108 @@
+// sem memoria no heap: antes de desistir (alloc_error -> panic) o KernelHeap chama os hooks registrados,
+// em ordem, e tenta a alocacao de novo depois de cada um que liberou alguma coisa
+// ex de hook: largar caches, encolher o scrollback, matar a tarefa mais nova
+// cada vez que acontece vira um Event no log (circular), com o hook que resolveu ou nenhum
+// os hooks rodam dentro de um alloc: se um deles alocar e faltar de novo (na mesma cpu), essa alocacao volta
+// null direto
+use crate::cpu;
+use core::alloc::Layout;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use spin::Mutex;
+
+const MAX_HOOKS: usize = 8;
+pub const LOG_SIZE: usize = 16;
+
+// recebe quantos bytes faltaram, devolve quantos (mais ou menos) liberou; 0 = nao tinha o que fazer
+pub type Hook = fn(needed: usize) -> usize;
+
+#[derive(Clone, Copy)]
+struct Registered {
+    name: &'static str,
+    hook: Hook,
+}
+
+static HOOKS: Mutex<heapless::Vec<Registered, MAX_HOOKS>> = Mutex::new(heapless::Vec::new());
+
+// false se ja tem MAX_HOOKS
+#[cfg_attr(feature = "bump_allocator", allow(dead_code))]
+pub fn register(name: &'static str, hook: Hook) -> bool {
+    cpu::without_interrupts(|| HOOKS.lock().push(Registered { name, hook }).is_ok())
+}
+
+pub fn hooks() -> heapless::Vec<&'static str, MAX_HOOKS> {
+    cpu::without_interrupts(|| HOOKS.lock().iter().map(|hook| hook.name).collect())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Event {
+    pub size: usize,
+    pub align: usize,
+    pub uptime_ms: u64,
+    pub resolved_by: Option<&'static str>,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ms: {} bytes (align {}) -> ", self.uptime_ms, self.size, self.align)?;
+        match self.resolved_by {
+            Some(name) => write!(f, "resolvido por {}", name),
+            None => f.write_str("sem memoria"),
+        }
+    }
+}
+
+static LOG: Mutex<heapless::Deque<Event, LOG_SIZE>> = Mutex::new(heapless::Deque::new());
+static EVENTS: AtomicU64 = AtomicU64::new(0);
+// um hook alocando nao entra aqui de novo; por cpu (pelo apic id, como o IN_POLICY da page fault) -> a outra
+// cpu sem memoria ao mesmo tempo roda os hooks dela em vez de levar null
+static RUNNING: [AtomicBool; 256] = [const { AtomicBool::new(false) }; 256];
+
+fn record(event: Event) {
+    EVENTS.fetch_add(1, Ordering::Relaxed);
+    cpu::without_interrupts(|| {
+        let mut log = LOG.lock();
+        if log.is_full() {
+            log.pop_front();
+        }
+        let _ = log.push_back(event);
+    });
+}
+
+// chamado pelo KernelHeap quando o alocador devolveu null; `retry` tenta de novo
+pub fn recover<F: FnMut() -> *mut u8>(layout: Layout, mut retry: F) -> *mut u8 {
+    let running = &RUNNING[crate::percpu::initial_apic_id() as usize];
+    if running.swap(true, Ordering::Acquire) {
+        return core::ptr::null_mut();
+    }
+    // copia: o lock nao fica pego enquanto os hooks rodam
+    let hooks = cpu::without_interrupts(|| HOOKS.lock().clone());
+    let mut result = (core::ptr::null_mut(), None);
+    for hook in &hooks {
+        if (hook.hook)(layout.size()) == 0 {
+            continue;
+        }
+        let ptr = retry();
+        if !ptr.is_null() {
+            result = (ptr, Some(hook.name));
+            break;
+        }
+    }
+    running.store(false, Ordering::Release);
+    record(Event {
+        size: layout.size(),
+        align: layout.align(),
+        uptime_ms: crate::time::uptime_ms(),
+        resolved_by: result.1,
+    });
+    result.0
+}
+
+// quantas vezes faltou memoria desde o boot (o log só guarda as ultimas LOG_SIZE)
+pub fn count() -> u64 {
+    EVENTS.load(Ordering::Relaxed)
+}
+
+pub fn events() -> heapless::Vec<Event, LOG_SIZE> {
+    cpu::without_interrupts(|| LOG.lock().iter().copied().collect())
+}
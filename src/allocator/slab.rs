@@ -0,0 +1,206 @@
+// slab: cache de objetos de um tipo só (tarefas, arquivos abertos, buffers de rede...)
+// cada slab é um frame de 4 KiB tirado direto do alocador de frames (acessado pelo offset da memoria fisica)
+// e cortado em slots do tamanho de T -> sem fragmentar o heap e alloc/free é tirar/por na lista de slots livres
+// ex: static TASKS: SlabCache<Task> = SlabCache::new("task");
+//     let task = TASKS.alloc(Task::new())?; // volta pro cache no drop
+// obs: slab vazio nao volta pro alocador de frames (ainda)
+use crate::cpu;
+use crate::memory::{self, frame};
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use spin::Mutex;
+
+const SLAB_SIZE: usize = frame::FRAME_SIZE as usize;
+const MAX_CACHES: usize = 16;
+
+// slot livre guarda o endereco do proximo livre (0 = fim)
+struct Inner {
+    free: usize,
+    slabs: usize,
+    in_use: usize,
+    allocations: u64,
+    registered: bool,
+}
+
+pub struct SlabCache<T> {
+    name: &'static str,
+    inner: Mutex<Inner>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub name: &'static str,
+    pub object_size: usize,
+    pub slabs: usize,
+    pub in_use: usize,
+    pub free: usize,
+    pub allocations: u64,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<12} {:>5} B  {:>3} slabs  {:>5} em uso  {:>5} livres  {:>8} allocs",
+            self.name, self.object_size, self.slabs, self.in_use, self.free, self.allocations
+        )
+    }
+}
+
+// pra listar caches de tipos diferentes juntos
+pub trait Cache: Sync {
+    fn stats(&self) -> Stats;
+}
+
+static CACHES: Mutex<heapless::Vec<&'static dyn Cache, MAX_CACHES>> = Mutex::new(heapless::Vec::new());
+
+impl<T: 'static> SlabCache<T> {
+    // cada slot precisa caber o T e o ponteiro da lista de livres, alinhado pro T
+    const SLOT_SIZE: usize = {
+        let size = if mem::size_of::<T>() > mem::size_of::<usize>() {
+            mem::size_of::<T>()
+        } else {
+            mem::size_of::<usize>()
+        };
+        let align = if mem::align_of::<T>() > mem::align_of::<usize>() {
+            mem::align_of::<T>()
+        } else {
+            mem::align_of::<usize>()
+        };
+        assert!(align <= SLAB_SIZE);
+        (size + align - 1) & !(align - 1)
+    };
+    const PER_SLAB: usize = {
+        assert!(Self::SLOT_SIZE <= SLAB_SIZE, "objeto maior que um slab");
+        SLAB_SIZE / Self::SLOT_SIZE
+    };
+
+    pub const fn new(name: &'static str) -> SlabCache<T> {
+        SlabCache {
+            name,
+            inner: Mutex::new(Inner {
+                free: 0,
+                slabs: 0,
+                in_use: 0,
+                allocations: 0,
+                registered: false,
+            }),
+            _marker: PhantomData,
+        }
+    }
+
+    // None se nao tem frame pra um slab novo (o valor é descartado)
+    pub fn alloc(&'static self, value: T) -> Option<SlabBox<T>> {
+        let slot = cpu::without_interrupts(|| {
+            let mut inner = self.inner.lock();
+            if inner.free == 0 {
+                Self::grow(&mut inner)?;
+            }
+            if !inner.registered {
+                inner.registered = CACHES.lock().push(self).is_ok();
+            }
+            let slot = inner.free;
+            inner.free = unsafe { *(slot as *const usize) };
+            inner.in_use += 1;
+            inner.allocations += 1;
+            Some(slot)
+        })?;
+        let ptr = slot as *mut T;
+        unsafe {
+            ptr.write(value);
+            Some(SlabBox {
+                ptr: NonNull::new_unchecked(ptr),
+                cache: self,
+            })
+        }
+    }
+
+    // um frame novo cortado em slots, todos pra lista de livres
+    fn grow(inner: &mut Inner) -> Option<()> {
+        let frame = frame::allocate_frame()?;
+        let base = memory::phys_to_virt(frame.start_address()).as_u64() as usize;
+        for index in (0..Self::PER_SLAB).rev() {
+            let slot = base + index * Self::SLOT_SIZE;
+            unsafe { *(slot as *mut usize) = inner.free };
+            inner.free = slot;
+        }
+        inner.slabs += 1;
+        Some(())
+    }
+
+    // unsafe: o slot tem que ser desse cache e o T dentro já ter sido dropado
+    unsafe fn free(&self, slot: usize) {
+        cpu::without_interrupts(|| {
+            let mut inner = self.inner.lock();
+            *(slot as *mut usize) = inner.free;
+            inner.free = slot;
+            inner.in_use -= 1;
+        });
+    }
+}
+
+impl<T: 'static> Cache for SlabCache<T> {
+    fn stats(&self) -> Stats {
+        cpu::without_interrupts(|| {
+            let inner = self.inner.lock();
+            let capacity = inner.slabs * Self::PER_SLAB;
+            Stats {
+                name: self.name,
+                object_size: Self::SLOT_SIZE,
+                slabs: inner.slabs,
+                in_use: inner.in_use,
+                free: capacity - inner.in_use,
+                allocations: inner.allocations,
+            }
+        })
+    }
+}
+
+// caches que já alocaram alguma coisa
+pub fn caches() -> heapless::Vec<Stats, MAX_CACHES> {
+    // copia a lista antes: o stats() de cada um pega o lock dele
+    let caches = cpu::without_interrupts(|| CACHES.lock().clone());
+    caches.iter().map(|cache| cache.stats()).collect()
+}
+
+// dono de um objeto dentro de um slab, volta pro cache no drop (tipo um Box)
+pub struct SlabBox<T: 'static> {
+    ptr: NonNull<T>,
+    cache: &'static SlabCache<T>,
+}
+
+unsafe impl<T: Send> Send for SlabBox<T> {}
+unsafe impl<T: Sync> Sync for SlabBox<T> {}
+
+impl<T> Deref for SlabBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for SlabBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for SlabBox<T> {
+    fn drop(&mut self) {
+        unsafe {
+            self.ptr.as_ptr().drop_in_place();
+            self.cache.free(self.ptr.as_ptr() as usize);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SlabBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
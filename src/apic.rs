@@ -0,0 +1,132 @@
+// local apic: o controlador de interrupcoes de cada cpu (substitui o 8259 nas maquinas novas)
+// os registradores sao mmio (por padrao no fisico 0xFEE00000), acessados pelo mapeamento da memoria fisica
+use crate::cpu::msr;
+use crate::memory;
+use core::sync::atomic::{AtomicU64, Ordering};
+use x86_64::PhysAddr;
+
+const APIC_BASE_ENABLE: u64 = 1 << 11;
+const APIC_BASE_MASK: u64 = 0x000f_ffff_ffff_f000;
+
+// offsets dos registradores
+pub const ID: usize = 0x020;
+#[allow(dead_code)]
+pub const VERSION: usize = 0x030;
+pub const TASK_PRIORITY: usize = 0x080;
+pub const EOI: usize = 0x0B0;
+pub const SPURIOUS: usize = 0x0F0;
+pub const ERROR_STATUS: usize = 0x280;
+pub const ICR_LOW: usize = 0x300;
+pub const ICR_HIGH: usize = 0x310;
+pub const LVT_TIMER: usize = 0x320;
+pub const LVT_LINT0: usize = 0x350;
+pub const LVT_LINT1: usize = 0x360;
+pub const LVT_ERROR: usize = 0x370;
+
+const SOFTWARE_ENABLE: u32 = 1 << 8;
+const LVT_MASKED: u32 = 1 << 16;
+
+// campos do ICR (interrupt command register)
+#[allow(dead_code)]
+pub const IPI_FIXED: u32 = 0b000 << 8;
+pub const IPI_INIT: u32 = 0b101 << 8;
+pub const IPI_STARTUP: u32 = 0b110 << 8;
+pub const IPI_ASSERT: u32 = 1 << 14;
+const IPI_DELIVERY_PENDING: u32 = 1 << 12;
+
+// o vetor do spurious precisa ter os 4 bits de baixo em 1 nas cpus antigas
+pub const SPURIOUS_VECTOR: u8 = 0xFF;
+
+// endereco virtual dos registradores; 0 -> apic nao foi ligado
+static BASE: AtomicU64 = AtomicU64::new(0);
+
+pub fn is_supported() -> bool {
+    crate::cpu::features().apic
+}
+
+pub fn is_enabled() -> bool {
+    BASE.load(Ordering::Relaxed) != 0
+}
+
+// liga o apic da cpu atual; retorna false se a cpu nao tem apic
+// o 8259 tem que ser mascarado por quem chama (se nao as irqs dele continuam chegando pelo LINT0)
+pub fn init() -> bool {
+    if !is_supported() {
+        return false;
+    }
+    let value = match msr::apic_base() {
+        Some(value) => value,
+        None => return false,
+    };
+    unsafe { msr::set_apic_base(value | APIC_BASE_ENABLE) };
+    let physical = PhysAddr::new(value & APIC_BASE_MASK);
+    if let Err(error) = memory::map_physical(physical, 4096) {
+        log::warn!("apic: nao deu pra mapear {:#x}: {}", physical.as_u64(), error);
+        return false;
+    }
+    BASE.store(memory::phys_to_virt(physical).as_u64(), Ordering::Relaxed);
+
+    unsafe {
+        write(TASK_PRIORITY, 0); // aceita todas as prioridades
+        // LINT0/LINT1 (ExtINT do 8259 e NMI) e o erro ficam mascarados ate alguem precisar
+        write(LVT_LINT0, LVT_MASKED);
+        write(LVT_LINT1, LVT_MASKED);
+        write(LVT_ERROR, LVT_MASKED);
+        write(LVT_TIMER, LVT_MASKED);
+        write(SPURIOUS, SOFTWARE_ENABLE | SPURIOUS_VECTOR as u32);
+        // o ESR só atualiza depois de uma escrita
+        write(ERROR_STATUS, 0);
+        write(ERROR_STATUS, 0);
+        write(EOI, 0);
+    }
+    true
+}
+
+// unsafe: só depois do init(), e `register` tem que ser um offset valido
+pub unsafe fn read(register: usize) -> u32 {
+    let base = BASE.load(Ordering::Relaxed);
+    core::ptr::read_volatile((base as usize + register) as *const u32)
+}
+
+pub unsafe fn write(register: usize, value: u32) {
+    let base = BASE.load(Ordering::Relaxed);
+    core::ptr::write_volatile((base as usize + register) as *mut u32, value);
+}
+
+// sem lock: chamado no fim dos handlers de irq
+pub fn end_of_interrupt() {
+    if is_enabled() {
+        unsafe { write(EOI, 0) };
+    }
+}
+
+pub fn id() -> u32 {
+    if !is_enabled() {
+        return 0;
+    }
+    unsafe { read(ID) >> 24 }
+}
+
+#[allow(dead_code)]
+pub fn version() -> u32 {
+    if !is_enabled() {
+        return 0;
+    }
+    unsafe { read(VERSION) & 0xff }
+}
+
+// manda uma interrupcao entre processadores (ex: INIT/SIPI pra acordar as outras cpus)
+// `command` = tipo | vetor; espera o apic aceitar antes de voltar
+pub fn send_ipi(apic_id: u32, command: u32) {
+    if !is_enabled() {
+        return;
+    }
+    unsafe {
+        write(ICR_HIGH, apic_id << 24);
+        // escrever a parte baixa é o que envia
+        write(ICR_LOW, command);
+        while read(ICR_LOW) & IPI_DELIVERY_PENDING != 0 {
+            crate::cpu::spin_hint();
+        }
+    }
+}
@@ -0,0 +1,101 @@
+// timer do local apic: um por cpu, mas a frequencia depende da maquina
+// -> no boot conta quantos ciclos ele anda em 10ms do PIT e guarda (calibrate)
+use crate::apic::{self, LVT_TIMER};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+const INITIAL_COUNT: usize = 0x380;
+const CURRENT_COUNT: usize = 0x390;
+const DIVIDE_CONFIG: usize = 0x3E0;
+
+const DIVIDE_BY_16: u32 = 0b0011;
+const LVT_MASKED: u32 = 1 << 16;
+const PERIODIC: u32 = 1 << 17;
+
+const CALIBRATION_MS: u64 = 10;
+
+// vetor da interrupcao do timer do apic
+pub const VECTOR: u8 = 0xF0;
+
+// contagens (com divisor 16) por milissegundo; 0 -> ainda nao calibrado
+static TICKS_PER_MS: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    #[allow(dead_code)]
+    OneShot,
+    Periodic,
+}
+
+// tem que rodar com interrupcoes desligadas (senao a espera do PIT atrasa e a conta sai errada)
+// retorna as contagens por ms, 0 se o apic nao esta ligado
+pub fn calibrate() -> u32 {
+    if !apic::is_enabled() {
+        return 0;
+    }
+    unsafe {
+        apic::write(DIVIDE_CONFIG, DIVIDE_BY_16);
+        apic::write(LVT_TIMER, LVT_MASKED);
+        apic::write(INITIAL_COUNT, u32::MAX);
+    }
+    crate::time::pit_wait_ms(CALIBRATION_MS);
+    let elapsed = u32::MAX - unsafe { apic::read(CURRENT_COUNT) };
+    unsafe { apic::write(INITIAL_COUNT, 0) };
+
+    let per_ms = elapsed / CALIBRATION_MS as u32;
+    TICKS_PER_MS.store(per_ms, Ordering::Relaxed);
+    per_ms
+}
+
+pub fn ticks_per_ms() -> u32 {
+    TICKS_PER_MS.load(Ordering::Relaxed)
+}
+
+// Periodic: interrupcao a cada `us`; OneShot: uma vez depois de `us`
+// retorna false sem calibracao
+pub fn start(mode: Mode, us: u64) -> bool {
+    let per_ms = ticks_per_ms() as u64;
+    if per_ms == 0 {
+        return false;
+    }
+    let count = (per_ms * us / 1000).clamp(1, u32::MAX as u64) as u32;
+    let lvt = match mode {
+        Mode::OneShot => VECTOR as u32,
+        Mode::Periodic => VECTOR as u32 | PERIODIC,
+    };
+    unsafe {
+        apic::write(DIVIDE_CONFIG, DIVIDE_BY_16);
+        apic::write(LVT_TIMER, lvt);
+        // escrever o initial count é o que dispara a contagem
+        apic::write(INITIAL_COUNT, count);
+    }
+    true
+}
+
+// `hz` interrupcoes por segundo
+pub fn start_periodic(hz: u64) -> bool {
+    start(Mode::Periodic, 1_000_000 / hz.max(1))
+}
+
+#[allow(dead_code)]
+pub fn one_shot(us: u64) -> bool {
+    start(Mode::OneShot, us)
+}
+
+#[allow(dead_code)]
+pub fn stop() {
+    if apic::is_enabled() {
+        unsafe {
+            apic::write(LVT_TIMER, LVT_MASKED);
+            apic::write(INITIAL_COUNT, 0);
+        }
+    }
+}
+
+// quanto falta pro proximo disparo, em contagens
+#[allow(dead_code)]
+pub fn remaining() -> u32 {
+    if !apic::is_enabled() {
+        return 0;
+    }
+    unsafe { apic::read(CURRENT_COUNT) }
+}
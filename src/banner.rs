@@ -0,0 +1,41 @@
+// tela de abertura: limpa a tela, desenha o logo e o nome/versao do kernel centralizados
+// depois o cursor fica logo abaixo, pro log do boot continuar dali
+use crate::vga_buffer::{ColorCode, WRITER};
+
+const LOGO: [&str; 5] = [
+    r"                 _                   ",
+    r" _ __ _   _  ___| |_      ___  ___   ",
+    r"| '__| | | |/ __| __|___ / _ \/ __|  ",
+    r"| |  | |_| |\__ \ ||____| (_) \__ \  ",
+    r"|_|   \__,_||___/\__|    \___/|___/  ",
+];
+
+const NAME: &str = env!("CARGO_PKG_NAME");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// linha em branco em cima do logo
+const TOP_MARGIN: usize = 2;
+
+pub fn show_banner(logo_color: ColorCode, text_color: ColorCode) {
+    let mut writer = WRITER.lock();
+    writer.clear_screen();
+    let (first_row, _) = writer.cursor();
+    let width = writer.width();
+    let center = |len: usize| width.saturating_sub(len) / 2;
+
+    let mut row = first_row + TOP_MARGIN;
+    for line in LOGO.iter() {
+        writer.write_at(row, center(line.len()), line, Some(logo_color));
+        row += 1;
+    }
+
+    // "os_project v0.1.0"
+    row += 1;
+    let text_len = NAME.len() + 2 + VERSION.len();
+    let col = center(text_len);
+    writer.write_at(row, col, NAME, Some(text_color));
+    writer.write_at(row, col + NAME.len(), " v", Some(text_color));
+    writer.write_at(row, col + NAME.len() + 2, VERSION, Some(text_color));
+
+    writer.set_cursor(row + 2, 0);
+}
@@ -0,0 +1,21 @@
+// linha de comando do kernel: o bootloader 0.9 nao passa nenhuma, entao ela vem da compilacao
+// ex: KERNEL_CMDLINE="kaslr=off" cargo run -> palavras "chave=valor" (ou só "chave") separadas por espaco
+const CMDLINE: &str = match option_env!("KERNEL_CMDLINE") {
+    Some(line) => line,
+    None => "",
+};
+
+#[allow(dead_code)]
+pub fn raw() -> &'static str {
+    CMDLINE
+}
+
+// valor da chave; Some("") se ela aparece sem "=". a ultima vence
+pub fn get(key: &str) -> Option<&'static str> {
+    CMDLINE
+        .split_whitespace()
+        .rev()
+        .map(|word| word.split_once('=').unwrap_or((word, "")))
+        .find(|&(name, _)| name == key)
+        .map(|(_, value)| value)
+}
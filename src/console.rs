@@ -0,0 +1,303 @@
+// interface comum pros lugares onde da pra escrever texto (vga, serial, framebuffer...)
+// o println! manda a mesma saida pra todos os consoles registrados
+use crate::framebuffer::FramebufferConsole;
+use crate::vga_buffer::{Color, Writer, WRITER};
+use core::fmt;
+use spin::Mutex;
+
+pub trait Console {
+    fn write_str(&mut self, s: &str);
+    fn set_color(&mut self, foreground: Color, background: Color);
+    // (foreground, background)
+    fn color(&self) -> (Color, Color);
+    #[allow(dead_code)]
+    fn clear(&mut self);
+    // (colunas, linhas) -> console sem tamanho (serial) retorna (0, 0)
+    #[allow(dead_code)]
+    fn dimensions(&self) -> (usize, usize);
+}
+
+impl Console for Writer {
+    fn write_str(&mut self, s: &str) {
+        self.write_string(s);
+    }
+
+    fn set_color(&mut self, foreground: Color, background: Color) {
+        Writer::set_color(self, foreground, background);
+    }
+
+    fn color(&self) -> (Color, Color) {
+        Writer::color(self)
+    }
+
+    fn clear(&mut self) {
+        self.clear_screen();
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width(), self.text_rows())
+    }
+}
+
+impl Console for FramebufferConsole {
+    fn write_str(&mut self, s: &str) {
+        self.write_string(s);
+    }
+
+    fn set_color(&mut self, foreground: Color, background: Color) {
+        FramebufferConsole::set_color(self, foreground, background);
+    }
+
+    fn color(&self) -> (Color, Color) {
+        FramebufferConsole::color(self)
+    }
+
+    fn clear(&mut self) {
+        FramebufferConsole::clear(self);
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.columns(), self.rows())
+    }
+}
+
+// pro caso do console que ainda nao foi inicializado (ex: framebuffer::CONSOLE) -> nao faz nada
+impl<C: Console> Console for Option<C> {
+    fn write_str(&mut self, s: &str) {
+        if let Some(console) = self {
+            console.write_str(s);
+        }
+    }
+
+    fn set_color(&mut self, foreground: Color, background: Color) {
+        if let Some(console) = self {
+            console.set_color(foreground, background);
+        }
+    }
+
+    fn color(&self) -> (Color, Color) {
+        self.as_ref()
+            .map_or((Color::Yellow, Color::Black), |console| console.color())
+    }
+
+    fn clear(&mut self) {
+        if let Some(console) = self {
+            console.clear();
+        }
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        self.as_ref().map_or((0, 0), |console| console.dimensions())
+    }
+}
+
+// cada console fica no proprio Mutex (o mesmo que o resto do kernel usa), aqui só guarda a referencia
+// array fixo pq ainda nao tem heap
+pub type ConsoleRef = &'static Mutex<dyn Console + Send>;
+
+const MAX_CONSOLES: usize = 4;
+
+static CONSOLES: Mutex<[Option<ConsoleRef>; MAX_CONSOLES]> = Mutex::new([None; MAX_CONSOLES]);
+
+// retorna false se nao tem mais espaco; registrar o mesmo console duas vezes nao duplica
+pub fn register(console: ConsoleRef) -> bool {
+    let mut consoles = CONSOLES.lock();
+    if consoles.iter().flatten().any(|c| same(c, console)) {
+        return true;
+    }
+    match consoles.iter_mut().find(|slot| slot.is_none()) {
+        Some(slot) => {
+            *slot = Some(console);
+            true
+        }
+        None => false,
+    }
+}
+
+#[allow(dead_code)]
+pub fn unregister(console: ConsoleRef) {
+    for slot in CONSOLES.lock().iter_mut() {
+        if matches!(slot, Some(c) if same(c, console)) {
+            *slot = None;
+        }
+    }
+}
+
+pub fn is_registered(console: ConsoleRef) -> bool {
+    CONSOLES.lock().iter().flatten().any(|c| same(c, console))
+}
+
+// o mirror do vga, se o vga esta recebendo o println! (senao a copia nao acontece)
+fn mirror() -> Option<ConsoleRef> {
+    if is_registered(&*WRITER) {
+        WRITER.lock().mirror()
+    } else {
+        None
+    }
+}
+
+// o println! chega nesse console? (registrado, ou é a copia do vga)
+pub fn reaches(console: ConsoleRef) -> bool {
+    is_registered(console) || mirror().is_some_and(|mirror| same(mirror, console))
+}
+
+// compara só o endereco (o ponteiro da vtable pode mudar entre codegen units)
+fn same(a: &Mutex<dyn Console + Send>, b: &Mutex<dyn Console + Send>) -> bool {
+    core::ptr::addr_eq(a, b)
+}
+
+// chama f pra cada console registrado
+// o que é o mirror do vga fica de fora: já recebe pelo vga
+pub fn for_each<F: FnMut(&mut dyn Console)>(mut f: F) {
+    let mirror = mirror();
+    let consoles = *CONSOLES.lock();
+    for console in consoles.iter().flatten().filter(|&&c| !mirror.is_some_and(|mirror| same(mirror, c))) {
+        f(&mut *console.lock());
+    }
+}
+
+#[allow(dead_code)]
+pub fn set_color(foreground: Color, background: Color) {
+    for_each(|console| console.set_color(foreground, background));
+}
+
+#[allow(dead_code)]
+pub fn clear() {
+    for_each(|console| console.clear());
+}
+
+// saida de erro: eprint!/eprintln! vao pros consoles normais com ERROR_COLOR
+// e tambem pro log de erro (ex: serial), mesmo que ele nao esteja recebendo o println! normal
+static ERROR_COLOR: Mutex<Color> = Mutex::new(Color::LightRed);
+#[allow(dead_code)]
+static ERROR_LOG: Mutex<Option<ConsoleRef>> = Mutex::new(None);
+
+pub fn set_error_color(color: Color) {
+    *ERROR_COLOR.lock() = color;
+}
+
+#[allow(dead_code)]
+pub fn error_color() -> Color {
+    *ERROR_COLOR.lock()
+}
+
+// None desliga
+#[allow(dead_code)]
+pub fn set_error_log(console: Option<ConsoleRef>) {
+    *ERROR_LOG.lock() = console;
+}
+
+// registra o modo texto do vga, que é o console padrao, e a COM1 se ela respondeu (o println! sai nas duas;
+// depois do serial::init ela recebe pelo mirror do vga, que pega tambem o que é escrito direto no WRITER)
+pub fn init() {
+    register(&*WRITER);
+    if crate::serial::SERIAL1.lock().is_present() {
+        register(&*crate::serial::SERIAL1);
+    }
+}
+
+// adaptador pra usar write_fmt em cima de um &mut dyn Console
+struct Adapter<'a>(&'a mut dyn Console);
+
+impl fmt::Write for Adapter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s);
+        Ok(())
+    }
+}
+
+// mesmos macros da std, mas chamando o _print daqui
+// macro_export coloca o macro na raiz do crate -> usa crate::println! e nao crate::console::println!
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => ($crate::console::_print(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! println {
+    () => ($crate::print!("\n"));
+    ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
+}
+
+// igual o print!, mas com outra cor só pra esse texto (background None -> mantem o atual)
+// ex: println_colored!(Color::Green, "ok"); println_colored!(Color::White, Color::Red, "falhou: {}", e);
+#[macro_export]
+macro_rules! print_colored {
+    ($fg:expr, $fmt:literal $($arg:tt)*) => (
+        $crate::console::_print_colored($fg, None, format_args!($fmt $($arg)*))
+    );
+    ($fg:expr, $bg:expr, $($arg:tt)*) => (
+        $crate::console::_print_colored($fg, Some($bg), format_args!($($arg)*))
+    );
+}
+
+#[macro_export]
+macro_rules! println_colored {
+    ($fg:expr, $fmt:literal $($arg:tt)*) => (
+        $crate::print_colored!($fg, "{}\n", format_args!($fmt $($arg)*))
+    );
+    ($fg:expr, $bg:expr, $($arg:tt)*) => (
+        $crate::print_colored!($fg, $bg, "{}\n", format_args!($($arg)*))
+    );
+}
+
+// pros caminhos de erro/aviso do kernel -> aparecem em destaque
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => ($crate::console::_eprint(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! eprintln {
+    () => ($crate::eprint!("\n"));
+    ($($arg:tt)*) => ($crate::eprint!("{}\n", format_args!($($arg)*)));
+}
+
+// precisa ser pub pros macros conseguirem chamar de fora do modulo, mas é detalhe de implementacao
+// enquanto ninguem chamou o init vai direto pro vga, pra nao perder nada do comeco do boot
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use core::fmt::Write;
+    // um print dentro de uma interrupcao nao pode achar o WRITER pego pelo codigo interrompido
+    let _irq = crate::cpu::IrqGuard::new();
+    if CONSOLES.lock().iter().all(|c| c.is_none()) {
+        WRITER.lock().write_fmt(args).unwrap();
+        return;
+    }
+    for_each(|console| {
+        let _ = Adapter(console).write_fmt(args);
+    });
+}
+
+// troca a cor, escreve e volta a cor anterior com o lock do console pego o tempo todo
+// -> um print de outro lugar nao sai com a cor errada no meio
+#[doc(hidden)]
+pub fn _print_colored(foreground: Color, background: Option<Color>, args: fmt::Arguments) {
+    use core::fmt::Write;
+    let write = |console: &mut dyn Console| {
+        let previous = console.color();
+        console.set_color(foreground, background.unwrap_or(previous.1));
+        let _ = Adapter(console).write_fmt(args);
+        console.set_color(previous.0, previous.1);
+    };
+    let _irq = crate::cpu::IrqGuard::new();
+    if CONSOLES.lock().iter().all(|c| c.is_none()) {
+        write(&mut *WRITER.lock());
+        return;
+    }
+    for_each(write);
+}
+
+#[doc(hidden)]
+pub fn _eprint(args: fmt::Arguments) {
+    use core::fmt::Write;
+    _print_colored(error_color(), None, args);
+    let log = *ERROR_LOG.lock();
+    if let Some(log) = log {
+        // se o log tambem é um console normal (ou o mirror do vga) ja recebeu pelo _print_colored
+        if reaches(log) {
+            return;
+        }
+        let _ = Adapter(&mut *log.lock()).write_fmt(args);
+    }
+}
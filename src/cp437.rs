@@ -0,0 +1,72 @@
+// traducao de unicode pro code page 437 (o conjunto de caracteres da fonte da BIOS)
+// o VGA tem glifo pra acentos, box-drawing, graus, etc, só que com codigos diferentes do unicode
+// caracteres ascii printaveis sao iguais, entao só os de fora do ascii precisam da tabela
+
+// ordenada pelo char -> busca binaria
+const TABLE: [(char, u8); 167] = [
+    ('\u{00a0}', 0xff), ('¡', 0xad), ('¢', 0x9b), ('£', 0x9c),
+    ('¥', 0x9d), ('§', 0x15), ('ª', 0xa6), ('«', 0xae),
+    ('¬', 0xaa), ('°', 0xf8), ('±', 0xf1), ('²', 0xfd),
+    ('µ', 0xe6), ('¶', 0x14), ('·', 0xfa), ('º', 0xa7),
+    ('»', 0xaf), ('¼', 0xac), ('½', 0xab), ('¿', 0xa8),
+    ('Ä', 0x8e), ('Å', 0x8f), ('Æ', 0x92), ('Ç', 0x80),
+    ('É', 0x90), ('Ñ', 0xa5), ('Ö', 0x99), ('Ü', 0x9a),
+    ('ß', 0xe1), ('à', 0x85), ('á', 0xa0), ('â', 0x83),
+    ('ä', 0x84), ('å', 0x86), ('æ', 0x91), ('ç', 0x87),
+    ('è', 0x8a), ('é', 0x82), ('ê', 0x88), ('ë', 0x89),
+    ('ì', 0x8d), ('í', 0xa1), ('î', 0x8c), ('ï', 0x8b),
+    ('ñ', 0xa4), ('ò', 0x95), ('ó', 0xa2), ('ô', 0x93),
+    ('ö', 0x94), ('÷', 0xf6), ('ù', 0x97), ('ú', 0xa3),
+    ('û', 0x96), ('ü', 0x81), ('ÿ', 0x98), ('ƒ', 0x9f),
+    ('Γ', 0xe2), ('Θ', 0xe9), ('Σ', 0xe4), ('Φ', 0xe8),
+    ('Ω', 0xea), ('α', 0xe0), ('β', 0xe1), ('δ', 0xeb),
+    ('ε', 0xee), ('μ', 0xe6), ('π', 0xe3), ('σ', 0xe5),
+    ('τ', 0xe7), ('φ', 0xed), ('•', 0x07), ('‼', 0x13),
+    ('ⁿ', 0xfc), ('₧', 0x9e), ('←', 0x1b), ('↑', 0x18),
+    ('→', 0x1a), ('↓', 0x19), ('↔', 0x1d), ('↕', 0x12),
+    ('↨', 0x17), ('∅', 0xed), ('∈', 0xee), ('∎', 0xfe),
+    ('∙', 0xf9), ('√', 0xfb), ('∞', 0xec), ('∟', 0x1c),
+    ('∩', 0xef), ('≈', 0xf7), ('≡', 0xf0), ('≤', 0xf3),
+    ('≥', 0xf2), ('⌂', 0x7f), ('⌐', 0xa9), ('⌠', 0xf4),
+    ('⌡', 0xf5), ('─', 0xc4), ('│', 0xb3), ('┌', 0xda),
+    ('┐', 0xbf), ('└', 0xc0), ('┘', 0xd9), ('├', 0xc3),
+    ('┤', 0xb4), ('┬', 0xc2), ('┴', 0xc1), ('┼', 0xc5),
+    ('═', 0xcd), ('║', 0xba), ('╒', 0xd5), ('╓', 0xd6),
+    ('╔', 0xc9), ('╕', 0xb8), ('╖', 0xb7), ('╗', 0xbb),
+    ('╘', 0xd4), ('╙', 0xd3), ('╚', 0xc8), ('╛', 0xbe),
+    ('╜', 0xbd), ('╝', 0xbc), ('╞', 0xc6), ('╟', 0xc7),
+    ('╠', 0xcc), ('╡', 0xb5), ('╢', 0xb6), ('╣', 0xb9),
+    ('╤', 0xd1), ('╥', 0xd2), ('╦', 0xcb), ('╧', 0xcf),
+    ('╨', 0xd0), ('╩', 0xca), ('╪', 0xd8), ('╫', 0xd7),
+    ('╬', 0xce), ('▀', 0xdf), ('▄', 0xdc), ('█', 0xdb),
+    ('▌', 0xdd), ('▐', 0xde), ('░', 0xb0), ('▒', 0xb1),
+    ('▓', 0xb2), ('■', 0xfe), ('▪', 0xfe), ('▬', 0x16),
+    ('▲', 0x1e), ('►', 0x10), ('▼', 0x1f), ('◄', 0x11),
+    ('○', 0x09), ('◘', 0x08), ('◙', 0x0a), ('☺', 0x01),
+    ('☻', 0x02), ('☼', 0x0f), ('♀', 0x0c), ('♂', 0x0b),
+    ('♠', 0x06), ('♣', 0x05), ('♥', 0x03), ('♦', 0x04),
+    ('♪', 0x0d), ('♫', 0x0e), ('✓', 0xfb),
+];
+
+// None -> nao existe glifo pra esse caractere
+pub fn from_char(c: char) -> Option<u8> {
+    if (' '..='~').contains(&c) {
+        return Some(c as u8);
+    }
+    TABLE
+        .binary_search_by_key(&c, |&(unicode, _)| unicode)
+        .ok()
+        .map(|index| TABLE[index].1)
+}
+
+// o caminho contrario (dump da tela em texto): byte da tela -> char unicode
+// quando dois chars tem o mesmo glifo (ß e β) fica o primeiro da tabela; sem equivalente -> None
+pub fn to_char(byte: u8) -> Option<char> {
+    if (0x20..=0x7e).contains(&byte) {
+        return Some(byte as char);
+    }
+    TABLE
+        .iter()
+        .find(|&&(_, code)| code == byte)
+        .map(|&(unicode, _)| unicode)
+}
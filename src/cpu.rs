@@ -0,0 +1,68 @@
+// coisas da cpu que nao sao de um dispositivo: esperar, parar
+use x86_64::instructions::{hlt, interrupts};
+
+#[allow(dead_code)]
+pub mod features;
+#[allow(dead_code)]
+pub mod fpu;
+#[allow(dead_code)]
+pub mod msr;
+
+pub use features::features;
+
+// loop principal: roda `idle` e dorme ate a proxima interrupcao
+// um evento que chega entre o `idle` e o hlt espera no maximo o proximo tick do timer
+// com as interrupcoes desligadas o hlt nao volta mais (é o que o panic quer)
+pub fn hlt_loop<F: FnMut()>(mut idle: F) -> ! {
+    loop {
+        idle();
+        hlt();
+    }
+}
+
+// desliga as interrupcoes enquanto existir e, no drop, volta o IF como estava
+// (aninhar funciona: o de dentro ve o IF já desligado e nao religa na saida)
+// ex: pegar um lock que um handler tambem pega -> com o guard o handler nao entra no meio e nao trava
+pub struct IrqGuard {
+    was_enabled: bool,
+}
+
+impl IrqGuard {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> IrqGuard {
+        let was_enabled = interrupts::are_enabled();
+        if was_enabled {
+            interrupts::disable();
+        }
+        IrqGuard { was_enabled }
+    }
+}
+
+impl Drop for IrqGuard {
+    fn drop(&mut self) {
+        if self.was_enabled {
+            interrupts::enable();
+        }
+    }
+}
+
+pub fn without_interrupts<R, F: FnOnce() -> R>(f: F) -> R {
+    let _guard = IrqGuard::new();
+    f()
+}
+
+// pra quem espera um dispositivo: dorme se alguma interrupcao pode acordar, senao só gira
+#[allow(dead_code)]
+pub fn wait_for_interrupt() {
+    if interrupts::are_enabled() {
+        hlt();
+    } else {
+        spin_hint();
+    }
+}
+
+// dentro de um loop de espera ocupada (pause: economiza energia e nao atrapalha a outra thread do core)
+#[inline]
+pub fn spin_hint() {
+    core::hint::spin_loop();
+}
@@ -0,0 +1,171 @@
+// o que a cpu sabe fazer, lido do cpuid uma vez só (o cpuid é lento e sai da vm no qemu/kvm)
+use core::arch::x86_64::{__cpuid, __cpuid_count};
+use core::fmt;
+use spin::Once;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Features {
+    pub vendor: [u8; 12], // "GenuineIntel", "AuthenticAMD", "TCGTCGTCGTCG" (qemu sem kvm)...
+    pub max_leaf: u32,
+    pub max_extended_leaf: u32,
+    // cpuid 1 edx
+    pub fpu: bool,
+    pub pse: bool, // paginas de 2 MiB/4 MiB
+    pub tsc: bool,
+    pub msr: bool,
+    pub mce: bool,
+    pub apic: bool,
+    pub mca: bool,
+    pub pat: bool,
+    pub fxsr: bool,
+    pub sse: bool,
+    pub sse2: bool,
+    // cpuid 1 ecx
+    pub sse3: bool,
+    pub ssse3: bool,
+    pub sse4_1: bool,
+    pub sse4_2: bool,
+    pub pcid: bool,
+    pub x2apic: bool,
+    pub tsc_deadline: bool,
+    pub xsave: bool,
+    pub avx: bool,
+    pub rdrand: bool,
+    pub hypervisor: bool, // rodando dentro de uma vm
+    // cpuid 7 ebx
+    pub smep: bool,
+    pub avx2: bool,
+    pub rdseed: bool,
+    pub smap: bool,
+    // cpuid 0x80000001 edx
+    pub syscall: bool,
+    pub nx: bool,
+    pub page_1gb: bool,
+    pub rdtscp: bool,
+    // cpuid 0x80000007 edx
+    pub invariant_tsc: bool, // tsc anda na mesma velocidade em qualquer estado de energia
+}
+
+fn bit(value: u32, n: u32) -> bool {
+    value & (1 << n) != 0
+}
+
+fn detect() -> Features {
+    let mut features = Features::default();
+    let leaf0 = __cpuid(0);
+    features.max_leaf = leaf0.eax;
+    // a ordem da string é ebx, edx, ecx
+    features.vendor[0..4].copy_from_slice(&leaf0.ebx.to_le_bytes());
+    features.vendor[4..8].copy_from_slice(&leaf0.edx.to_le_bytes());
+    features.vendor[8..12].copy_from_slice(&leaf0.ecx.to_le_bytes());
+
+    if features.max_leaf >= 1 {
+        let leaf1 = __cpuid(1);
+        let (ecx, edx) = (leaf1.ecx, leaf1.edx);
+        features.fpu = bit(edx, 0);
+        features.pse = bit(edx, 3);
+        features.tsc = bit(edx, 4);
+        features.msr = bit(edx, 5);
+        features.mce = bit(edx, 7);
+        features.apic = bit(edx, 9);
+        features.mca = bit(edx, 14);
+        features.pat = bit(edx, 16);
+        features.fxsr = bit(edx, 24);
+        features.sse = bit(edx, 25);
+        features.sse2 = bit(edx, 26);
+        features.sse3 = bit(ecx, 0);
+        features.ssse3 = bit(ecx, 9);
+        features.sse4_1 = bit(ecx, 19);
+        features.sse4_2 = bit(ecx, 20);
+        features.pcid = bit(ecx, 17);
+        features.x2apic = bit(ecx, 21);
+        features.tsc_deadline = bit(ecx, 24);
+        features.xsave = bit(ecx, 26);
+        features.avx = bit(ecx, 28);
+        features.rdrand = bit(ecx, 30);
+        features.hypervisor = bit(ecx, 31);
+    }
+    if features.max_leaf >= 7 {
+        let ebx = __cpuid_count(7, 0).ebx;
+        features.smep = bit(ebx, 7);
+        features.avx2 = bit(ebx, 5);
+        features.rdseed = bit(ebx, 18);
+        features.smap = bit(ebx, 20);
+    }
+
+    features.max_extended_leaf = __cpuid(0x8000_0000).eax;
+    if features.max_extended_leaf >= 0x8000_0001 {
+        let edx = __cpuid(0x8000_0001).edx;
+        features.syscall = bit(edx, 11);
+        features.nx = bit(edx, 20);
+        features.page_1gb = bit(edx, 26);
+        features.rdtscp = bit(edx, 27);
+    }
+    if features.max_extended_leaf >= 0x8000_0007 {
+        features.invariant_tsc = bit(__cpuid(0x8000_0007).edx, 8);
+    }
+    features
+}
+
+static FEATURES: Once<Features> = Once::new();
+
+// a primeira chamada faz a deteccao (o kernel_main chama cedo)
+pub fn features() -> &'static Features {
+    FEATURES.call_once(detect)
+}
+
+impl Features {
+    pub fn vendor_str(&self) -> &str {
+        core::str::from_utf8(&self.vendor).unwrap_or("?")
+    }
+
+    // (nome, tem) pra listar
+    pub fn flags(&self) -> [(&'static str, bool); 30] {
+        [
+            ("fpu", self.fpu),
+            ("pse", self.pse),
+            ("tsc", self.tsc),
+            ("msr", self.msr),
+            ("mce", self.mce),
+            ("apic", self.apic),
+            ("mca", self.mca),
+            ("pat", self.pat),
+            ("fxsr", self.fxsr),
+            ("sse", self.sse),
+            ("sse2", self.sse2),
+            ("sse3", self.sse3),
+            ("ssse3", self.ssse3),
+            ("sse4.1", self.sse4_1),
+            ("sse4.2", self.sse4_2),
+            ("pcid", self.pcid),
+            ("x2apic", self.x2apic),
+            ("tsc-deadline", self.tsc_deadline),
+            ("xsave", self.xsave),
+            ("avx", self.avx),
+            ("rdrand", self.rdrand),
+            ("hypervisor", self.hypervisor),
+            ("smep", self.smep),
+            ("avx2", self.avx2),
+            ("rdseed", self.rdseed),
+            ("smap", self.smap),
+            ("syscall", self.syscall),
+            ("nx", self.nx),
+            ("1gb-pages", self.page_1gb),
+            ("rdtscp", self.rdtscp),
+        ]
+    }
+}
+
+// "GenuineIntel: fpu tsc msr ..."
+impl fmt::Display for Features {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.vendor_str())?;
+        f.write_str(":")?;
+        for (name, present) in self.flags() {
+            if present {
+                write!(f, " {}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
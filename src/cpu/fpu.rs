@@ -0,0 +1,77 @@
+// x87 + SSE: o kernel é compilado com soft-float (config.json), entao ele mesmo nao usa esses registradores,
+// mas o codigo das tarefas pode usar -> liga direito no boot e guarda/restaura o estado na troca de contexto
+use core::arch::asm;
+use x86_64::registers::control::{Cr0, Cr0Flags, Cr4, Cr4Flags};
+
+// valores do reset: todas as excecoes mascaradas, precisao dupla estendida / arredondamento pro mais proximo
+const DEFAULT_FCW: u16 = 0x037F;
+const DEFAULT_MXCSR: u32 = 0x1F80;
+
+// offsets dentro da area do fxsave
+const FCW_OFFSET: usize = 0;
+const MXCSR_OFFSET: usize = 24;
+
+// liga x87 e SSE na cpu atual; false se ela nao tem fxsave/sse2
+pub fn init() -> bool {
+    let features = super::features();
+    if !(features.fpu && features.fxsr && features.sse && features.sse2) {
+        return false;
+    }
+    unsafe {
+        Cr0::update(|flags| {
+            flags.remove(Cr0Flags::EMULATE_COPROCESSOR | Cr0Flags::TASK_SWITCHED);
+            // MP: o wait respeita o TS; NE: erro do x87 vira #MF em vez do irq 13 antigo
+            flags.insert(Cr0Flags::MONITOR_COPROCESSOR | Cr0Flags::NUMERIC_ERROR);
+        });
+        // OSFXSR: libera fxsave/fxrstor e as instrucoes SSE; OSXMMEXCPT: erro de SSE vira #XM
+        Cr4::update(|flags| flags.insert(Cr4Flags::OSFXSR | Cr4Flags::OSXMMEXCPT_ENABLE));
+        asm!("fninit", options(nomem, nostack));
+        let mxcsr = DEFAULT_MXCSR;
+        asm!("ldmxcsr [{}]", in(reg) &mxcsr, options(nostack, readonly));
+    }
+    true
+}
+
+// registradores x87/MMX/SSE de uma tarefa (o formato do fxsave: 512 bytes alinhados em 16)
+// a troca de contexto faz `saindo.save()` e `entrando.restore()`
+#[derive(Clone)]
+#[repr(C, align(16))]
+pub struct State([u8; 512]);
+
+impl State {
+    // estado de uma tarefa nova (igual ao depois do fninit)
+    pub const fn new() -> State {
+        let mut bytes = [0u8; 512];
+        let fcw = DEFAULT_FCW.to_le_bytes();
+        bytes[FCW_OFFSET] = fcw[0];
+        bytes[FCW_OFFSET + 1] = fcw[1];
+        let mxcsr = DEFAULT_MXCSR.to_le_bytes();
+        let mut i = 0;
+        while i < 4 {
+            bytes[MXCSR_OFFSET + i] = mxcsr[i];
+            i += 1;
+        }
+        State(bytes)
+    }
+
+    // guarda o estado atual da cpu aqui
+    pub fn save(&mut self) {
+        unsafe { asm!("fxsave64 [{}]", in(reg) self.0.as_mut_ptr(), options(nostack)) };
+    }
+
+    // carrega esse estado na cpu (só sai daqui um State valido: new() ou save())
+    pub fn restore(&self) {
+        unsafe { asm!("fxrstor64 [{}]", in(reg) self.0.as_ptr(), options(nostack, readonly)) };
+    }
+
+    pub fn mxcsr(&self) -> u32 {
+        let b = &self.0[MXCSR_OFFSET..MXCSR_OFFSET + 4];
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+    }
+}
+
+impl Default for State {
+    fn default() -> State {
+        State::new()
+    }
+}
@@ -0,0 +1,139 @@
+// wrappers dos msrs que o kernel usa, pra ninguem precisar de rdmsr/wrmsr solto
+// ler ou escrever um msr que a cpu nao tem da #GP -> os que dependem de feature checam o cpuid antes
+// (EFER e FS/GS base sempre existem em modo 64 bits, nao precisa)
+use x86_64::registers::model_specific::Msr;
+use x86_64::VirtAddr;
+
+pub const IA32_APIC_BASE: u32 = 0x1B;
+pub const IA32_EFER: u32 = 0xC000_0080;
+pub const IA32_STAR: u32 = 0xC000_0081;
+pub const IA32_LSTAR: u32 = 0xC000_0082;
+pub const IA32_FMASK: u32 = 0xC000_0084;
+pub const IA32_FS_BASE: u32 = 0xC000_0100;
+pub const IA32_GS_BASE: u32 = 0xC000_0101;
+pub const IA32_KERNEL_GS_BASE: u32 = 0xC000_0102; // trocado com o GS_BASE pelo swapgs
+
+// bits do EFER
+pub const EFER_SCE: u64 = 1 << 0; // syscall/sysret
+pub const EFER_LME: u64 = 1 << 8;
+pub const EFER_LMA: u64 = 1 << 10;
+pub const EFER_NXE: u64 = 1 << 11; // bit NX nas tabelas de pagina
+
+pub fn has_apic_base() -> bool {
+    super::features().apic
+}
+
+pub fn has_syscall() -> bool {
+    super::features().syscall
+}
+
+// unsafe: `msr` tem que existir nessa cpu
+pub unsafe fn read(msr: u32) -> u64 {
+    Msr::new(msr).read()
+}
+
+// unsafe: alem de existir, o valor pode mudar o comportamento da cpu inteira
+pub unsafe fn write(msr: u32, value: u64) {
+    Msr::new(msr).write(value)
+}
+
+pub fn efer() -> u64 {
+    unsafe { read(IA32_EFER) }
+}
+
+// unsafe: desligar LME/NXE com o kernel rodando derruba tudo
+pub unsafe fn set_efer(value: u64) {
+    write(IA32_EFER, value)
+}
+
+// liga os bits de `flags` sem mexer nos outros
+pub unsafe fn enable_efer(flags: u64) {
+    set_efer(efer() | flags)
+}
+
+pub fn fs_base() -> VirtAddr {
+    VirtAddr::new_truncate(unsafe { read(IA32_FS_BASE) })
+}
+
+// unsafe: codigo que le pelo fs (tls do usuario, quando tiver) passa a ler de `address`
+pub unsafe fn set_fs_base(address: VirtAddr) {
+    write(IA32_FS_BASE, address.as_u64())
+}
+
+pub fn gs_base() -> VirtAddr {
+    VirtAddr::new_truncate(unsafe { read(IA32_GS_BASE) })
+}
+
+// unsafe: o percpu::current() le gs:[0] -> `address` tem que ser o bloco percpu dessa cpu (ou o do usuario,
+// trocado pelo swapgs) enquanto o kernel roda
+pub unsafe fn set_gs_base(address: VirtAddr) {
+    write(IA32_GS_BASE, address.as_u64())
+}
+
+pub fn kernel_gs_base() -> VirtAddr {
+    VirtAddr::new_truncate(unsafe { read(IA32_KERNEL_GS_BASE) })
+}
+
+// unsafe: é o GS que o proximo swapgs coloca -> na entrada do syscall/int 0x80 tem que ser o bloco percpu
+pub unsafe fn set_kernel_gs_base(address: VirtAddr) {
+    write(IA32_KERNEL_GS_BASE, address.as_u64())
+}
+
+// None -> cpu sem apic
+pub fn apic_base() -> Option<u64> {
+    if !has_apic_base() {
+        return None;
+    }
+    Some(unsafe { read(IA32_APIC_BASE) })
+}
+
+// unsafe: mudar o endereco ou desligar o apic com irqs chegando por ele
+pub unsafe fn set_apic_base(value: u64) -> bool {
+    if !has_apic_base() {
+        return false;
+    }
+    write(IA32_APIC_BASE, value);
+    true
+}
+
+// STAR: bits 32-47 seletor base do syscall (CS do kernel, SS = +8), 48-63 do sysret
+pub fn star() -> Option<(u16, u16)> {
+    if !has_syscall() {
+        return None;
+    }
+    let value = unsafe { read(IA32_STAR) };
+    Some(((value >> 32) as u16, (value >> 48) as u16))
+}
+
+pub unsafe fn set_star(kernel_base: u16, user_base: u16) -> bool {
+    if !has_syscall() {
+        return false;
+    }
+    write(IA32_STAR, (kernel_base as u64) << 32 | (user_base as u64) << 48);
+    true
+}
+
+// LSTAR: pra onde o syscall pula em modo 64 bits
+pub fn lstar() -> Option<VirtAddr> {
+    if !has_syscall() {
+        return None;
+    }
+    Some(VirtAddr::new_truncate(unsafe { read(IA32_LSTAR) }))
+}
+
+pub unsafe fn set_lstar(entry: VirtAddr) -> bool {
+    if !has_syscall() {
+        return false;
+    }
+    write(IA32_LSTAR, entry.as_u64());
+    true
+}
+
+// FMASK: bits do RFLAGS que o syscall zera na entrada (ex: IF pra entrar com interrupcoes desligadas)
+pub unsafe fn set_fmask(mask: u64) -> bool {
+    if !has_syscall() {
+        return false;
+    }
+    write(IA32_FMASK, mask);
+    true
+}
@@ -0,0 +1,90 @@
+// porta 0xE9 (debugcon do qemu/bochs): cada byte escrito sai direto no host, sem configurar nada
+// qemu ... -debugcon stdio (ou -debugcon file:boot.log)
+// funciona antes da serial e do vga; da pra registrar como console ou ligar como sink do logger
+use crate::console::Console;
+use crate::vga_buffer::Color;
+use core::fmt;
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+const PORT: u16 = 0xE9;
+
+// o qemu e o bochs respondem 0xE9 na leitura da porta; sem debugcon vem 0xff
+pub fn present() -> bool {
+    unsafe { Port::<u8>::new(PORT).read() == 0xE9 }
+}
+
+// sem lock nenhum: pode ser chamado de qualquer lugar (panic, interrupcao, boot bem no inicio)
+pub fn write_str(s: &str) {
+    let mut port = Port::<u8>::new(PORT);
+    for byte in s.bytes() {
+        unsafe { port.write(byte) }
+    }
+}
+
+pub struct DebugCon {
+    foreground: Color,
+    background: Color,
+}
+
+pub static DEBUGCON: Mutex<DebugCon> = Mutex::new(DebugCon {
+    foreground: Color::LightGray,
+    background: Color::Black,
+});
+
+impl fmt::Write for DebugCon {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_str(s);
+        Ok(())
+    }
+}
+
+// cor é só guardada: o log do debugcon geralmente vai pra arquivo, escape ansi ali só atrapalha
+impl Console for DebugCon {
+    fn write_str(&mut self, s: &str) {
+        write_str(s);
+    }
+
+    fn set_color(&mut self, foreground: Color, background: Color) {
+        self.foreground = foreground;
+        self.background = background;
+    }
+
+    fn color(&self) -> (Color, Color) {
+        (self.foreground, self.background)
+    }
+
+    fn clear(&mut self) {}
+
+    fn dimensions(&self) -> (usize, usize) {
+        (0, 0)
+    }
+}
+
+#[macro_export]
+macro_rules! debugcon_print {
+    ($($arg:tt)*) => ($crate::debugcon::_print(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! debugcon_println {
+    () => ($crate::debugcon_print!("\n"));
+    ($($arg:tt)*) => ($crate::debugcon_print!("{}\n", format_args!($($arg)*)));
+}
+
+// o macro nao pega o lock do DEBUGCON (a cor nao importa aqui)
+#[allow(dead_code)]
+struct Raw;
+
+impl fmt::Write for Raw {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_str(s);
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use core::fmt::Write;
+    let _ = Raw.write_fmt(args);
+}
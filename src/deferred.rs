@@ -0,0 +1,98 @@
+// trabalho adiado: o handler de interrupcao só le o dispositivo, manda o EOI e marca o que falta fazer
+// o resto (decodificar tecla, rodar comando do shell...) roda depois no loop principal, com interrupcao ligada
+// dois jeitos:
+// - softirq: um bit por fonte, com a funcao registrada antes -> raise() é só um fetch_or, pode ser chamado de qualquer handler
+// - fila de trabalho: funcao + argumento pra rodar uma vez (sem heap ainda -> nada de closure)
+// obs: nada daqui pode ser chamado do NMI (a fila tem lock)
+use crate::cpu;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use spin::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Softirq {
+    Keyboard,
+    Serial,
+}
+
+const SOFTIRQ_COUNT: usize = 2;
+
+const QUEUE_SIZE: usize = 64;
+
+#[derive(Clone, Copy)]
+struct Work {
+    func: fn(usize),
+    data: usize,
+}
+
+static PENDING: AtomicU32 = AtomicU32::new(0);
+type Handlers = [Option<fn()>; SOFTIRQ_COUNT];
+
+static HANDLERS: Mutex<Handlers> = Mutex::new([None; SOFTIRQ_COUNT]);
+// o lock é pego com as interrupcoes desligadas -> um handler na mesma cpu nunca encontra ele preso
+static QUEUE: Mutex<heapless::Deque<Work, QUEUE_SIZE>> = Mutex::new(heapless::Deque::new());
+#[allow(dead_code)]
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+static EXECUTED: AtomicUsize = AtomicUsize::new(0);
+
+// funcao que roda quando alguem der raise(softirq); troca a anterior
+pub fn register(softirq: Softirq, handler: fn()) {
+    cpu::without_interrupts(|| HANDLERS.lock()[softirq as usize] = Some(handler));
+}
+
+// marca a softirq como pendente (varios raise antes do run viram uma chamada só)
+pub fn raise(softirq: Softirq) {
+    PENDING.fetch_or(1 << softirq as u32, Ordering::Release);
+}
+
+// coloca `func(data)` na fila; false se a fila esta cheia (o trabalho é perdido e contado)
+#[allow(dead_code)]
+pub fn schedule(func: fn(usize), data: usize) -> bool {
+    let pushed = cpu::without_interrupts(|| QUEUE.lock().push_back(Work { func, data }).is_ok());
+    if !pushed {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+    pushed
+}
+
+#[allow(dead_code)]
+pub fn has_pending() -> bool {
+    PENDING.load(Ordering::Acquire) != 0 || cpu::without_interrupts(|| !QUEUE.lock().is_empty())
+}
+
+// chamado pelo loop principal: primeiro as softirqs, depois a fila
+// da fila roda no maximo o que coube nela -> um trabalho que se coloca de novo nao prende o loop
+pub fn run() {
+    let pending = PENDING.swap(0, Ordering::AcqRel);
+    if pending != 0 {
+        let handlers = cpu::without_interrupts(|| *HANDLERS.lock());
+        for (index, handler) in handlers.iter().enumerate() {
+            if pending & (1 << index) != 0 {
+                if let Some(handler) = handler {
+                    handler();
+                    EXECUTED.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+    for _ in 0..QUEUE_SIZE {
+        // o lock nao fica preso enquanto o trabalho roda
+        let Some(work) = cpu::without_interrupts(|| QUEUE.lock().pop_front()) else {
+            break;
+        };
+        (work.func)(work.data);
+        EXECUTED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// trabalhos perdidos por fila cheia
+#[allow(dead_code)]
+pub fn dropped() -> usize {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+// softirqs e trabalhos que já rodaram
+#[allow(dead_code)]
+pub fn executed() -> usize {
+    EXECUTED.load(Ordering::Relaxed)
+}
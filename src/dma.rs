@@ -0,0 +1,118 @@
+// buffers pra dma: memoria fisicamente seguida que o dispositivo acessa pelo endereco fisico
+// e o kernel pelo offset da memoria fisica (sempre mapeado, nunca sai do lugar)
+// sai do buddy -> o tamanho vira potencia de 2 paginas, e o bloco é alinhado no proprio tamanho,
+// entao qualquer alinhamento ate o tamanho vem de graca
+// no x86 o dma é coerente com o cache, nao precisa de flush nem de mapear sem cache
+// ex: let ring = dma::alloc(4096, 4096)?; nic.write(RING_BASE, ring.phys().as_u64()); ring.as_mut_ptr::<Descriptor>()
+use crate::memory::{self, frame};
+use core::fmt;
+use x86_64::structures::paging::PhysFrame;
+use x86_64::{PhysAddr, VirtAddr};
+
+// dispositivo de 32 bits só enxerga abaixo disso (hoje o frame::MAX_MEMORY é o mesmo valor, entao sempre cabe;
+// o limite vale quando o alocador passar a usar a memoria acima de 4 GiB)
+#[allow(dead_code)]
+pub const LIMIT_32: u64 = 1 << 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Error {
+    BadAlign,    // alinhamento que nao é potencia de 2
+    TooLarge,    // maior que o maior bloco do buddy
+    OutOfMemory, // nenhum bloco livre desse tamanho
+    AboveLimit,  // tem bloco livre, mas nenhum abaixo do limite do dispositivo
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Error::BadAlign => "alinhamento invalido",
+            Error::TooLarge => "buffer grande demais",
+            Error::OutOfMemory => "sem memoria contigua",
+            Error::AboveLimit => "sem memoria abaixo do limite",
+        })
+    }
+}
+
+// o drop devolve os frames -> o dispositivo tem que ter parado de usar antes
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Buffer {
+    frame: PhysFrame,
+    order: usize,
+    len: usize,
+}
+
+#[allow(dead_code)]
+impl Buffer {
+    pub fn phys(&self) -> PhysAddr {
+        self.frame.start_address()
+    }
+
+    pub fn virt(&self) -> VirtAddr {
+        memory::phys_to_virt(self.phys())
+    }
+
+    // o que foi pedido; o bloco de verdade pode ser maior (capacity)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn capacity(&self) -> usize {
+        (frame::FRAME_SIZE as usize) << self.order
+    }
+
+    pub fn as_mut_ptr<T>(&self) -> *mut T {
+        self.virt().as_mut_ptr()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.virt().as_ptr(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.virt().as_mut_ptr(), self.len) }
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        frame::deallocate_contiguous(self.frame, self.order);
+    }
+}
+
+// `len` bytes zerados, o endereco fisico alinhado em `align`, em qualquer lugar da memoria
+#[allow(dead_code)]
+pub fn alloc(len: usize, align: usize) -> Result<Buffer, Error> {
+    alloc_below(len, align, u64::MAX)
+}
+
+// pra dispositivo que só tem 32 bits de endereco
+#[allow(dead_code)]
+pub fn alloc_32(len: usize, align: usize) -> Result<Buffer, Error> {
+    alloc_below(len, align, LIMIT_32)
+}
+
+// o buffer inteiro abaixo de `limit`
+#[allow(dead_code)]
+pub fn alloc_below(len: usize, align: usize, limit: u64) -> Result<Buffer, Error> {
+    if !align.is_power_of_two() {
+        return Err(Error::BadAlign);
+    }
+    let pages = len.max(align).max(1).div_ceil(frame::FRAME_SIZE as usize);
+    let order = pages.next_power_of_two().trailing_zeros() as usize;
+    if order > frame::MAX_ORDER {
+        return Err(Error::TooLarge);
+    }
+    let block = match frame::allocate_contiguous_below(order, limit) {
+        Some(block) => block,
+        // tem bloco livre, só que acima do limite
+        None if limit < frame::MAX_MEMORY && frame::free_blocks()[order..].iter().any(|&count| count > 0) => {
+            return Err(Error::AboveLimit)
+        }
+        None => return Err(Error::OutOfMemory),
+    };
+    let buffer = Buffer { frame: block, order, len };
+    unsafe { core::ptr::write_bytes(buffer.as_mut_ptr::<u8>(), 0, buffer.capacity()) };
+    Ok(buffer)
+}
@@ -0,0 +1,99 @@
+// caixas e linhas com os caracteres de moldura do cp437 (paineis, dialogos)
+// ex: draw::draw_box(2, 10, 40, 8, BoxStyle::Double, None);
+use crate::vga_buffer::{ColorCode, Writer, WRITER};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum BoxStyle {
+    Single, // ┌─┐│└┘
+    Double, // ╔═╗║╚╝
+}
+
+#[allow(dead_code)]
+struct BoxChars {
+    horizontal: u8,
+    vertical: u8,
+    top_left: u8,
+    top_right: u8,
+    bottom_left: u8,
+    bottom_right: u8,
+}
+
+impl BoxStyle {
+    #[allow(dead_code)]
+    fn chars(self) -> BoxChars {
+        match self {
+            BoxStyle::Single => BoxChars {
+                horizontal: 0xc4,
+                vertical: 0xb3,
+                top_left: 0xda,
+                top_right: 0xbf,
+                bottom_left: 0xc0,
+                bottom_right: 0xd9,
+            },
+            BoxStyle::Double => BoxChars {
+                horizontal: 0xcd,
+                vertical: 0xba,
+                top_left: 0xc9,
+                top_right: 0xbb,
+                bottom_left: 0xc8,
+                bottom_right: 0xbc,
+            },
+        }
+    }
+}
+
+// color None -> cor atual do writer; o que sai da tela é cortado pelo put_char_at
+#[allow(dead_code)]
+pub fn hline(row: usize, col: usize, len: usize, style: BoxStyle, color: Option<ColorCode>) {
+    let mut writer = WRITER.lock();
+    let color_code = color.unwrap_or(writer.color_code());
+    put_hline(&mut writer, row, col, len, style.chars().horizontal, color_code);
+    writer.flush_if_auto();
+}
+
+#[allow(dead_code)]
+pub fn vline(row: usize, col: usize, len: usize, style: BoxStyle, color: Option<ColorCode>) {
+    let mut writer = WRITER.lock();
+    let color_code = color.unwrap_or(writer.color_code());
+    put_vline(&mut writer, row, col, len, style.chars().vertical, color_code);
+    writer.flush_if_auto();
+}
+
+// `width` e `height` contam a moldura; menor que 2x2 nao desenha nada
+// o interior nao é apagado (da pra desenhar em volta de texto que ja esta na tela)
+#[allow(dead_code)]
+pub fn draw_box(row: usize, col: usize, width: usize, height: usize, style: BoxStyle, color: Option<ColorCode>) {
+    if width < 2 || height < 2 {
+        return;
+    }
+    let chars = style.chars();
+    let mut writer = WRITER.lock();
+    let color_code = color.unwrap_or(writer.color_code());
+    let right = col + width - 1;
+    let bottom = row + height - 1;
+
+    writer.put_char_at(row, col, chars.top_left, color_code);
+    writer.put_char_at(row, right, chars.top_right, color_code);
+    writer.put_char_at(bottom, col, chars.bottom_left, color_code);
+    writer.put_char_at(bottom, right, chars.bottom_right, color_code);
+    put_hline(&mut writer, row, col + 1, width - 2, chars.horizontal, color_code);
+    put_hline(&mut writer, bottom, col + 1, width - 2, chars.horizontal, color_code);
+    put_vline(&mut writer, row + 1, col, height - 2, chars.vertical, color_code);
+    put_vline(&mut writer, row + 1, right, height - 2, chars.vertical, color_code);
+    writer.flush_if_auto();
+}
+
+#[allow(dead_code)]
+fn put_hline(writer: &mut Writer, row: usize, col: usize, len: usize, byte: u8, color_code: ColorCode) {
+    for i in 0..len {
+        writer.put_char_at(row, col + i, byte, color_code);
+    }
+}
+
+#[allow(dead_code)]
+fn put_vline(writer: &mut Writer, row: usize, col: usize, len: usize, byte: u8, color_code: ColorCode) {
+    for i in 0..len {
+        writer.put_char_at(row + i, col, byte, color_code);
+    }
+}
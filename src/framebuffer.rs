@@ -0,0 +1,289 @@
+// console em cima de um framebuffer linear (pixels), pra quando nao existe modo texto (UEFI)
+// o bootloader que entrega o framebuffer passa endereco/tamanho/formato pro init()
+// os caracteres sao desenhados com uma fonte bitmap PSF, mesma interface do Writer do vga_buffer
+use crate::cp437;
+use crate::vga_buffer::Color;
+use core::fmt;
+use spin::Mutex;
+
+// fonte padrao: 8x8, 256 caracteres na ordem do code page 437 (igual o modo texto)
+static DEFAULT_FONT: &[u8] = include_bytes!("fonts/font8x8.psf");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum PixelFormat {
+    Rgb, // byte 0 = vermelho
+    Bgr, // byte 0 = azul (o mais comum)
+}
+
+// o que o bootloader informa sobre o framebuffer
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub struct FrameBufferInfo {
+    pub address: usize,
+    pub width: usize,
+    pub height: usize,
+    pub stride: usize, // pixels por linha na memoria (pode ser maior que width)
+    pub bytes_per_pixel: usize,
+    pub pixel_format: PixelFormat,
+}
+
+// PSF1: magic 0x36 0x04, modo, altura -> largura sempre 8
+// PSF2: magic 0x72b54a86 + header com largura/altura/numero de glifos
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+#[derive(Clone, Copy)]
+pub struct PsfFont {
+    #[allow(dead_code)]
+    pub width: usize,
+    #[allow(dead_code)]
+    pub height: usize,
+    #[allow(dead_code)]
+    bytes_per_row: usize,
+    glyph_size: usize,
+    glyph_count: usize,
+    glyphs: &'static [u8],
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<usize> {
+    let b = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+}
+
+impl PsfFont {
+    // None se o arquivo nao for PSF valido
+    pub fn parse(bytes: &'static [u8]) -> Option<PsfFont> {
+        if bytes.len() >= 4 && bytes[0..2] == PSF1_MAGIC {
+            let glyph_count = if bytes[2] & PSF1_MODE_512 != 0 { 512 } else { 256 };
+            let height = bytes[3] as usize;
+            let glyphs = bytes.get(4..4 + glyph_count * height)?;
+            return Some(PsfFont {
+                width: 8,
+                height,
+                bytes_per_row: 1,
+                glyph_size: height,
+                glyph_count,
+                glyphs,
+            });
+        }
+        if bytes.len() >= 32 && bytes[0..4] == PSF2_MAGIC {
+            let header_size = read_u32(bytes, 8)?;
+            let glyph_count = read_u32(bytes, 16)?;
+            let glyph_size = read_u32(bytes, 20)?;
+            let height = read_u32(bytes, 24)?;
+            let width = read_u32(bytes, 28)?;
+            // sem glifo nenhum nao tem nem o de fallback
+            if glyph_count == 0 {
+                return None;
+            }
+            let glyphs = bytes.get(header_size..header_size.checked_add(glyph_count.checked_mul(glyph_size)?)?)?;
+            return Some(PsfFont {
+                width,
+                height,
+                bytes_per_row: width.div_ceil(8),
+                glyph_size,
+                glyph_count,
+                glyphs,
+            });
+        }
+        None
+    }
+
+    pub fn glyph(&self, index: usize) -> &[u8] {
+        // caractere que a fonte nao tem vira o ■ (0xfe), igual no modo texto; fonte sem o 0xfe usa o glifo 0
+        let index = if index < self.glyph_count {
+            index
+        } else if 0xfe < self.glyph_count {
+            0xfe
+        } else {
+            0
+        };
+        &self.glyphs[index * self.glyph_size..(index + 1) * self.glyph_size]
+    }
+
+    // bit mais significativo = pixel mais a esquerda
+    #[allow(dead_code)]
+    fn pixel(&self, glyph: &[u8], x: usize, y: usize) -> bool {
+        let byte = glyph[y * self.bytes_per_row + x / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+}
+
+// fonte embutida (o modo texto 80x50 tambem usa)
+pub fn default_font() -> PsfFont {
+    PsfFont::parse(DEFAULT_FONT).expect("fonte PSF embutida invalida")
+}
+
+// cores do modo texto em rgb, pra manter a mesma aparencia
+#[allow(dead_code)]
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0x00, 0x00, 0xaa),
+    (0x00, 0xaa, 0x00),
+    (0x00, 0xaa, 0xaa),
+    (0xaa, 0x00, 0x00),
+    (0xaa, 0x00, 0xaa),
+    (0xaa, 0x55, 0x00),
+    (0xaa, 0xaa, 0xaa),
+    (0x55, 0x55, 0x55),
+    (0x55, 0x55, 0xff),
+    (0x55, 0xff, 0x55),
+    (0x55, 0xff, 0xff),
+    (0xff, 0x55, 0x55),
+    (0xff, 0x55, 0xff),
+    (0xff, 0xff, 0x55),
+    (0xff, 0xff, 0xff),
+];
+
+#[allow(dead_code)]
+pub struct FramebufferConsole {
+    info: FrameBufferInfo,
+    font: PsfFont,
+    column_position: usize,
+    row_position: usize,
+    foreground: Color,
+    background: Color,
+}
+
+#[allow(dead_code)]
+impl FramebufferConsole {
+    // unsafe: quem chama garante que o framebuffer descrito em `info` esta mapeado e nao é usado por mais ninguem
+    pub unsafe fn new(info: FrameBufferInfo, font: PsfFont) -> FramebufferConsole {
+        FramebufferConsole {
+            info,
+            font,
+            column_position: 0,
+            row_position: 0,
+            foreground: Color::Yellow,
+            background: Color::Black,
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.info.width / self.font.width
+    }
+
+    pub fn rows(&self) -> usize {
+        self.info.height / self.font.height
+    }
+
+    pub fn set_color(&mut self, foreground: Color, background: Color) {
+        self.foreground = foreground;
+        self.background = background;
+    }
+
+    pub fn color(&self) -> (Color, Color) {
+        (self.foreground, self.background)
+    }
+
+    fn put_pixel(&mut self, x: usize, y: usize, color: Color) {
+        let (red, green, blue) = PALETTE[color as usize];
+        let bytes = match self.info.pixel_format {
+            PixelFormat::Rgb => [red, green, blue, 0],
+            PixelFormat::Bgr => [blue, green, red, 0],
+        };
+        let offset = (y * self.info.stride + x) * self.info.bytes_per_pixel;
+        let pixel = (self.info.address + offset) as *mut u8;
+        for (i, byte) in bytes.iter().take(self.info.bytes_per_pixel.min(4)).enumerate() {
+            unsafe { core::ptr::write_volatile(pixel.add(i), *byte) };
+        }
+    }
+
+    fn draw_glyph(&mut self, row: usize, col: usize, byte: u8) {
+        let font = self.font;
+        let glyph = font.glyph(byte as usize);
+        let x0 = col * font.width;
+        let y0 = row * font.height;
+        for y in 0..font.height {
+            for x in 0..font.width {
+                let color = if font.pixel(glyph, x, y) {
+                    self.foreground
+                } else {
+                    self.background
+                };
+                self.put_pixel(x0 + x, y0 + y, color);
+            }
+        }
+    }
+
+    pub fn write_byte(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.new_line(),
+            b'\r' => self.column_position = 0,
+            byte => self.put_glyph(byte),
+        }
+    }
+
+    fn put_glyph(&mut self, byte: u8) {
+        if self.column_position >= self.columns() {
+            self.new_line();
+        }
+        self.draw_glyph(self.row_position, self.column_position, byte);
+        self.column_position += 1;
+    }
+
+    // mesma regra do Writer: a fonte esta na ordem do cp437, o que nao tem glifo vira ■
+    pub fn write_string(&mut self, s: &str) {
+        for c in s.chars() {
+            match c {
+                '\n' | '\r' => self.write_byte(c as u8),
+                c => self.put_glyph(cp437::from_char(c).unwrap_or(0xfe)),
+            }
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.column_position = 0;
+        if self.row_position + 1 < self.rows() {
+            self.row_position += 1;
+            return;
+        }
+        // scroll: sobe tudo uma linha de texto (font.height linhas de pixels)
+        let line_bytes = self.info.stride * self.info.bytes_per_pixel;
+        let text_line_bytes = line_bytes * self.font.height;
+        let visible_bytes = line_bytes * self.rows() * self.font.height;
+        unsafe {
+            let base = self.info.address as *mut u8;
+            core::ptr::copy(base.add(text_line_bytes), base, visible_bytes - text_line_bytes);
+        }
+        self.clear_row(self.row_position);
+    }
+
+    fn clear_row(&mut self, row: usize) {
+        let y0 = row * self.font.height;
+        for y in y0..y0 + self.font.height {
+            for x in 0..self.info.width {
+                self.put_pixel(x, y, self.background);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for row in 0..self.rows() {
+            self.clear_row(row);
+        }
+        self.column_position = 0;
+        self.row_position = 0;
+    }
+}
+
+impl fmt::Write for FramebufferConsole {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_string(s);
+        Ok(())
+    }
+}
+
+// None enquanto ninguem chamou o init (boot pela BIOS usa o modo texto)
+#[allow(dead_code)]
+pub static CONSOLE: Mutex<Option<FramebufferConsole>> = Mutex::new(None);
+
+// unsafe pelo mesmo motivo do FramebufferConsole::new
+#[allow(dead_code)]
+pub unsafe fn init(info: FrameBufferInfo) {
+    let mut console = FramebufferConsole::new(info, default_font());
+    console.clear();
+    *CONSOLE.lock() = Some(console);
+}
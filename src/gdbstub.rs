@@ -0,0 +1,448 @@
+// stub do protocolo remoto do gdb (RSP) numa porta serial (COM2 por padrao), sem depender do stub do qemu
+// qemu ... -serial stdio -serial tcp::1234,server,nowait
+// gdb target/.../os_project -ex "target remote :1234"
+//
+// os handlers de excecao (int3 = vetor 3, debug = vetor 1, nmi) chamam handle_exception() com os
+// registradores que o stub de entrada guardou; aqui fica respondendo o gdb ate chegar um continue/step
+use crate::serial::SerialPort;
+use spin::Mutex;
+
+#[allow(dead_code)]
+const BAUD: u32 = 115200;
+const MAX_PACKET: usize = 1024;
+const MAX_BREAKPOINTS: usize = 16;
+const INT3: u8 = 0xCC;
+const TRAP_FLAG: u64 = 1 << 8;
+
+// na ordem que o gdb espera no pacote 'g' (amd64)
+#[derive(Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct Registers {
+    pub rax: u64,
+    pub rbx: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub rbp: u64,
+    pub rsp: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub r10: u64,
+    pub r11: u64,
+    pub r12: u64,
+    pub r13: u64,
+    pub r14: u64,
+    pub r15: u64,
+    pub rip: u64,
+    pub rflags: u64, // vai como 32 bits
+    pub cs: u64,     // segmentos tambem vao como 32 bits
+    pub ss: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+}
+
+const GPRS: usize = 17; // rax..r15 + rip, 64 bits cada
+const SEGMENTS: usize = 7; // rflags, cs, ss, ds, es, fs, gs, 32 bits cada
+
+impl Registers {
+    fn as_array(&self) -> [u64; GPRS + SEGMENTS] {
+        // repr(C) e só u64 -> da pra ler como array
+        unsafe { core::mem::transmute(*self) }
+    }
+
+    fn from_array(values: [u64; GPRS + SEGMENTS]) -> Registers {
+        unsafe { core::mem::transmute(values) }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Breakpoint {
+    address: u64,
+    original: u8,
+}
+
+struct Stub {
+    port: usize, // COMn; 0 -> desligado
+    breakpoints: [Option<Breakpoint>; MAX_BREAKPOINTS],
+}
+
+// o stub conversando com o gdb: a porta fica travada enquanto o kernel esta parado
+struct Session<'a> {
+    port: &'a mut SerialPort,
+    stub: &'a mut Stub,
+}
+
+static STUB: Mutex<Stub> = Mutex::new(Stub {
+    port: 0,
+    breakpoints: [None; MAX_BREAKPOINTS],
+});
+
+#[allow(dead_code)]
+const DEFAULT_PORT: usize = 2;
+
+#[allow(dead_code)]
+pub fn init() -> bool {
+    bind(DEFAULT_PORT)
+}
+
+// liga o stub na COMn (tem que ser diferente da porta do logger/shell, senao o gdb ve lixo)
+// retorna false se a porta nao existe ou nao respondeu
+#[allow(dead_code)]
+pub fn bind(number: usize) -> bool {
+    let port = match crate::serial::port(number) {
+        Some(port) => port,
+        None => return false,
+    };
+    let mut serial = port.lock();
+    if !serial.init(BAUD) {
+        return false;
+    }
+    STUB.lock().port = number;
+    true
+}
+
+pub fn port() -> usize {
+    STUB.lock().port
+}
+
+// para o kernel aqui e espera o gdb (precisa do handler do int3 instalado)
+#[allow(dead_code)]
+pub fn breakpoint() {
+    x86_64::instructions::interrupts::int3();
+}
+
+// o que o handler tem que fazer quando o gdb manda seguir
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    Continue,
+    Step,
+    Detach,
+}
+
+// chamado pelos handlers de excecao; `regs` é alterado com o que o gdb escrever
+// (o handler copia de volta pros registradores salvos e pro InterruptStackFrame antes do iretq)
+pub fn handle_exception(vector: u8, regs: &mut Registers) -> Resume {
+    let mut stub = match STUB.try_lock() {
+        Some(stub) => stub,
+        None => return Resume::Continue, // excecao dentro do proprio stub
+    };
+    let mut port = match crate::serial::port(stub.port).and_then(|port| port.try_lock()) {
+        Some(port) if port.is_present() => port,
+        _ => return Resume::Continue, // desligado, ou a excecao veio no meio de uma escrita nessa porta
+    };
+
+    // int3 deixa o rip depois do 0xCC -> volta pro endereco do breakpoint
+    if vector == 3 && stub.breakpoint_at(regs.rip.wrapping_sub(1)).is_some() {
+        regs.rip -= 1;
+    }
+    regs.rflags &= !TRAP_FLAG;
+    let mut session = Session {
+        port: &mut port,
+        stub: &mut stub,
+    };
+    session.send_packet(stop_reply(vector).as_bytes());
+
+    let mut packet = [0u8; MAX_PACKET];
+    loop {
+        let len = session.receive_packet(&mut packet);
+        if let Some(resume) = session.handle_packet(&packet[..len], vector, regs) {
+            if resume == Resume::Step {
+                regs.rflags |= TRAP_FLAG;
+            }
+            return resume;
+        }
+    }
+}
+
+// sinal unix que o gdb mostra: 5 = SIGTRAP, 11 = SIGSEGV, 8 = SIGFPE, 4 = SIGILL
+fn stop_reply(vector: u8) -> &'static str {
+    match vector {
+        1 | 3 => "S05",
+        0 | 16 | 19 => "S08",
+        6 => "S04",
+        _ => "S0b",
+    }
+}
+
+fn hex_digit(value: u8) -> u8 {
+    b"0123456789abcdef"[(value & 0xf) as usize]
+}
+
+fn from_hex(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn parse_hex(bytes: &[u8]) -> Option<u64> {
+    if bytes.is_empty() || bytes.len() > 16 {
+        return None;
+    }
+    bytes
+        .iter()
+        .try_fold(0u64, |value, &b| Some(value << 4 | from_hex(b)? as u64))
+}
+
+// "addr,len" -> (addr, len)
+fn parse_range(bytes: &[u8]) -> Option<(u64, usize)> {
+    let comma = bytes.iter().position(|&b| b == b',')?;
+    let address = parse_hex(&bytes[..comma])?;
+    let len = parse_hex(&bytes[comma + 1..])? as usize;
+    Some((address, len))
+}
+
+// resposta montada na stack (sem heap)
+struct Reply {
+    bytes: [u8; MAX_PACKET],
+    len: usize,
+}
+
+impl Reply {
+    fn new() -> Reply {
+        Reply {
+            bytes: [0; MAX_PACKET],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.len < MAX_PACKET {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        }
+    }
+
+    fn bytes_from(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push(byte);
+        }
+    }
+
+    // little endian, do jeito que o gdb le os registradores
+    fn push_hex_le(&mut self, value: u64, size: usize) {
+        for i in 0..size {
+            let byte = (value >> (i * 8)) as u8;
+            self.push(hex_digit(byte >> 4));
+            self.push(hex_digit(byte));
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+impl Stub {
+    fn breakpoint_at(&self, address: u64) -> Option<usize> {
+        self.breakpoints
+            .iter()
+            .position(|bp| matches!(bp, Some(bp) if bp.address == address))
+    }
+
+    fn insert_breakpoint(&mut self, address: u64) -> bool {
+        if self.breakpoint_at(address).is_some() {
+            return true;
+        }
+        let slot = match self.breakpoints.iter().position(|bp| bp.is_none()) {
+            Some(slot) => slot,
+            None => return false,
+        };
+        let original = unsafe { core::ptr::read_volatile(address as *const u8) };
+        unsafe { write_code(address, INT3) };
+        self.breakpoints[slot] = Some(Breakpoint { address, original });
+        true
+    }
+
+    fn remove_breakpoint(&mut self, address: u64) -> bool {
+        match self.breakpoint_at(address) {
+            Some(slot) => {
+                let bp = self.breakpoints[slot].take().unwrap();
+                unsafe { write_code(bp.address, bp.original) };
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Session<'_> {
+    fn read_byte(&mut self) -> u8 {
+        loop {
+            if let Some(byte) = self.port.try_receive() {
+                return byte;
+            }
+            crate::cpu::spin_hint();
+        }
+    }
+
+    // "$dados#cs" -> espera o '+' do gdb, reenvia se vier '-'
+    fn send_packet(&mut self, data: &[u8]) {
+        let checksum = data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        loop {
+            self.port.send(b'$');
+            for &byte in data {
+                self.port.send(byte);
+            }
+            self.port.send(b'#');
+            self.port.send(hex_digit(checksum >> 4));
+            self.port.send(hex_digit(checksum));
+            match self.read_byte() {
+                b'+' => return,
+                b'-' => continue,
+                _ => return, // ctrl-c ou lixo: nao fica preso aqui
+            }
+        }
+    }
+
+    // retorna o tamanho dos dados; pacote com checksum errado é recusado com '-'
+    fn receive_packet(&mut self, buffer: &mut [u8; MAX_PACKET]) -> usize {
+        loop {
+            while self.read_byte() != b'$' {}
+            let mut len = 0;
+            let mut checksum = 0u8;
+            loop {
+                let byte = self.read_byte();
+                if byte == b'#' {
+                    break;
+                }
+                checksum = checksum.wrapping_add(byte);
+                if len < MAX_PACKET {
+                    buffer[len] = byte;
+                    len += 1;
+                }
+            }
+            let high = from_hex(self.read_byte());
+            let low = from_hex(self.read_byte());
+            match (high, low) {
+                (Some(high), Some(low)) if high << 4 | low == checksum => {
+                    self.port.send(b'+');
+                    return len;
+                }
+                _ => self.port.send(b'-'),
+            }
+        }
+    }
+
+    // Some(..) -> sai do loop e volta pro codigo interrompido
+    fn handle_packet(&mut self, packet: &[u8], vector: u8, regs: &mut Registers) -> Option<Resume> {
+        let (&command, args) = match packet.split_first() {
+            Some(split) => split,
+            None => {
+                self.send_packet(b"");
+                return None;
+            }
+        };
+        let mut reply = Reply::new();
+        match command {
+            b'?' => reply.bytes_from(stop_reply(vector).as_bytes()),
+            b'g' => {
+                let values = regs.as_array();
+                for (i, &value) in values.iter().enumerate() {
+                    reply.push_hex_le(value, if i < GPRS { 8 } else { 4 });
+                }
+            }
+            b'G' => {
+                let mut values = regs.as_array();
+                let mut offset = 0;
+                for (i, value) in values.iter_mut().enumerate() {
+                    let size = if i < GPRS { 8 } else { 4 };
+                    match read_hex_le(args, offset, size) {
+                        Some(parsed) => *value = parsed,
+                        None => break,
+                    }
+                    offset += size * 2;
+                }
+                *regs = Registers::from_array(values);
+                reply.bytes_from(b"OK");
+            }
+            b'm' => match parse_range(args) {
+                // o gdb pode pedir endereco nao mapeado -> page fault (ainda sem tratamento aqui)
+                Some((address, len)) => {
+                    for i in 0..len.min(MAX_PACKET / 2) {
+                        let byte = unsafe { core::ptr::read_volatile((address + i as u64) as *const u8) };
+                        reply.push_hex_le(byte as u64, 1);
+                    }
+                }
+                None => reply.bytes_from(b"E01"),
+            },
+            b'M' => {
+                let colon = args.iter().position(|&b| b == b':');
+                match colon.and_then(|colon| Some((parse_range(&args[..colon])?, colon))) {
+                    Some(((address, len), colon)) => {
+                        let data = &args[colon + 1..];
+                        for i in 0..len.min(data.len() / 2) {
+                            let byte = read_hex_le(data, i * 2, 1).unwrap_or(0) as u8;
+                            unsafe { write_code(address + i as u64, byte) };
+                        }
+                        reply.bytes_from(b"OK");
+                    }
+                    None => reply.bytes_from(b"E01"),
+                }
+            }
+            // Z0,addr,kind / z0,addr,kind: breakpoint de software
+            b'Z' | b'z' => {
+                let address = args
+                    .strip_prefix(b"0,")
+                    .and_then(|rest| parse_hex(rest.split(|&b| b == b',').next()?));
+                // hardware/watchpoint nao suportado -> resposta vazia
+                if let Some(address) = address {
+                    let ok = if command == b'Z' {
+                        self.stub.insert_breakpoint(address)
+                    } else {
+                        self.stub.remove_breakpoint(address)
+                    };
+                    reply.bytes_from(if ok { b"OK" } else { b"E02" });
+                }
+            }
+            b'c' | b's' => {
+                if let Some(address) = parse_hex(args) {
+                    regs.rip = address;
+                }
+                return Some(if command == b'c' {
+                    Resume::Continue
+                } else {
+                    Resume::Step
+                });
+            }
+            b'D' => {
+                for slot in 0..MAX_BREAKPOINTS {
+                    if let Some(bp) = self.stub.breakpoints[slot] {
+                        self.stub.remove_breakpoint(bp.address);
+                    }
+                }
+                self.send_packet(b"OK");
+                return Some(Resume::Detach);
+            }
+            b'k' => return Some(Resume::Detach),
+            _ => {} // comando nao suportado -> resposta vazia
+        }
+        self.send_packet(reply.as_bytes());
+        None
+    }
+}
+
+// `size` bytes em hex little endian a partir de `offset` (em digitos)
+fn read_hex_le(bytes: &[u8], offset: usize, size: usize) -> Option<u64> {
+    let digits = bytes.get(offset..offset + size * 2)?;
+    let mut value = 0u64;
+    for (i, pair) in digits.chunks(2).enumerate() {
+        let byte = from_hex(pair[0])? << 4 | from_hex(pair[1])?;
+        value |= (byte as u64) << (i * 8);
+    }
+    Some(value)
+}
+
+// o codigo do kernel pode estar mapeado só leitura -> desliga o WP do CR0 pra escrever
+unsafe fn write_code(address: u64, byte: u8) {
+    use x86_64::registers::control::{Cr0, Cr0Flags};
+    let cr0 = Cr0::read();
+    Cr0::write(cr0 - Cr0Flags::WRITE_PROTECT);
+    core::ptr::write_volatile(address as *mut u8, byte);
+    Cr0::write(cr0);
+}
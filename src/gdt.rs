@@ -0,0 +1,99 @@
+// GDT: segmentos de codigo/dados do kernel e do usuario (ring 3) e a TSS
+// a TSS guarda stacks separadas (IST) pra excecoes que nao podem usar a stack atual -
+// ex: double fault por stack overflow, onde empilhar o frame na stack estourada daria triple fault
+use crate::memory::stack;
+use spin::Once;
+use x86_64::instructions::segmentation::{Segment, CS, DS, ES, SS};
+use x86_64::instructions::tables::load_tss;
+use x86_64::structures::gdt::{Descriptor, GlobalDescriptorTable, SegmentSelector};
+use x86_64::structures::tss::TaskStateSegment;
+use x86_64::VirtAddr;
+
+pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+
+const STACK_PAGES: usize = 5;
+const MAX_CPUS: usize = crate::smp::MAX_CPUS;
+
+// cada cpu tem a sua TSS (o ltr marca a TSS como ocupada, nao da pra carregar a mesma em duas)
+// e por isso a sua GDT; as stacks (com guard page) saem do memory::stack e nunca sao liberadas
+
+static TSS: [Once<TaskStateSegment>; MAX_CPUS] = [const { Once::new() }; MAX_CPUS];
+static GDT: [Once<(GlobalDescriptorTable, Selectors)>; MAX_CPUS] = [const { Once::new() }; MAX_CPUS];
+
+// sem essas stacks a cpu nao tem como seguir
+fn cpu_stack(owner: &'static str, cpu: usize) -> VirtAddr {
+    match stack::allocate(STACK_PAGES, owner, cpu) {
+        Ok(stack) => stack.leak(),
+        Err(error) => panic!("stack {} da cpu {}: {}", owner, cpu, error),
+    }
+}
+
+fn build_tss(cpu: usize) -> TaskStateSegment {
+    let mut tss = TaskStateSegment::new();
+    // a stack cresce pra baixo -> o IST aponta pro fim
+    // stack de emergencia do double fault
+    tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = cpu_stack("double fault", cpu);
+    // stack que a cpu pega quando uma interrupcao chega com o codigo em ring 3 (RSP0 da TSS)
+    tss.privilege_stack_table[0] = cpu_stack("privilege", cpu);
+    tss
+}
+
+struct Selectors {
+    code: SegmentSelector,
+    data: SegmentSelector,
+    user_data: SegmentSelector,
+    user_code: SegmentSelector,
+    tss: SegmentSelector,
+}
+
+// a ordem importa pro syscall/sysret: codigo do kernel, dados do kernel, dados do usuario, codigo do usuario
+// (o sysret pega SS = base + 8 e CS = base + 16 a partir de um seletor só, ver syscall::init)
+fn tables(cpu: usize) -> &'static (GlobalDescriptorTable, Selectors) {
+    GDT[cpu].call_once(|| {
+        let tss = TSS[cpu].call_once(|| build_tss(cpu));
+        let mut gdt = GlobalDescriptorTable::new();
+        let code = gdt.append(Descriptor::kernel_code_segment());
+        let data = gdt.append(Descriptor::kernel_data_segment());
+        let user_data = gdt.append(Descriptor::user_data_segment());
+        let user_code = gdt.append(Descriptor::user_code_segment());
+        let tss = gdt.append(Descriptor::tss_segment(tss));
+        (gdt, Selectors { code, data, user_data, user_code, tss })
+    })
+}
+
+// os seletores sao iguais em todas as cpus
+pub fn kernel_code_selector() -> SegmentSelector {
+    tables(0).1.code
+}
+
+// já com RPL 3
+pub fn user_code_selector() -> SegmentSelector {
+    tables(0).1.user_code
+}
+
+pub fn user_data_selector() -> SegmentSelector {
+    tables(0).1.user_data
+}
+
+// carrega a GDT e recarrega os registradores de segmento pra apontar pra ela
+// (os seletores do bootloader apontam pra GDT dele, que pode ser sobrescrita)
+pub fn init() {
+    load(0);
+}
+
+// numa cpu que acabou de acordar (smp), com o indice dela
+pub fn init_ap(cpu: usize) {
+    load(cpu);
+}
+
+fn load(cpu: usize) {
+    let (gdt, selectors) = tables(cpu);
+    gdt.load();
+    unsafe {
+        CS::set_reg(selectors.code);
+        SS::set_reg(selectors.data);
+        DS::set_reg(selectors.data);
+        ES::set_reg(selectors.data);
+        load_tss(selectors.tss);
+    }
+}
@@ -0,0 +1,200 @@
+// modo grafico 320x200 com 256 cores (mode 13h)
+// cada pixel é um byte (indice na paleta) na memoria a partir de 0xA0000, linha por linha
+use crate::vga_buffer::WRITER;
+use crate::vga_registers::{self, FONT_SIZE};
+use font8x8::legacy::BASIC_LEGACY;
+use lazy_static::lazy_static;
+use spin::Mutex;
+use volatile::Volatile;
+use x86_64::instructions::port::Port;
+
+pub const WIDTH: usize = 320;
+pub const HEIGHT: usize = 200;
+
+#[allow(dead_code)]
+const FONT_WIDTH: usize = 8;
+#[allow(dead_code)]
+const FONT_HEIGHT: usize = 8;
+
+// portas do DAC (paleta): escreve o indice e depois r, g, b (6 bits cada)
+#[allow(dead_code)]
+const DAC_READ_INDEX: u16 = 0x3C7;
+#[allow(dead_code)]
+const DAC_WRITE_INDEX: u16 = 0x3C8;
+#[allow(dead_code)]
+const DAC_DATA: u16 = 0x3C9;
+
+struct Framebuffer {
+    #[allow(dead_code)]
+    pixels: [[Volatile<u8>; WIDTH]; HEIGHT],
+}
+
+pub struct Graphics {
+    #[allow(dead_code)]
+    framebuffer: &'static mut Framebuffer,
+    active: bool, // false -> esta no modo texto e desenhar nao faz nada
+}
+
+lazy_static! {
+    pub static ref GRAPHICS: Mutex<Graphics> = Mutex::new(Graphics {
+        framebuffer: unsafe { &mut *(0xa0000 as *mut Framebuffer) },
+        active: false,
+    });
+}
+
+// o mode 13h escreve nos 4 planos (chain-4), inclusive no plano 2 onde fica a fonte do modo texto
+// entao a fonte é guardada antes de trocar e restaurada quando volta pro texto
+static SAVED_FONT: Mutex<[u8; FONT_SIZE]> = Mutex::new([0; FONT_SIZE]);
+
+impl Graphics {
+    #[allow(dead_code)]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // troca pro modo 13h e limpa a tela com a cor 0
+    #[allow(dead_code)]
+    pub fn enter(&mut self) {
+        if self.active {
+            return;
+        }
+        vga_registers::save_font(&mut SAVED_FONT.lock());
+        vga_registers::set_mode(&vga_registers::MODE_13H);
+        load_default_palette();
+        self.active = true;
+        self.clear(0);
+    }
+
+    // volta pro modo texto que estava antes (80x25 ou 80x50) e redesenha o que o writer tinha na tela
+    pub fn leave(&mut self) {
+        if !self.active {
+            return;
+        }
+        self.active = false;
+        let mut writer = WRITER.lock();
+        vga_registers::set_mode(writer.mode_registers());
+        vga_registers::restore_font(&SAVED_FONT.lock());
+        writer.refresh();
+    }
+
+    // desenhar fora da tela nao faz nada
+    #[allow(dead_code)]
+    pub fn put_pixel(&mut self, x: usize, y: usize, color: u8) {
+        if !self.active || x >= WIDTH || y >= HEIGHT {
+            return;
+        }
+        self.framebuffer.pixels[y][x].write(color);
+    }
+
+    #[allow(dead_code)]
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: u8) {
+        if !self.active {
+            return;
+        }
+        let x_end = (x + width).min(WIDTH);
+        let y_end = (y + height).min(HEIGHT);
+        for row in y.min(HEIGHT)..y_end {
+            for col in x.min(WIDTH)..x_end {
+                self.framebuffer.pixels[row][col].write(color);
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn clear(&mut self, color: u8) {
+        self.fill_rect(0, 0, WIDTH, HEIGHT, color);
+    }
+
+    // fonte 8x8: cada byte é uma linha do caractere, bit 0 = pixel mais a esquerda
+    // background None -> transparente (só desenha os pixels ligados)
+    #[allow(dead_code)]
+    pub fn draw_char(&mut self, x: usize, y: usize, c: char, foreground: u8, background: Option<u8>) {
+        let index = c as usize;
+        let glyph = if index < BASIC_LEGACY.len() {
+            BASIC_LEGACY[index]
+        } else {
+            BASIC_LEGACY[b'?' as usize]
+        };
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..FONT_WIDTH {
+                if bits & (1 << col) != 0 {
+                    self.put_pixel(x + col, y + row, foreground);
+                } else if let Some(background) = background {
+                    self.put_pixel(x + col, y + row, background);
+                }
+            }
+        }
+    }
+
+    // \n volta pro x inicial na linha de baixo
+    #[allow(dead_code)]
+    pub fn draw_text(&mut self, x: usize, y: usize, s: &str, foreground: u8, background: Option<u8>) {
+        let mut col = x;
+        let mut row = y;
+        for c in s.chars() {
+            if c == '\n' {
+                col = x;
+                row += FONT_HEIGHT;
+                continue;
+            }
+            self.draw_char(col, row, c, foreground, background);
+            col += FONT_WIDTH;
+        }
+    }
+}
+
+// paleta: cada componente vai de 0 a 63
+#[allow(dead_code)]
+pub fn set_palette(index: u8, red: u8, green: u8, blue: u8) {
+    unsafe {
+        Port::new(DAC_WRITE_INDEX).write(index);
+        let mut data: Port<u8> = Port::new(DAC_DATA);
+        data.write(red & 0x3f);
+        data.write(green & 0x3f);
+        data.write(blue & 0x3f);
+    }
+}
+
+// 0-15: as mesmas cores do modo texto, 16-231: cubo 6x6x6, 232-255: tons de cinza
+#[allow(dead_code)]
+pub fn load_default_palette() {
+    const TEXT_COLORS: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (0, 0, 42),
+        (0, 42, 0),
+        (0, 42, 42),
+        (42, 0, 0),
+        (42, 0, 42),
+        (42, 21, 0),
+        (42, 42, 42),
+        (21, 21, 21),
+        (21, 21, 63),
+        (21, 63, 21),
+        (21, 63, 63),
+        (63, 21, 21),
+        (63, 21, 63),
+        (63, 63, 21),
+        (63, 63, 63),
+    ];
+    for (i, (red, green, blue)) in TEXT_COLORS.iter().enumerate() {
+        set_palette(i as u8, *red, *green, *blue);
+    }
+    for i in 0..216u16 {
+        let level = |n: u16| (n * 63 / 5) as u8;
+        set_palette((16 + i) as u8, level(i / 36), level(i / 6 % 6), level(i % 6));
+    }
+    for i in 0..24u16 {
+        let gray = (i * 63 / 23) as u8;
+        set_palette((232 + i) as u8, gray, gray, gray);
+    }
+}
+
+// retorna (r, g, b)
+#[allow(dead_code)]
+pub fn palette(index: u8) -> (u8, u8, u8) {
+    unsafe {
+        Port::new(DAC_READ_INDEX).write(index);
+        let mut data: Port<u8> = Port::new(DAC_DATA);
+        (data.read(), data.read(), data.read())
+    }
+}
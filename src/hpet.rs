@@ -0,0 +1,156 @@
+// HPET (high precision event timer): contador de 64 bits que só sobe, com periodo fixo em femtosegundos
+// serve de relogio monotonico e cada comparador gera uma interrupcao quando o contador passa dele
+// ex: hpet::one_shot(0, 500_000) -> irq em 0,5 ms (precisa do I/O APIC)
+use crate::{acpi, memory};
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
+use x86_64::PhysAddr;
+
+const CAPABILITIES: u64 = 0x000;
+const CONFIGURATION: u64 = 0x010;
+const MAIN_COUNTER: u64 = 0x0F0;
+#[allow(dead_code)]
+const fn timer_config(n: u64) -> u64 {
+    0x100 + 0x20 * n
+}
+#[allow(dead_code)]
+const fn timer_comparator(n: u64) -> u64 {
+    0x108 + 0x20 * n
+}
+
+const ENABLE: u64 = 1 << 0;
+#[allow(dead_code)]
+const TIMER_INTERRUPT_ENABLE: u64 = 1 << 2;
+#[allow(dead_code)]
+const TIMER_ROUTE_SHIFT: u64 = 9;
+
+// endereco do registrador base dentro da tabela HPET (depois do header + id do bloco + inicio do GAS)
+const TABLE_ADDRESS_OFFSET: u64 = 44;
+
+// vetor das interrupcoes dos comparadores
+pub const VECTOR: u8 = 0xF1;
+
+// endereco virtual dos registradores; 0 -> sem hpet
+static BASE: AtomicU64 = AtomicU64::new(0);
+static PERIOD_FS: AtomicU64 = AtomicU64::new(0);
+
+static CALLBACK: Mutex<Option<fn()>> = Mutex::new(None);
+
+fn read(register: u64) -> u64 {
+    let base = BASE.load(Ordering::Relaxed);
+    unsafe { core::ptr::read_volatile((base + register) as *const u64) }
+}
+
+fn write(register: u64, value: u64) {
+    let base = BASE.load(Ordering::Relaxed);
+    unsafe { core::ptr::write_volatile((base + register) as *mut u64, value) }
+}
+
+// acha o hpet pela tabela acpi e liga o contador; false se nao tem
+pub fn init() -> bool {
+    let table = match acpi::find_table(b"HPET") {
+        Some(table) => table,
+        None => return false,
+    };
+    let physical: u64 = unsafe {
+        let ptr = memory::phys_to_virt(PhysAddr::new(table + TABLE_ADDRESS_OFFSET)).as_ptr::<u64>();
+        core::ptr::read_unaligned(ptr)
+    };
+    if memory::map_physical(PhysAddr::new(physical), 4096).is_err() {
+        return false;
+    }
+    BASE.store(memory::phys_to_virt(PhysAddr::new(physical)).as_u64(), Ordering::Relaxed);
+
+    let period = read(CAPABILITIES) >> 32;
+    if period == 0 || period > 100_000_000 {
+        // a especificacao limita em 100 ns; fora disso nao é um hpet de verdade
+        BASE.store(0, Ordering::Relaxed);
+        return false;
+    }
+    PERIOD_FS.store(period, Ordering::Relaxed);
+
+    // zera o contador com ele parado e liga (sem o modo legado: PIT e RTC continuam com as irqs deles)
+    write(CONFIGURATION, 0);
+    write(MAIN_COUNTER, 0);
+    write(CONFIGURATION, ENABLE);
+    true
+}
+
+pub fn is_present() -> bool {
+    BASE.load(Ordering::Relaxed) != 0
+}
+
+// femtosegundos por incremento do contador
+pub fn period_fs() -> u64 {
+    PERIOD_FS.load(Ordering::Relaxed)
+}
+
+pub fn frequency() -> u64 {
+    match period_fs() {
+        0 => 0,
+        period => 1_000_000_000_000_000 / period,
+    }
+}
+
+pub fn counter() -> u64 {
+    if !is_present() {
+        return 0;
+    }
+    read(MAIN_COUNTER)
+}
+
+// nanossegundos desde o init()
+pub fn now_ns() -> u64 {
+    (counter() as u128 * period_fs() as u128 / 1_000_000) as u64
+}
+
+pub fn timers() -> u64 {
+    if !is_present() {
+        return 0;
+    }
+    ((read(CAPABILITIES) >> 8) & 0x1f) + 1
+}
+
+// quem recebe as irqs dos comparadores (roda dentro da interrupcao)
+#[allow(dead_code)]
+pub fn set_callback(callback: Option<fn()>) {
+    crate::cpu::without_interrupts(|| *CALLBACK.lock() = callback);
+}
+
+// comparador `timer` dispara uma vez daqui a `ns`
+// a irq sai numa linha do I/O APIC que o comparador aceita (bits 32-63 da config) e cai em VECTOR
+#[allow(dead_code)]
+pub fn one_shot(timer: u64, ns: u64) -> bool {
+    if timer >= timers() {
+        return false;
+    }
+    let config = read(timer_config(timer));
+    let routes = config >> 32;
+    if routes == 0 {
+        return false;
+    }
+    let gsi = routes.trailing_zeros();
+    if !crate::ioapic::route_gsi(gsi, VECTOR, crate::apic::id() as u8, 0) {
+        return false;
+    }
+    let ticks = (ns as u128 * 1_000_000 / period_fs() as u128) as u64;
+    let new_config = (config & !(0x1f << TIMER_ROUTE_SHIFT)) | (gsi as u64) << TIMER_ROUTE_SHIFT | TIMER_INTERRUPT_ENABLE;
+    write(timer_config(timer), new_config);
+    write(timer_comparator(timer), counter().wrapping_add(ticks.max(1)));
+    true
+}
+
+#[allow(dead_code)]
+pub fn cancel(timer: u64) {
+    if timer < timers() {
+        let config = read(timer_config(timer));
+        write(timer_config(timer), config & !TIMER_INTERRUPT_ENABLE);
+    }
+}
+
+// chamado pelo handler do VECTOR
+pub fn handle_interrupt() {
+    if let Some(callback) = CALLBACK.try_lock().and_then(|callback| *callback) {
+        callback();
+    }
+}
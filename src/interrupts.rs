@@ -0,0 +1,664 @@
+// tabela de interrupcoes (IDT): cada excecao/irq aponta pro seu handler aqui
+// init_idt() tem que rodar cedo no boot, antes de qualquer coisa que possa gerar excecao
+use crate::gdbstub::{self, Registers};
+use crate::pic::{PICS, PIC_1_OFFSET};
+use crate::println;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use lazy_static::lazy_static;
+use spin::Mutex;
+use x86_64::registers::control::Cr2;
+use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
+use x86_64::{PrivilegeLevel, VirtAddr};
+
+// vetores das irqs do pic (irq n -> PIC_1_OFFSET + n)
+// allow(dead_code) -> COM2 ainda sem handler
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum InterruptIndex {
+    Timer = PIC_1_OFFSET,
+    Keyboard,
+    Com2 = PIC_1_OFFSET + 3,
+    Com1,
+    Spurious = PIC_1_OFFSET + 7,
+    SlaveSpurious = PIC_1_OFFSET + 15,
+}
+
+impl InterruptIndex {
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    // linha no pic
+    pub fn irq(self) -> u8 {
+        self as u8 - PIC_1_OFFSET
+    }
+}
+
+lazy_static! {
+    static ref IDT: InterruptDescriptorTable = {
+        let mut idt = InterruptDescriptorTable::new();
+        // esses tres podem parar no gdb -> entram pelos stubs em assembly que guardam os registradores gerais
+        unsafe {
+            idt.breakpoint.set_handler_addr(VirtAddr::from_ptr(breakpoint_entry as *const ()));
+            idt.debug.set_handler_addr(VirtAddr::from_ptr(debug_entry as *const ()));
+            idt.non_maskable_interrupt.set_handler_addr(VirtAddr::from_ptr(nmi_entry as *const ()));
+        }
+        // stack propria (IST): se foi stack overflow, a stack atual nao serve
+        unsafe {
+            idt.double_fault
+                .set_handler_fn(double_fault_handler)
+                .set_stack_index(crate::gdt::DOUBLE_FAULT_IST_INDEX);
+        }
+        idt.page_fault.set_handler_fn(page_fault_handler);
+        idt.machine_check.set_handler_fn(machine_check_handler);
+        idt.x87_floating_point.set_handler_fn(x87_floating_point_handler);
+        idt.simd_floating_point.set_handler_fn(simd_floating_point_handler);
+        idt.general_protection_fault.set_handler_fn(general_protection_fault_handler);
+        idt[InterruptIndex::Timer.as_u8()].set_handler_fn(timer_handler);
+        idt[InterruptIndex::Keyboard.as_u8()].set_handler_fn(keyboard_handler);
+        idt[InterruptIndex::Com1.as_u8()].set_handler_fn(com1_handler);
+        idt[InterruptIndex::Spurious.as_u8()].set_handler_fn(spurious_handler);
+        idt[InterruptIndex::SlaveSpurious.as_u8()].set_handler_fn(slave_spurious_handler);
+        idt[crate::apic::SPURIOUS_VECTOR].set_handler_fn(apic_spurious_handler);
+        idt[crate::apic_timer::VECTOR].set_handler_fn(apic_timer_handler);
+        idt[crate::hpet::VECTOR].set_handler_fn(hpet_handler);
+        // DPL 3: senao o `int 0x80` do ring 3 vira GPF
+        unsafe {
+            idt[crate::syscall::INT80_VECTOR]
+                .set_handler_addr(crate::syscall::int80_entry())
+                .set_privilege_level(PrivilegeLevel::Ring3);
+        }
+        idt
+    };
+}
+
+pub fn init_idt() {
+    IDT.load();
+}
+
+// quem entrega as irqs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Controller {
+    Pic,
+    Apic,
+}
+
+static USING_APIC: AtomicBool = AtomicBool::new(false);
+
+// o 8259 é sempre remapeado (mesmo desligado ele pode gerar irq espuria, que nao pode cair em cima das excecoes)
+// com a feature "apic", uma cpu com apic e um I/O APIC na MADT, o 8259 fica todo mascarado e o apic assume
+pub fn init_controller() -> Controller {
+    crate::pic::init();
+    if cfg!(feature = "apic") && crate::ioapic::init() && crate::apic::init() {
+        PICS.lock().disable();
+        USING_APIC.store(true, Ordering::Relaxed);
+        return Controller::Apic;
+    }
+    Controller::Pic
+}
+
+pub fn controller() -> Controller {
+    if USING_APIC.load(Ordering::Relaxed) {
+        Controller::Apic
+    } else {
+        Controller::Pic
+    }
+}
+
+// libera a irq do dispositivo no controlador atual
+pub fn enable_irq(index: InterruptIndex) {
+    match controller() {
+        Controller::Pic => PICS.lock().unmask(index.irq()),
+        // sempre pra cpu que chamou
+        Controller::Apic => {
+            crate::ioapic::route(index.irq(), index.as_u8(), crate::apic::id() as u8);
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn disable_irq(index: InterruptIndex) {
+    match controller() {
+        Controller::Pic => PICS.lock().mask(index.irq()),
+        Controller::Apic => {
+            crate::ioapic::mask(index.irq());
+        }
+    }
+}
+
+// no fim de todo handler de irq
+pub fn end_of_interrupt(index: InterruptIndex) {
+    match controller() {
+        Controller::Pic => PICS.lock().notify_end_of_interrupt(index.as_u8()),
+        Controller::Apic => crate::apic::end_of_interrupt(),
+    }
+}
+
+// quantas vezes cada vetor disparou desde o boot (tempestade de irq, EOI esquecido...)
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+static COUNTS: [AtomicU64; 256] = [ZERO; 256];
+
+// primeira coisa em todo handler (pub pros stubs em assembly de fora, ex: int 0x80)
+pub fn count(vector: u8) {
+    COUNTS[vector as usize].fetch_add(1, Ordering::Relaxed);
+    if crate::percpu::is_ready() {
+        crate::percpu::current().stats.interrupts.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn count_of(vector: u8) -> u64 {
+    COUNTS[vector as usize].load(Ordering::Relaxed)
+}
+
+// (vetor, quantas vezes) dos que dispararam pelo menos uma vez
+pub fn stats() -> impl Iterator<Item = (u8, u64)> {
+    (0..=255u8).map(|vector| (vector, count_of(vector))).filter(|&(_, count)| count > 0)
+}
+
+// nome pra mostrar junto do numero
+pub fn vector_name(vector: u8) -> &'static str {
+    match vector {
+        0 => "divide error",
+        1 => "debug",
+        2 => "nmi",
+        3 => "breakpoint",
+        6 => "invalid opcode",
+        8 => "double fault",
+        13 => "general protection",
+        14 => "page fault",
+        16 => "x87 floating point",
+        18 => "machine check",
+        19 => "simd floating point",
+        v if v == InterruptIndex::Timer.as_u8() => "pit timer",
+        v if v == InterruptIndex::Keyboard.as_u8() => "keyboard",
+        v if v == InterruptIndex::Com1.as_u8() => "com1",
+        v if v == InterruptIndex::Spurious.as_u8() => "pic spurious",
+        v if v == InterruptIndex::SlaveSpurious.as_u8() => "pic spurious (slave)",
+        crate::apic_timer::VECTOR => "apic timer",
+        crate::hpet::VECTOR => "hpet",
+        crate::apic::SPURIOUS_VECTOR => "apic spurious",
+        crate::syscall::INT80_VECTOR => "syscall (int 0x80)",
+        _ => "",
+    }
+}
+
+// registradores gerais do codigo interrompido, na ordem em que o stub empilha (o ultimo push fica no comeco)
+// o rsp nao entra: é o do frame da cpu
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SavedRegisters {
+    rax: u64,
+    rbx: u64,
+    rcx: u64,
+    rdx: u64,
+    rsi: u64,
+    rdi: u64,
+    rbp: u64,
+    r8: u64,
+    r9: u64,
+    r10: u64,
+    r11: u64,
+    r12: u64,
+    r13: u64,
+    r14: u64,
+    r15: u64,
+}
+
+// entrada das excecoes que podem ir pro gdb: empilha os 15 registradores, chama o handler com
+// (registradores, frame da cpu logo acima deles) e devolve o que ele deixou antes do iretq
+// sem codigo de erro: frame da cpu (40 bytes, com o rsp alinhado em 16 antes) + 15 pushes -> alinhado no call
+// sem swapgs, igual aos handlers x86-interrupt
+macro_rules! debugger_entry {
+    ($name:literal, $handler:ident) => {
+        core::arch::global_asm!(
+            concat!(".global ", $name),
+            concat!($name, ":"),
+            "push r15",
+            "push r14",
+            "push r13",
+            "push r12",
+            "push r11",
+            "push r10",
+            "push r9",
+            "push r8",
+            "push rbp",
+            "push rdi",
+            "push rsi",
+            "push rdx",
+            "push rcx",
+            "push rbx",
+            "push rax",
+            "cld",
+            "mov rdi, rsp",
+            "lea rsi, [rsp + 15 * 8]",
+            "call {handler}",
+            "pop rax",
+            "pop rbx",
+            "pop rcx",
+            "pop rdx",
+            "pop rsi",
+            "pop rdi",
+            "pop rbp",
+            "pop r8",
+            "pop r9",
+            "pop r10",
+            "pop r11",
+            "pop r12",
+            "pop r13",
+            "pop r14",
+            "pop r15",
+            "iretq",
+            handler = sym $handler,
+        );
+    };
+}
+
+debugger_entry!("breakpoint_entry", breakpoint_handler);
+debugger_entry!("debug_entry", debug_handler);
+debugger_entry!("nmi_entry", nmi_handler);
+
+extern "C" {
+    fn breakpoint_entry();
+    fn debug_entry();
+    fn nmi_entry();
+}
+
+extern "C" fn breakpoint_handler(saved: &mut SavedRegisters, stack_frame: &mut InterruptStackFrame) {
+    count(3);
+    if gdbstub::port() != 0 {
+        enter_debugger(3, saved, stack_frame);
+        return;
+    }
+    println!("EXCEPTION: BREAKPOINT\n{:#?}", stack_frame);
+}
+
+// trap flag (step do gdb)
+extern "C" fn debug_handler(saved: &mut SavedRegisters, stack_frame: &mut InterruptStackFrame) {
+    count(1);
+    if gdbstub::port() != 0 {
+        enter_debugger(1, saved, stack_frame);
+        return;
+    }
+    println!("EXCEPTION: DEBUG\n{:#?}", stack_frame);
+}
+
+// o que fazer quando chega um NMI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NmiPolicy {
+    Panic,    // tela de excecao e para (padrao: NMI geralmente é hardware com problema)
+    Log,      // avisa e continua
+    Debugger, // para no gdb (cai pro Log se o stub nao estiver ligado)
+}
+
+static NMI_POLICY: AtomicU8 = AtomicU8::new(NmiPolicy::Panic as u8);
+
+#[allow(dead_code)]
+pub fn set_nmi_policy(policy: NmiPolicy) {
+    NMI_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+pub fn nmi_policy() -> NmiPolicy {
+    match NMI_POLICY.load(Ordering::Relaxed) {
+        0 => NmiPolicy::Panic,
+        1 => NmiPolicy::Log,
+        _ => NmiPolicy::Debugger,
+    }
+}
+
+const SYSTEM_CONTROL_A: u16 = 0x92; // bit 4: watchdog estourou
+const SYSTEM_CONTROL_B: u16 = 0x61; // bit 7: erro de paridade da memoria, bit 6: erro de canal de i/o
+const NMI_PARITY: u8 = 1 << 7;
+const NMI_IO_CHECK: u8 = 1 << 6;
+const NMI_WATCHDOG: u8 = 1 << 4;
+const CLEAR_PARITY: u8 = 1 << 2; // escrever 1 e depois 0 zera o latch
+const CLEAR_IO_CHECK: u8 = 1 << 3;
+
+// le o motivo nas portas de controle do sistema e limpa o latch (senao o proximo NMI nao vem)
+fn nmi_reason() -> &'static str {
+    use x86_64::instructions::port::Port;
+    let mut port_a = Port::<u8>::new(SYSTEM_CONTROL_A);
+    let mut port_b = Port::<u8>::new(SYSTEM_CONTROL_B);
+    let (status_a, status_b) = unsafe { (port_a.read(), port_b.read()) };
+    let reason = if status_b & NMI_PARITY != 0 {
+        "erro de memoria (paridade/SERR)"
+    } else if status_b & NMI_IO_CHECK != 0 {
+        "erro de canal de i/o (IOCHK)"
+    } else if status_a & NMI_WATCHDOG != 0 {
+        "watchdog"
+    } else {
+        "desconhecido"
+    };
+    if status_b & (NMI_PARITY | NMI_IO_CHECK) != 0 {
+        // os bits de baixo da porta B controlam o alto-falante -> mantem como estavam
+        let control = status_b & 0x0f;
+        unsafe {
+            port_b.write(control | CLEAR_PARITY | CLEAR_IO_CHECK);
+            port_b.write(control & !(CLEAR_PARITY | CLEAR_IO_CHECK));
+        }
+    }
+    reason
+}
+
+// pode chegar no meio de qualquer coisa (inclusive com o WRITER pego) -> o log vai pela fila de irq
+extern "C" fn nmi_handler(saved: &mut SavedRegisters, stack_frame: &mut InterruptStackFrame) {
+    count(2);
+    let reason = nmi_reason();
+    match nmi_policy() {
+        NmiPolicy::Panic => crate::panic_screen::show_exception(
+            "NON-MASKABLE INTERRUPT",
+            stack_frame,
+            None,
+            Some(format_args!("motivo: {}", reason)),
+        ),
+        NmiPolicy::Debugger if gdbstub::port() != 0 => enter_debugger(2, saved, stack_frame),
+        _ => {
+            crate::println_from_irq!(
+                "NMI: {} (rip {:#x})",
+                reason,
+                stack_frame.instruction_pointer.as_u64()
+            );
+        }
+    }
+}
+
+// sem esse handler uma excecao dentro de outra vira triple fault e o qemu reinicia sem mostrar nada
+// estouro de stack do kernel quase sempre chega aqui: a cpu nao consegue empilhar o frame do page fault
+// na stack estourada -> o CR2 ainda aponta pra guard page
+extern "x86-interrupt" fn double_fault_handler(stack_frame: InterruptStackFrame, error_code: u64) -> ! {
+    count(8);
+    if let Some(guard) = Cr2::read().ok().and_then(crate::memory::guard::find) {
+        crate::panic_screen::show_exception(
+            "KERNEL STACK OVERFLOW",
+            &stack_frame,
+            Some(error_code),
+            Some(format_args!("estourou a stack {} (double fault)", guard)),
+        )
+    }
+    crate::panic_screen::show_exception("DOUBLE FAULT", &stack_frame, Some(error_code), None)
+}
+
+// o que a cpu conta sobre um page fault
+#[derive(Debug, Clone, Copy)]
+pub struct PageFault {
+    pub address: VirtAddr, // CR2: endereco que foi acessado
+    pub error: PageFaultErrorCode,
+    #[allow(dead_code)]
+    pub instruction: VirtAddr, // RIP da instrucao que falhou
+}
+
+impl PageFault {
+    pub fn is_write(&self) -> bool {
+        self.error.contains(PageFaultErrorCode::CAUSED_BY_WRITE)
+    }
+
+    // false -> a pagina nao esta mapeada; true -> esta, mas o acesso nao é permitido
+    pub fn is_protection_violation(&self) -> bool {
+        self.error.contains(PageFaultErrorCode::PROTECTION_VIOLATION)
+    }
+
+    pub fn is_user(&self) -> bool {
+        self.error.contains(PageFaultErrorCode::USER_MODE)
+    }
+
+    pub fn is_instruction_fetch(&self) -> bool {
+        self.error.contains(PageFaultErrorCode::INSTRUCTION_FETCH)
+    }
+}
+
+// "escrita em pagina nao presente (kernel)"
+impl fmt::Display for PageFault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let access = if self.is_instruction_fetch() {
+            "execucao"
+        } else if self.is_write() {
+            "escrita"
+        } else {
+            "leitura"
+        };
+        let reason = if self.error.contains(PageFaultErrorCode::MALFORMED_TABLE) {
+            "tabela de paginas corrompida (bit reservado)"
+        } else if self.is_protection_violation() {
+            "violacao de protecao"
+        } else {
+            "pagina nao presente"
+        };
+        let mode = if self.is_user() { "user" } else { "kernel" };
+        write!(f, "{} em {:#x}: {} ({})", access, self.address.as_u64(), reason, mode)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    Retry, // a policy resolveu (ex: mapeou a pagina) -> executa a instrucao de novo
+    Fatal, // mostra o erro e para
+}
+
+// quem decide se o fault tem conserto (demand paging, copy-on-write...); sem policy todo fault é fatal
+pub type PageFaultPolicy = fn(&PageFault) -> FaultAction;
+
+static PAGE_FAULT_POLICY: Mutex<Option<PageFaultPolicy>> = Mutex::new(None);
+// cpu (pelo apic id do cpuid, o GS nao vale se o fault veio do ring 3) que esta dentro da policy agora
+static IN_POLICY: [AtomicBool; 256] = [const { AtomicBool::new(false) }; 256];
+
+pub fn set_page_fault_policy(policy: Option<PageFaultPolicy>) {
+    crate::cpu::without_interrupts(|| {
+        *PAGE_FAULT_POLICY.lock() = policy;
+    });
+}
+
+extern "x86-interrupt" fn page_fault_handler(stack_frame: InterruptStackFrame, error_code: PageFaultErrorCode) {
+    count(14);
+    let fault = PageFault {
+        address: Cr2::read().unwrap_or(VirtAddr::zero()),
+        error: error_code,
+        instruction: stack_frame.instruction_pointer,
+    };
+    if let Some(guard) = crate::memory::guard::find(fault.address) {
+        crate::panic_screen::show_exception(
+            "KERNEL STACK OVERFLOW",
+            &stack_frame,
+            Some(error_code.bits()),
+            Some(format_args!("estourou a stack {}: {}", guard, fault)),
+        )
+    }
+    // um fault dentro da propria policy nao chama ela de novo -> vai direto pro panic
+    // (a flag é por cpu: outra cpu pode estar resolvendo o fault dela ao mesmo tempo)
+    let in_policy = &IN_POLICY[crate::percpu::initial_apic_id() as usize];
+    if !in_policy.swap(true, Ordering::Acquire) {
+        let policy = PAGE_FAULT_POLICY.try_lock().and_then(|policy| *policy);
+        let action = policy.map(|policy| policy(&fault));
+        in_policy.store(false, Ordering::Release);
+        if action == Some(FaultAction::Retry) {
+            return;
+        }
+    }
+    crate::panic_screen::show_exception(
+        "PAGE FAULT",
+        &stack_frame,
+        Some(error_code.bits()),
+        Some(format_args!("{}", fault)),
+    )
+}
+
+// error code do GPF: 0 -> nao foi por causa de um seletor (instrucao privilegiada, endereco nao canonico...)
+struct SelectorError(u64);
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 == 0 {
+            return f.write_str("sem seletor envolvido");
+        }
+        let table = match (self.0 >> 1) & 0b11 {
+            0 => "GDT",
+            2 => "LDT",
+            _ => "IDT",
+        };
+        let external = if self.0 & 1 != 0 { " (evento externo)" } else { "" };
+        write!(f, "seletor: {} indice {}{}", table, (self.0 >> 3) & 0x1fff, external)
+    }
+}
+
+// registradores de segmento no momento da falha (CS e SS vem do frame)
+struct Segments;
+
+impl fmt::Display for Segments {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use x86_64::instructions::segmentation::{Segment, DS, ES, FS, GS};
+        write!(
+            f,
+            "DS {:#06x}  ES {:#06x}  FS {:#06x}  GS {:#06x}",
+            DS::get_reg().0,
+            ES::get_reg().0,
+            FS::get_reg().0,
+            GS::get_reg().0
+        )
+    }
+}
+
+extern "x86-interrupt" fn general_protection_fault_handler(stack_frame: InterruptStackFrame, error_code: u64) {
+    count(13);
+    crate::panic_screen::show_exception(
+        "GENERAL PROTECTION FAULT",
+        &stack_frame,
+        Some(error_code),
+        Some(format_args!("{}\n  {}", SelectorError(error_code), Segments)),
+    )
+}
+
+// erro de hardware: nao tem como continuar, mas pelo menos mostra os bancos antes de parar
+extern "x86-interrupt" fn machine_check_handler(stack_frame: InterruptStackFrame) -> ! {
+    count(18);
+    crate::panic_screen::show_exception(
+        "MACHINE CHECK",
+        &stack_frame,
+        None,
+        Some(format_args!("{}", crate::mce::Report)),
+    )
+}
+
+// só chegam se alguem desmascarar as excecoes no FCW/MXCSR
+extern "x86-interrupt" fn x87_floating_point_handler(stack_frame: InterruptStackFrame) {
+    count(16);
+    crate::panic_screen::show_exception("X87 FLOATING POINT", &stack_frame, None, None)
+}
+
+extern "x86-interrupt" fn simd_floating_point_handler(stack_frame: InterruptStackFrame) {
+    count(19);
+    let mut mxcsr: u32 = 0;
+    unsafe { core::arch::asm!("stmxcsr [{}]", in(reg) &mut mxcsr, options(nostack)) };
+    crate::panic_screen::show_exception(
+        "SIMD FLOATING POINT",
+        &stack_frame,
+        None,
+        Some(format_args!("MXCSR {:#x}", mxcsr)),
+    )
+}
+
+extern "x86-interrupt" fn timer_handler(_stack_frame: InterruptStackFrame) {
+    count(InterruptIndex::Timer.as_u8());
+    crate::time::tick();
+    end_of_interrupt(InterruptIndex::Timer);
+}
+
+extern "x86-interrupt" fn keyboard_handler(_stack_frame: InterruptStackFrame) {
+    count(InterruptIndex::Keyboard.as_u8());
+    crate::keyboard::handle_interrupt();
+    end_of_interrupt(InterruptIndex::Keyboard);
+}
+
+extern "x86-interrupt" fn com1_handler(_stack_frame: InterruptStackFrame) {
+    count(InterruptIndex::Com1.as_u8());
+    crate::serial::handle_interrupt();
+    end_of_interrupt(InterruptIndex::Com1);
+}
+
+extern "x86-interrupt" fn spurious_handler(_stack_frame: InterruptStackFrame) {
+    count(InterruptIndex::Spurious.as_u8());
+    let mut pics = PICS.lock();
+    if !pics.is_spurious(InterruptIndex::Spurious.as_u8()) {
+        pics.notify_end_of_interrupt(InterruptIndex::Spurious.as_u8());
+    }
+}
+
+extern "x86-interrupt" fn slave_spurious_handler(_stack_frame: InterruptStackFrame) {
+    count(InterruptIndex::SlaveSpurious.as_u8());
+    let mut pics = PICS.lock();
+    if !pics.is_spurious(InterruptIndex::SlaveSpurious.as_u8()) {
+        pics.notify_end_of_interrupt(InterruptIndex::SlaveSpurious.as_u8());
+    }
+}
+
+// com o apic o tick vem daqui e nao do PIT
+extern "x86-interrupt" fn apic_timer_handler(_stack_frame: InterruptStackFrame) {
+    count(crate::apic_timer::VECTOR);
+    crate::time::tick();
+    crate::apic::end_of_interrupt();
+}
+
+extern "x86-interrupt" fn hpet_handler(_stack_frame: InterruptStackFrame) {
+    count(crate::hpet::VECTOR);
+    crate::hpet::handle_interrupt();
+    crate::apic::end_of_interrupt();
+}
+
+// o spurious do apic nao leva EOI
+extern "x86-interrupt" fn apic_spurious_handler(_stack_frame: InterruptStackFrame) {
+    count(crate::apic::SPURIOUS_VECTOR);
+}
+
+// passa o controle pro gdb; volta quando ele mandar continue/step
+// o que o gdb escrever ('G') volta pros registradores salvos e pro frame -> o iretq do stub já sai com eles
+// (rsp e segmentos ficam como estavam: trocar a stack do kernel por baixo do handler nao da)
+fn enter_debugger(vector: u8, saved: &mut SavedRegisters, stack_frame: &mut InterruptStackFrame) {
+    let mut regs = Registers {
+        rax: saved.rax,
+        rbx: saved.rbx,
+        rcx: saved.rcx,
+        rdx: saved.rdx,
+        rsi: saved.rsi,
+        rdi: saved.rdi,
+        rbp: saved.rbp,
+        rsp: stack_frame.stack_pointer.as_u64(),
+        r8: saved.r8,
+        r9: saved.r9,
+        r10: saved.r10,
+        r11: saved.r11,
+        r12: saved.r12,
+        r13: saved.r13,
+        r14: saved.r14,
+        r15: saved.r15,
+        rip: stack_frame.instruction_pointer.as_u64(),
+        rflags: stack_frame.cpu_flags.bits(),
+        cs: stack_frame.code_segment.0 as u64,
+        ss: stack_frame.stack_segment.0 as u64,
+        ..Registers::default()
+    };
+    gdbstub::handle_exception(vector, &mut regs);
+    *saved = SavedRegisters {
+        rax: regs.rax,
+        rbx: regs.rbx,
+        rcx: regs.rcx,
+        rdx: regs.rdx,
+        rsi: regs.rsi,
+        rdi: regs.rdi,
+        rbp: regs.rbp,
+        r8: regs.r8,
+        r9: regs.r9,
+        r10: regs.r10,
+        r11: regs.r11,
+        r12: regs.r12,
+        r13: regs.r13,
+        r14: regs.r14,
+        r15: regs.r15,
+    };
+    unsafe {
+        stack_frame.as_mut().update(|frame| {
+            frame.instruction_pointer = VirtAddr::new(regs.rip);
+            frame.cpu_flags = x86_64::registers::rflags::RFlags::from_bits_truncate(regs.rflags);
+        });
+    }
+}
@@ -0,0 +1,141 @@
+// I/O APIC: recebe as irqs dos dispositivos e manda pro local apic de alguma cpu
+// cada linha (GSI) tem uma entrada de redirecionamento: vetor, cpu destino, polaridade, trigger, mascara
+use crate::acpi::{self, IoApicInfo};
+use crate::memory;
+use spin::Mutex;
+use x86_64::PhysAddr;
+
+const IOREGSEL: u64 = 0x00;
+const IOWIN: u64 = 0x10;
+const IOAPICVER: u32 = 0x01;
+const REDIRECTION_TABLE: u32 = 0x10; // entrada n: 0x10 + 2n (baixo) e 0x11 + 2n (alto)
+
+const MASKED: u32 = 1 << 16;
+const LEVEL_TRIGGERED: u32 = 1 << 15;
+const ACTIVE_LOW: u32 = 1 << 13;
+
+struct IoApic {
+    base: u64, // endereco virtual dos registradores
+    gsi_base: u32,
+    entries: u32,
+}
+
+impl IoApic {
+    fn new(info: &IoApicInfo) -> IoApic {
+        let physical = PhysAddr::new(info.address as u64);
+        if let Err(error) = memory::map_physical(physical, 4096) {
+            log::warn!("ioapic: nao deu pra mapear {:#x}: {}", physical.as_u64(), error);
+        }
+        let base = memory::phys_to_virt(physical).as_u64();
+        let mut io_apic = IoApic {
+            base,
+            gsi_base: info.gsi_base,
+            entries: 0,
+        };
+        // bits 16-23 da versao: numero de entradas - 1
+        io_apic.entries = ((io_apic.read(IOAPICVER) >> 16) & 0xff) + 1;
+        io_apic
+    }
+
+    fn read(&mut self, register: u32) -> u32 {
+        unsafe {
+            core::ptr::write_volatile((self.base + IOREGSEL) as *mut u32, register);
+            core::ptr::read_volatile((self.base + IOWIN) as *const u32)
+        }
+    }
+
+    fn write(&mut self, register: u32, value: u32) {
+        unsafe {
+            core::ptr::write_volatile((self.base + IOREGSEL) as *mut u32, register);
+            core::ptr::write_volatile((self.base + IOWIN) as *mut u32, value);
+        }
+    }
+
+    fn handles(&self, gsi: u32) -> bool {
+        (self.gsi_base..self.gsi_base + self.entries).contains(&gsi)
+    }
+
+    fn set_entry(&mut self, gsi: u32, low: u32, high: u32) {
+        let index = (gsi - self.gsi_base) * 2;
+        // mascara antes de trocar o resto, pra nao chegar irq com a entrada pela metade
+        self.write(REDIRECTION_TABLE + index, MASKED);
+        self.write(REDIRECTION_TABLE + index + 1, high);
+        self.write(REDIRECTION_TABLE + index, low);
+    }
+
+    #[allow(dead_code)]
+    fn set_masked(&mut self, gsi: u32, masked: bool) {
+        let register = REDIRECTION_TABLE + (gsi - self.gsi_base) * 2;
+        let low = self.read(register);
+        self.write(register, if masked { low | MASKED } else { low & !MASKED });
+    }
+}
+
+static IO_APICS: Mutex<heapless::Vec<IoApic, 8>> = Mutex::new(heapless::Vec::new());
+
+// acha os I/O APICs pela MADT e mascara todas as linhas; false se nao tem nenhum
+pub fn init() -> bool {
+    let madt = match acpi::madt() {
+        Some(madt) => madt,
+        None => return false,
+    };
+    let mut io_apics = IO_APICS.lock();
+    io_apics.clear();
+    for info in madt.io_apics.iter() {
+        let mut io_apic = IoApic::new(info);
+        for gsi in io_apic.gsi_base..io_apic.gsi_base + io_apic.entries {
+            io_apic.set_entry(gsi, MASKED, 0);
+        }
+        let _ = io_apics.push(io_apic);
+    }
+    !io_apics.is_empty()
+}
+
+// liga a irq isa `irq` no `vector` da cpu `apic_id` (segue os overrides da MADT, ex: irq 0 -> GSI 2)
+// retorna false se nenhum I/O APIC atende a GSI
+pub fn route(irq: u8, vector: u8, apic_id: u8) -> bool {
+    let (gsi, flags) = gsi_for(irq);
+    route_gsi(gsi, vector, apic_id, flags)
+}
+
+// linha global direto (dispositivo que nao é isa, ex: comparador do hpet)
+// `flags` no formato dos overrides da MADT (0 -> borda, ativo em alto)
+pub fn route_gsi(gsi: u32, vector: u8, apic_id: u8, flags: u16) -> bool {
+    let mut low = vector as u32;
+    // flags do override: polaridade 3 = ativo em baixo, trigger 3 = nivel
+    if flags & 0b11 == 0b11 {
+        low |= ACTIVE_LOW;
+    }
+    if (flags >> 2) & 0b11 == 0b11 {
+        low |= LEVEL_TRIGGERED;
+    }
+    let high = (apic_id as u32) << 24;
+    with_io_apic(gsi, |io_apic| io_apic.set_entry(gsi, low, high))
+}
+
+#[allow(dead_code)]
+pub fn mask(irq: u8) -> bool {
+    let (gsi, _) = gsi_for(irq);
+    with_io_apic(gsi, |io_apic| io_apic.set_masked(gsi, true))
+}
+
+#[allow(dead_code)]
+pub fn unmask(irq: u8) -> bool {
+    let (gsi, _) = gsi_for(irq);
+    with_io_apic(gsi, |io_apic| io_apic.set_masked(gsi, false))
+}
+
+fn gsi_for(irq: u8) -> (u32, u16) {
+    acpi::madt().map(|madt| madt.isa_irq(irq)).unwrap_or((irq as u32, 0))
+}
+
+fn with_io_apic<F: FnOnce(&mut IoApic)>(gsi: u32, f: F) -> bool {
+    let mut io_apics = IO_APICS.lock();
+    match io_apics.iter_mut().find(|io_apic| io_apic.handles(gsi)) {
+        Some(io_apic) => {
+            f(io_apic);
+            true
+        }
+        None => false,
+    }
+}
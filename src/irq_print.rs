@@ -0,0 +1,176 @@
+// print de dentro de handler de interrupcao
+// se a interrupcao chegou enquanto o codigo interrompido estava com o lock do WRITER, um println!
+// no handler trava pra sempre -> o handler só coloca os bytes nessa fila (sem lock nenhum)
+// e o loop principal chama drain() pra mandar pro console de verdade
+use core::fmt;
+use core::sync::atomic::{AtomicU16, AtomicUsize, Ordering};
+use spin::Mutex;
+
+const QUEUE_SIZE: usize = 4096;
+const VALID: u16 = 0x100; // bit 8 marca que o byte ja foi escrito e pode ser lido
+
+// cada handler reserva um pedaco com CAS no head e escreve os bytes
+// o drain le a partir do tail enquanto os slots estao marcados como validos
+// -> varios produtores (interrupcoes aninhadas) e um consumidor, sem lock
+struct Queue {
+    slots: [AtomicU16; QUEUE_SIZE],
+    head: AtomicUsize, // proximo slot a ser reservado
+    tail: AtomicUsize, // proximo slot a ser lido
+    dropped: AtomicUsize, // mensagens perdidas por falta de espaco
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: AtomicU16 = AtomicU16::new(0);
+
+static QUEUE: Queue = Queue {
+    slots: [EMPTY_SLOT; QUEUE_SIZE],
+    head: AtomicUsize::new(0),
+    tail: AtomicUsize::new(0),
+    dropped: AtomicUsize::new(0),
+};
+
+impl Queue {
+    // a mensagem entra inteira ou nao entra (nunca pela metade)
+    fn push(&self, bytes: &[u8]) -> bool {
+        let len = bytes.len();
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            if head.wrapping_sub(tail) + len > QUEUE_SIZE {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            match self.head.compare_exchange_weak(
+                head,
+                head.wrapping_add(len),
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+        for (i, byte) in bytes.iter().enumerate() {
+            let slot = &self.slots[head.wrapping_add(i) % QUEUE_SIZE];
+            slot.store(VALID | *byte as u16, Ordering::Release);
+        }
+        true
+    }
+
+    // tira o proximo byte pronto; None se a fila esta vazia ou o proximo ainda esta sendo escrito
+    fn pop(&self) -> Option<u8> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let slot = &self.slots[tail % QUEUE_SIZE];
+        let value = slot.load(Ordering::Acquire);
+        if value & VALID == 0 {
+            return None;
+        }
+        slot.store(0, Ordering::Relaxed);
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Some(value as u8)
+    }
+}
+
+// a mensagem é formatada aqui (na stack do handler) e depois vai pra fila de uma vez
+// assim duas interrupcoes nao misturam os bytes das mensagens; o que passar do tamanho é cortado
+const MESSAGE_SIZE: usize = 256;
+
+struct Message {
+    bytes: [u8; MESSAGE_SIZE],
+    len: usize,
+}
+
+impl fmt::Write for Message {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let n = s.len().min(MESSAGE_SIZE - self.len);
+        let n = (0..=n).rev().find(|&i| s.is_char_boundary(i)).unwrap_or(0);
+        self.bytes[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+#[macro_export]
+macro_rules! print_from_irq {
+    ($($arg:tt)*) => ($crate::irq_print::_print(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! println_from_irq {
+    () => ($crate::print_from_irq!("\n"));
+    ($($arg:tt)*) => ($crate::print_from_irq!("{}\n", format_args!($($arg)*)));
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use core::fmt::Write;
+    let mut message = Message {
+        bytes: [0; MESSAGE_SIZE],
+        len: 0,
+    };
+    let _ = message.write_fmt(args);
+    QUEUE.push(&message.bytes[..message.len]);
+}
+
+// quantas mensagens foram descartadas com a fila cheia desde o boot
+#[allow(dead_code)]
+pub fn dropped() -> usize {
+    QUEUE.dropped.load(Ordering::Relaxed)
+}
+
+// char pela metade no fim do ultimo drain (o handler ainda estava escrevendo): volta pro comeco do proximo
+// (no maximo 3 bytes: com 4 o char já estaria completo). só o drain mexe
+static PENDING: Mutex<([u8; 3], usize)> = Mutex::new(([0; 3], 0));
+
+// manda o que esta na fila pro print! normal; só pode ser chamado fora de interrupcao
+pub fn drain() {
+    let mut pending = PENDING.lock();
+    let mut chunk = [0; 64];
+    let mut len = pending.1;
+    chunk[..len].copy_from_slice(&pending.0[..len]);
+    while let Some(byte) = QUEUE.pop() {
+        chunk[len] = byte;
+        len += 1;
+        if len == chunk.len() {
+            len = print_valid(&mut chunk, len);
+        }
+    }
+    // sobrou um char pela metade -> espera o resto no proximo drain
+    if len > 0 {
+        len = print_valid(&mut chunk, len);
+    }
+    pending.0[..len].copy_from_slice(&chunk[..len]);
+    pending.1 = len;
+}
+
+// imprime o comeco do chunk que é utf-8 valido (byte invalido vira �)
+// um char incompleto no fim fica no comeco do chunk pra juntar com os proximos bytes
+// retorna quantos bytes sobraram
+fn print_valid(chunk: &mut [u8; 64], mut len: usize) -> usize {
+    loop {
+        match core::str::from_utf8(&chunk[..len]) {
+            Ok(text) => {
+                crate::print!("{}", text);
+                return 0;
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                if let Ok(text) = core::str::from_utf8(&chunk[..valid]) {
+                    crate::print!("{}", text);
+                }
+                let skip = match e.error_len() {
+                    Some(bad) => {
+                        crate::print!("\u{fffd}");
+                        valid + bad
+                    }
+                    None => valid,
+                };
+                chunk.copy_within(skip..len, 0);
+                len -= skip;
+                if e.error_len().is_none() {
+                    return len;
+                }
+            }
+        }
+    }
+}
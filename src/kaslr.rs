@@ -0,0 +1,20 @@
+// kaslr: o mapa da memoria fisica, o heap, as regioes do vm e as stacks do kernel mudam de lugar a cada boot
+// (sorteado com o rand dentro de janelas fixas) -> um ponteiro vazado/adivinhado nao serve no boot seguinte
+// kaslr=off na linha de comando (cmdline) volta pro layout de sempre, pra debugar com enderecos que se repetem
+use crate::{cmdline, rand};
+use core::ops::Range;
+
+// onde o mapa da memoria fisica pode cair (metade de cima, longe do que o bootloader usa); sem kaslr, no comeco
+pub const PHYSICAL_WINDOW: Range<u64> = crate::memory::PHYSICAL_MAP_BASE..0xffff_c000_0000_0000;
+
+pub fn enabled() -> bool {
+    cmdline::get("kaslr") != Some("off")
+}
+
+// multiplo de `align` sorteado em [0, span]; 0 com o kaslr desligado
+pub fn slide(span: u64, align: u64) -> u64 {
+    if !enabled() || span < align {
+        return 0;
+    }
+    (rand::next_u64() % (span / align + 1)) * align
+}
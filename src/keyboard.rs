@@ -0,0 +1,225 @@
+// teclado ps/2: a irq 1 só le o scancode da porta 0x60 e poe numa fila
+// o poll() (loop principal) decodifica o scancode set 1 em KeyEvent e entrega pro consumidor registrado
+// o consumidor roda fora da interrupcao -> pode usar o WRITER e o resto à vontade
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+const DATA_PORT: u16 = 0x60;
+const EXTENDED: u8 = 0xE0;
+const RELEASED: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char), // ja com shift/caps aplicados
+    Enter,
+    Backspace,
+    Tab,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    F(u8), // F1..F12
+    Shift,
+    Ctrl,
+    Alt,
+    CapsLock,
+    Unknown(u8), // scancode sem traducao
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub caps_lock: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub pressed: bool, // false -> tecla solta
+    pub modifiers: Modifiers, // estado depois dessa tecla
+}
+
+// scancode set 1, layout us; 0 -> nao é caractere
+const LOWER: &[u8; 58] = b"\0\x1b1234567890-=\x08\tqwertyuiop[]\n\0asdfghjkl;'`\0\\zxcvbnm,./\0*\0 ";
+const UPPER: &[u8; 58] = b"\0\x1b!@#$%^&*()_+\x08\tQWERTYUIOP{}\n\0ASDFGHJKL:\"~\0|ZXCVBNM<>?\0*\0 ";
+// teclado numerico (0x47..=0x53), como se o num lock estivesse ligado
+const KEYPAD: &[u8; 13] = b"789-456+1230.";
+
+struct Decoder {
+    extended: bool, // o ultimo byte foi 0xE0
+    modifiers: Modifiers,
+}
+
+impl Decoder {
+    fn feed(&mut self, scancode: u8) -> Option<KeyEvent> {
+        if scancode == EXTENDED {
+            self.extended = true;
+            return None;
+        }
+        let extended = core::mem::replace(&mut self.extended, false);
+        let pressed = scancode & RELEASED == 0;
+        let code = scancode & !RELEASED;
+        let key = if extended {
+            extended_key(code)
+        } else {
+            self.key(code)
+        };
+
+        match key {
+            Key::Shift => self.modifiers.shift = pressed,
+            Key::Ctrl => self.modifiers.ctrl = pressed,
+            Key::Alt => self.modifiers.alt = pressed,
+            Key::CapsLock if pressed => self.modifiers.caps_lock = !self.modifiers.caps_lock,
+            _ => {}
+        }
+        Some(KeyEvent {
+            key,
+            pressed,
+            modifiers: self.modifiers,
+        })
+    }
+
+    fn key(&self, code: u8) -> Key {
+        match code {
+            0x01 => Key::Escape,
+            0x0E => Key::Backspace,
+            0x0F => Key::Tab,
+            0x1C => Key::Enter,
+            0x1D => Key::Ctrl,
+            0x2A | 0x36 => Key::Shift,
+            0x38 => Key::Alt,
+            0x3A => Key::CapsLock,
+            0x3B..=0x44 => Key::F(code - 0x3B + 1),
+            0x57 => Key::F(11),
+            0x58 => Key::F(12),
+            0x47..=0x53 => Key::Char(KEYPAD[(code - 0x47) as usize] as char),
+            _ => match LOWER.get(code as usize) {
+                Some(&lower) if lower != 0 => {
+                    let letter = lower.is_ascii_lowercase();
+                    let upper = if letter {
+                        self.modifiers.shift != self.modifiers.caps_lock
+                    } else {
+                        self.modifiers.shift
+                    };
+                    let byte = if upper { UPPER[code as usize] } else { lower };
+                    Key::Char(byte as char)
+                }
+                _ => Key::Unknown(code),
+            },
+        }
+    }
+}
+
+// teclas com prefixo 0xE0 (setas, bloco de edicao, lado direito)
+fn extended_key(code: u8) -> Key {
+    match code {
+        0x1C => Key::Enter,
+        0x1D => Key::Ctrl,
+        0x35 => Key::Char('/'),
+        0x38 => Key::Alt,
+        0x47 => Key::Home,
+        0x48 => Key::Up,
+        0x49 => Key::PageUp,
+        0x4B => Key::Left,
+        0x4D => Key::Right,
+        0x4F => Key::End,
+        0x50 => Key::Down,
+        0x51 => Key::PageDown,
+        0x52 => Key::Insert,
+        0x53 => Key::Delete,
+        // 0x2A/0x37 do print screen e shift "falso" que alguns teclados mandam
+        _ => Key::Unknown(code),
+    }
+}
+
+static DECODER: Mutex<Decoder> = Mutex::new(Decoder {
+    extended: false,
+    modifiers: Modifiers {
+        shift: false,
+        ctrl: false,
+        alt: false,
+        caps_lock: false,
+    },
+});
+
+static CONSUMER: Mutex<Option<fn(KeyEvent)>> = Mutex::new(None);
+
+// quem recebe as teclas (shell da tela, editor...); None -> as teclas sao descartadas
+pub fn set_consumer(consumer: Option<fn(KeyEvent)>) {
+    *CONSUMER.lock() = consumer;
+}
+
+#[allow(dead_code)]
+pub fn modifiers() -> Modifiers {
+    DECODER.lock().modifiers
+}
+
+// scancodes crus: a interrupcao coloca, o poll() tira (mesma ideia do rx da serial)
+const QUEUE_SIZE: usize = 128;
+
+struct ScancodeQueue {
+    bytes: [AtomicU8; QUEUE_SIZE],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY: AtomicU8 = AtomicU8::new(0);
+
+static QUEUE: ScancodeQueue = ScancodeQueue {
+    bytes: [EMPTY; QUEUE_SIZE],
+    head: AtomicUsize::new(0),
+    tail: AtomicUsize::new(0),
+};
+
+impl ScancodeQueue {
+    // cheio -> perde o scancode (o decoder se recupera no proximo make/break)
+    fn push(&self, byte: u8) {
+        let head = self.head.load(Ordering::Relaxed);
+        if head.wrapping_sub(self.tail.load(Ordering::Acquire)) == QUEUE_SIZE {
+            return;
+        }
+        self.bytes[head % QUEUE_SIZE].store(byte, Ordering::Relaxed);
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+    }
+
+    fn pop(&self) -> Option<u8> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail == self.head.load(Ordering::Acquire) {
+            return None;
+        }
+        let byte = self.bytes[tail % QUEUE_SIZE].load(Ordering::Relaxed);
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Some(byte)
+    }
+}
+
+// chamado pelo handler da irq 1
+pub fn handle_interrupt() {
+    let scancode = unsafe { Port::<u8>::new(DATA_PORT).read() };
+    // o momento exato de cada tecla é imprevisivel -> vira entropia
+    crate::rand::add_entropy(crate::time::rdtsc() ^ scancode as u64);
+    QUEUE.push(scancode);
+    crate::deferred::raise(crate::deferred::Softirq::Keyboard);
+}
+
+// softirq do teclado (roda no loop principal)
+pub fn poll() {
+    while let Some(scancode) = QUEUE.pop() {
+        let event = DECODER.lock().feed(scancode);
+        let consumer = *CONSUMER.lock();
+        if let (Some(event), Some(consumer)) = (event, consumer) {
+            consumer(event);
+        }
+    }
+}
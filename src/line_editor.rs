@@ -0,0 +1,149 @@
+// editor de uma linha de entrada (base pro shell)
+// guarda o texto digitado e redesenha a linha no lugar a cada tecla, sem mexer no que esta antes do prompt
+use crate::vga_buffer::WRITER;
+use heapless::{String, Vec};
+
+pub const MAX_LINE: usize = 256;
+
+pub type Line = String<MAX_LINE>;
+
+// o driver de teclado traduz o scancode pra uma dessas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKey {
+    Char(char),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Enter,
+}
+
+pub struct LineEditor {
+    chars: Vec<char, MAX_LINE>, // char e nao String pq inserir/apagar no meio é por posicao
+    cursor: usize, // indice em `chars` onde o proximo char entra
+    start: (usize, usize), // (linha, coluna) da tela onde a linha comeca (logo depois do prompt)
+    drawn: usize, // quantas celulas o ultimo redesenho ocupou (pra apagar o que sobrou)
+}
+
+impl LineEditor {
+    pub const fn new() -> LineEditor {
+        LineEditor {
+            chars: Vec::new(),
+            cursor: 0,
+            start: (0, 0),
+            drawn: 0,
+        }
+    }
+
+    // escreve o prompt e comeca uma linha vazia logo depois dele
+    pub fn begin(&mut self, prompt: &str) {
+        self.chars.clear();
+        self.cursor = 0;
+        self.drawn = 0;
+        let mut writer = WRITER.lock();
+        writer.write_string(prompt);
+        self.start = writer.cursor();
+    }
+
+    // o que ja foi digitado (ainda sem Enter)
+    #[allow(dead_code)]
+    pub fn chars(&self) -> &[char] {
+        &self.chars
+    }
+
+    // retorna a linha completa quando recebe Enter, senao None
+    pub fn handle_key(&mut self, key: EditKey) -> Option<Line> {
+        match key {
+            EditKey::Char(c) if !c.is_control() => {
+                // linha cheia -> ignora
+                if self.chars.insert(self.cursor, c).is_ok() {
+                    self.cursor += 1;
+                }
+            }
+            EditKey::Char(_) => return None,
+            EditKey::Backspace => {
+                if self.cursor == 0 {
+                    return None;
+                }
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            EditKey::Delete => {
+                if self.cursor < self.chars.len() {
+                    self.chars.remove(self.cursor);
+                }
+            }
+            EditKey::Left => self.cursor = self.cursor.saturating_sub(1),
+            EditKey::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            EditKey::Home => self.cursor = 0,
+            EditKey::End => self.cursor = self.chars.len(),
+            EditKey::Enter => return Some(self.finish()),
+        }
+        self.render();
+        None
+    }
+
+    // cursor pro fim, quebra a linha e devolve o texto
+    fn finish(&mut self) -> Line {
+        self.cursor = self.chars.len();
+        self.render();
+        WRITER.lock().write_string("\n");
+
+        let mut line = Line::new();
+        for c in self.chars.iter() {
+            // o String tem MAX_LINE bytes -> com acento (2+ bytes por char) o fim pode ser cortado
+            if line.push(*c).is_err() {
+                break;
+            }
+        }
+        self.chars.clear();
+        self.cursor = 0;
+        self.drawn = 0;
+        line
+    }
+
+    // reescreve a linha inteira a partir do inicio e poe o cursor no lugar
+    fn render(&mut self) {
+        let mut writer = WRITER.lock();
+        let (start_row, start_col) = self.start;
+        writer.set_cursor(start_row, start_col);
+
+        let mut buf = [0; 4];
+        for c in self.chars.iter() {
+            writer.write_string(c.encode_utf8(&mut buf));
+        }
+        // linha ficou menor -> apaga o resto do desenho anterior
+        let len = self.chars.len();
+        for _ in len..self.drawn {
+            writer.write_string(" ");
+        }
+        let written = len.max(self.drawn);
+        self.drawn = len;
+
+        // se a linha passou da ultima linha da tela teve scroll -> o inicio subiu junto
+        let (row, col) = writer.cursor();
+        let width = writer.width();
+        let expected = start_row * width + start_col + written;
+        let actual = row * width + col;
+        let scrolled = expected.saturating_sub(actual) / width;
+        self.start.0 = start_row.saturating_sub(scrolled);
+
+        let (row, col) = self.screen_position(self.cursor, width);
+        writer.set_cursor(row, col);
+    }
+
+    // posicao na tela do char `index` da linha
+    fn screen_position(&self, index: usize, width: usize) -> (usize, usize) {
+        let (start_row, start_col) = self.start;
+        let linear = start_row * width + start_col + index;
+        let (row, col) = (linear / width, linear % width);
+        // bem no fim de uma linha cheia: fica no fim dessa linha (o proximo char quebra), nao na de baixo
+        if col == 0 && index > 0 && index == self.chars.len() {
+            (row - 1, width)
+        } else {
+            (row, col)
+        }
+    }
+}
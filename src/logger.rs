@@ -0,0 +1,180 @@
+// logger do kernel pro crate `log`: os subsistemas usam info!/warn!/error!... e aqui decide
+// o nivel, o filtro por modulo e pra onde vai (vga, serial)
+// ex: logger::set_module_level("os_project::serial", LevelFilter::Trace);
+use crate::theme::{self, Role};
+use crate::vga_buffer::Color;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use spin::Mutex;
+
+// sinks (da pra ligar varios)
+pub const SINK_VGA: u8 = 1 << 0;
+pub const SINK_SERIAL: u8 = 1 << 1;
+pub const SINK_DEBUGCON: u8 = 1 << 2;
+
+static SINKS: AtomicU8 = AtomicU8::new(SINK_VGA | SINK_SERIAL);
+static SERIAL_PORT: AtomicUsize = AtomicUsize::new(1); // COMn do sink serial
+static LEVEL: AtomicUsize = AtomicUsize::new(LevelFilter::Info as usize);
+
+// filtro por modulo: o prefixo mais comprido que bate com o target ganha
+const MAX_FILTERS: usize = 16;
+static FILTERS: Mutex<heapless::Vec<(&'static str, LevelFilter), MAX_FILTERS>> =
+    Mutex::new(heapless::Vec::new());
+
+struct KernelLogger;
+
+static LOGGER: KernelLogger = KernelLogger;
+
+pub fn init() {
+    // o filtro de verdade é no enabled(); o max_level do crate fica aberto pros filtros por modulo
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Trace);
+    }
+}
+
+pub fn set_level(level: LevelFilter) {
+    LEVEL.store(level as usize, Ordering::Relaxed);
+}
+
+pub fn level() -> LevelFilter {
+    filter_from(LEVEL.load(Ordering::Relaxed))
+}
+
+// retorna false se a tabela de filtros esta cheia
+#[allow(dead_code)]
+pub fn set_module_level(module: &'static str, level: LevelFilter) -> bool {
+    let mut filters = FILTERS.lock();
+    if let Some(filter) = filters.iter_mut().find(|(name, _)| *name == module) {
+        filter.1 = level;
+        return true;
+    }
+    filters.push((module, level)).is_ok()
+}
+
+#[allow(dead_code)]
+pub fn clear_module_level(module: &str) {
+    let mut filters = FILTERS.lock();
+    if let Some(index) = filters.iter().position(|(name, _)| *name == module) {
+        filters.swap_remove(index);
+    }
+}
+
+pub fn set_sinks(sinks: u8) {
+    SINKS.store(sinks, Ordering::Relaxed);
+}
+
+pub fn sinks() -> u8 {
+    SINKS.load(Ordering::Relaxed)
+}
+
+// manda o sink serial pra outra COM (ex: 3 pra deixar a COM1 livre pro shell)
+// retorna false se a porta nao existe ou nao respondeu
+#[allow(dead_code)]
+pub fn set_serial_port(number: usize) -> bool {
+    match crate::serial::port(number) {
+        Some(port) if port.lock().is_present() => {
+            SERIAL_PORT.store(number, Ordering::Relaxed);
+            true
+        }
+        _ => false,
+    }
+}
+
+pub fn serial_port() -> usize {
+    SERIAL_PORT.load(Ordering::Relaxed)
+}
+
+fn filter_from(value: usize) -> LevelFilter {
+    match value {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+// "os_project::serial" bate com "os_project::serial" e "os_project::serial::rx", nao com "os_project::serialx"
+fn matches(target: &str, module: &str) -> bool {
+    match target.strip_prefix(module) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
+
+fn level_for(target: &str) -> LevelFilter {
+    let filters = FILTERS.lock();
+    filters
+        .iter()
+        .filter(|(module, _)| matches(target, module))
+        .max_by_key(|(module, _)| module.len())
+        .map(|(_, level)| *level)
+        .unwrap_or_else(level)
+}
+
+fn color(level: Level) -> (Color, Option<Color>) {
+    let role = match level {
+        Level::Error => Role::Error,
+        Level::Warn => Role::Warning,
+        Level::Info => Role::Normal,
+        Level::Debug | Level::Trace => return (Color::DarkGray, None),
+    };
+    let color = theme::color(role);
+    (color.foreground(), Some(color.background()))
+}
+
+impl Log for KernelLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // a linha inteira sai sem interrupcao no meio (e sem travar num lock que o handler tambem quer)
+        let _irq = crate::cpu::IrqGuard::new();
+        let sinks = sinks();
+        let ticks = crate::time::ticks();
+        // "[     123] WARN  os_project::serial: mensagem"
+        if sinks & SINK_VGA != 0 {
+            let (foreground, background) = color(record.level());
+            crate::console::_print_colored(
+                foreground,
+                background,
+                format_args!("[{:>8}] {:<5} {}: {}\n", ticks, record.level(), record.target(), record.args()),
+            );
+        }
+        if sinks & SINK_DEBUGCON != 0 {
+            let _ = writeln!(
+                crate::debugcon::DEBUGCON.lock(),
+                "[{:>8}] {:<5} {}: {}",
+                ticks,
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+        // o sink do vga vai pra todos os consoles: se essa COM é um deles (ou a copia do mirror do vga),
+        // a linha já saiu nela -> nao escreve duas vezes
+        let port = crate::serial::port(serial_port());
+        let mirrored = sinks & SINK_VGA != 0 && port.is_some_and(|port| crate::console::reaches(port));
+        if sinks & SINK_SERIAL == 0 || mirrored {
+            return;
+        }
+        if let Some(port) = port {
+            let _ = writeln!(
+                port.lock(),
+                "[{:>8}] {:<5} {}: {}",
+                ticks,
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
@@ -2,20 +2,191 @@
 //isso aqui tem q adicionar pra linguagem nao compilar nenhuma biblioteca dela q tenha coisa do s.o.
 //dai por causa disso começa a dar erro
 #![no_main]
+#![feature(abi_x86_interrupt)]
+#![feature(alloc_error_handler)]
 // para dizer q usa o start c0
+mod acpi;
+mod allocator;
+mod apic;
+mod apic_timer;
+mod banner;
+mod cmdline;
+mod console;
+mod cp437;
+mod cpu;
+mod debugcon;
+mod deferred;
+mod dma;
+mod draw;
+mod framebuffer;
+mod gdbstub;
+mod gdt;
+mod graphics;
+mod hpet;
+mod interrupts;
+mod ioapic;
+mod irq_print;
+mod kaslr;
+mod keyboard;
+mod line_editor;
+mod logger;
+mod mce;
+mod memory;
+mod panic_screen;
+mod percpu;
+mod pic;
+mod progress_bar;
+mod rand;
+mod serial;
+mod serial_shell;
+mod shell;
+mod smp;
+mod status_bar;
+mod syscall;
+mod theme;
+mod time;
+mod tty;
 mod vga_buffer;
+mod vga_registers;
+mod vm;
+mod window;
+mod xmodem;
 
+use bootloader::{entry_point, BootInfo};
 use core::panic::PanicInfo;
 
+extern crate alloc;
+
 #[panic_handler]
-fn panic(_info: &PanicInfo) -> ! {
-    loop {}
+fn panic(info: &PanicInfo) -> ! {
+    panic_screen::show(info)
 }
 // ! is the "never" return
 
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
-    vga_buffer::print_something();
+// Box/Vec que nao coube no heap nem depois dos hooks de oom (allocator::oom) -> vira panic normal (tela de panic, serial)
+#[alloc_error_handler]
+fn alloc_error(layout: core::alloc::Layout) -> ! {
+    panic!(
+        "sem memoria no heap: {} bytes (align {}), {} vezes desde o boot",
+        layout.size(),
+        layout.align(),
+        allocator::oom::count()
+    )
+}
+
+entry_point!(kernel_main);
+
+fn kernel_main(boot_info: &'static BootInfo) -> ! {
+    memory::init(boot_info);
+    vm::init();
+    let heap = allocator::init_heap();
+    let wx_fixed = if heap.is_ok() { memory::enforce_wx() } else { 0 };
+    cpu::features(); // le o cpuid antes de qualquer driver perguntar
+    let fpu = cpu::fpu::init();
+    console::init();
+    gdt::init();
+    percpu::init(0);
+    interrupts::init_idt();
+    let syscall = syscall::init();
+    serial::init();
+    let controller = interrupts::init_controller();
+    let tsc_hz = time::calibrate_tsc();
+    // com o apic o tick vem do timer dele (calibrado pelo PIT); senao da irq 0 do PIT
+    if controller == interrupts::Controller::Apic && apic_timer::calibrate() != 0 {
+        apic_timer::start_periodic(time::HZ);
+    } else {
+        time::init();
+        interrupts::enable_irq(interrupts::InterruptIndex::Timer);
+    }
+    // os handlers só enfileiram, quem processa é o loop principal
+    deferred::register(deferred::Softirq::Keyboard, keyboard::poll);
+    deferred::register(deferred::Softirq::Serial, serial_shell::poll);
+    interrupts::enable_irq(interrupts::InterruptIndex::Keyboard);
+    interrupts::enable_irq(interrupts::InterruptIndex::Com1);
+    x86_64::instructions::interrupts::enable();
+    logger::init();
+    if debugcon::present() {
+        logger::set_sinks(logger::sinks() | logger::SINK_DEBUGCON);
+    }
+    banner::show_banner(
+        theme::color(theme::Role::Prompt),
+        theme::color(theme::Role::Normal),
+    );
+    if !serial::SERIAL1.lock().is_present() {
+        log::warn!("COM1 nao respondeu, sem saida serial");
+    }
+    log::info!("cpu {}", cpu::features());
+    if tsc_hz != 0 {
+        log::info!(
+            "TSC a {} MHz{}",
+            tsc_hz / 1_000_000,
+            if time::tsc_is_invariant() { "" } else { " (nao invariante)" }
+        );
+    }
+    log::debug!("numeros aleatorios via {:?}", rand::source());
+    if !syscall {
+        log::warn!("cpu sem syscall/sysret");
+    }
+    if !fpu {
+        log::warn!("cpu sem fxsave/sse2, tarefas nao podem usar ponto flutuante");
+    }
+    log::info!(
+        "memoria: {} KiB livres de {} KiB, tabelas de pagina {}",
+        memory::frame::free_frames() * 4,
+        memory::frame::total_frames() * 4,
+        memory::mapper::mode()
+    );
+    match heap {
+        Ok(()) => log::info!(
+            "heap de {} KiB em {:#x} ({})",
+            allocator::HEAP_SIZE / 1024,
+            allocator::heap_start().as_u64(),
+            allocator::name()
+        ),
+        Err(error) => log::error!("heap nao foi mapeado: {}", error),
+    }
+    if kaslr::enabled() {
+        log::info!(
+            "kaslr: memoria fisica em {:#x}, heap em {:#x}",
+            memory::physical_memory_offset().as_u64(),
+            allocator::heap_start().as_u64()
+        );
+    } else {
+        log::info!(
+            "kaslr desligado (kaslr=off), memoria fisica em {:#x}",
+            memory::physical_memory_offset().as_u64()
+        );
+    }
+    if memory::mapper::nx_enabled() {
+        log::info!("W^X: {} paginas do boot viraram NX", wx_fixed);
+    } else {
+        log::warn!("cpu sem NX, W^X nao tem como ser garantido");
+    }
+    log::info!("interrupcoes pelo {:?}", controller);
+    if mce::init() {
+        log::info!("machine check ligado, {} bancos", mce::bank_count());
+    }
+    if hpet::init() {
+        log::info!("HPET a {} Hz, {} comparadores", hpet::frequency(), hpet::timers());
+    }
+    let cpus = smp::init();
+    if cpus > 1 {
+        log::info!("{} cpus online", cpus);
+    }
+    for (number, port) in serial::enumerate() {
+        log::debug!("COM{} em {:#x}", number, port.lock().base());
+    }
+    println!("Hello World{}", "!");
+
+    // sem placa de video -> o shell vai pela serial
+    if vga_registers::present() {
+        shell::start();
+    } else {
+        serial_shell::set_enabled(true);
+    }
 
-    loop {}
+    cpu::hlt_loop(|| {
+        irq_print::drain();
+        deferred::run();
+    })
 }
\ No newline at end of file
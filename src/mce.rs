@@ -0,0 +1,190 @@
+// machine check (#MC, vetor 18): a cpu avisa de erro de hardware (memoria, cache, barramento)
+// sem CR4.MCE a cpu reinicia direto -> com ele a gente mostra o que os bancos MCi_STATUS dizem antes de parar
+use crate::cpu::msr;
+use core::fmt;
+use x86_64::registers::control::{Cr4, Cr4Flags};
+
+const IA32_MCG_CAP: u32 = 0x179;
+const IA32_MCG_STATUS: u32 = 0x17A;
+const IA32_MCG_CTL: u32 = 0x17B;
+// cada banco tem 4 msrs: CTL, STATUS, ADDR, MISC
+const IA32_MC0_CTL: u32 = 0x400;
+
+const MCG_CTL_PRESENT: u64 = 1 << 8;
+
+const MCG_RIPV: u64 = 1 << 0; // da pra continuar do RIP salvo
+const MCG_EIPV: u64 = 1 << 1; // o RIP salvo é da instrucao que deu o erro
+const MCG_MCIP: u64 = 1 << 2; // machine check em andamento
+
+const STATUS_VAL: u64 = 1 << 63;
+const STATUS_OVER: u64 = 1 << 62; // teve outro erro antes desse ser lido
+const STATUS_UC: u64 = 1 << 61; // nao corrigido
+const STATUS_EN: u64 = 1 << 60;
+const STATUS_MISCV: u64 = 1 << 59;
+const STATUS_ADDRV: u64 = 1 << 58;
+const STATUS_PCC: u64 = 1 << 57; // contexto do processador corrompido
+
+// (MCE: a excecao, MCA: os bancos)
+fn cpuid_flags() -> (bool, bool) {
+    let features = crate::cpu::features();
+    (features.mce, features.mca)
+}
+
+#[allow(dead_code)]
+pub fn is_supported() -> bool {
+    cpuid_flags().0
+}
+
+fn read(msr: u32) -> u64 {
+    unsafe { msr::read(msr) }
+}
+
+// numero de bancos (0 se a cpu nao tem MCA)
+pub fn bank_count() -> u32 {
+    if !cpuid_flags().1 {
+        return 0;
+    }
+    (read(IA32_MCG_CAP) & 0xff) as u32
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bank {
+    pub index: u32,
+    pub status: u64,
+    pub address: Option<u64>,
+    pub misc: Option<u64>,
+}
+
+impl Bank {
+    pub fn read(index: u32) -> Bank {
+        let base = IA32_MC0_CTL + 4 * index;
+        let status = read(base + 1);
+        Bank {
+            index,
+            status,
+            address: if status & STATUS_ADDRV != 0 { Some(read(base + 2)) } else { None },
+            misc: if status & STATUS_MISCV != 0 { Some(read(base + 3)) } else { None },
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.status & STATUS_VAL != 0
+    }
+
+    #[allow(dead_code)]
+    pub fn is_uncorrected(&self) -> bool {
+        self.status & STATUS_UC != 0
+    }
+
+    // codigo de erro da arquitetura (bits 0-15)
+    pub fn error_code(&self) -> u16 {
+        self.status as u16
+    }
+
+    fn clear(&self) {
+        unsafe { msr::write(IA32_MC0_CTL + 4 * self.index + 1, 0) };
+    }
+}
+
+// bancos com erro registrado
+pub fn pending() -> impl Iterator<Item = Bank> {
+    (0..bank_count()).map(Bank::read).filter(Bank::is_valid)
+}
+
+// classe do erro pelo codigo (manual da intel, vol 3 "MCA error codes"); o bit 12 é só filtro
+fn error_class(code: u16) -> &'static str {
+    let code = code & !(1 << 12);
+    match code {
+        0x0000 => "sem erro",
+        0x0001 => "nao classificado",
+        0x0002 => "paridade da rom do microcodigo",
+        0x0003 => "erro externo (outra cpu)",
+        0x0004 => "FRC",
+        0x0005 => "paridade interna",
+        0x0400..=0x04ff => "erro interno",
+        c if c & 0xfffc == 0x000c => "hierarquia de cache",
+        c if c & 0xfff0 == 0x0010 => "TLB",
+        c if c & 0xff80 == 0x0080 => "controlador de memoria",
+        c if c & 0xff00 == 0x0100 => "cache/memoria",
+        c if c & 0xf800 == 0x0800 => "barramento",
+        _ => "desconhecido",
+    }
+}
+
+impl fmt::Display for Bank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "banco {}: {} ({:#06x}) status {:#018x}",
+            self.index,
+            error_class(self.error_code()),
+            self.error_code(),
+            self.status
+        )?;
+        for (bit, name) in [(STATUS_UC, "UC"), (STATUS_PCC, "PCC"), (STATUS_OVER, "OVER"), (STATUS_EN, "EN")] {
+            if self.status & bit != 0 {
+                write!(f, " {}", name)?;
+            }
+        }
+        if let Some(address) = self.address {
+            write!(f, " addr {:#x}", address)?;
+        }
+        if let Some(misc) = self.misc {
+            write!(f, " misc {:#x}", misc)?;
+        }
+        Ok(())
+    }
+}
+
+// tudo que da pra dizer de dentro do handler (uma linha por banco)
+pub struct Report;
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = if cpuid_flags().1 { read(IA32_MCG_STATUS) } else { 0 };
+        write!(
+            f,
+            "MCG_STATUS {:#x} (RIPV {} EIPV {} MCIP {})",
+            status,
+            status & MCG_RIPV != 0,
+            status & MCG_EIPV != 0,
+            status & MCG_MCIP != 0
+        )?;
+        let mut any = false;
+        for bank in pending() {
+            write!(f, "\n  {}", bank)?;
+            any = true;
+        }
+        if !any {
+            f.write_str("\n  nenhum banco com erro valido")?;
+        }
+        Ok(())
+    }
+}
+
+// liga a reportagem em todos os bancos e o CR4.MCE
+// erros que ficaram de antes do reset (o bios nao limpa) sao logados e apagados
+pub fn init() -> bool {
+    let (mce, mca) = cpuid_flags();
+    if !mce {
+        return false;
+    }
+    if mca {
+        let capabilities = read(IA32_MCG_CAP);
+        if capabilities & MCG_CTL_PRESENT != 0 {
+            unsafe { msr::write(IA32_MCG_CTL, u64::MAX) };
+        }
+        for bank in (0..bank_count()).map(Bank::read) {
+            if bank.is_valid() {
+                log::warn!("machine check de antes do boot: {}", bank);
+                bank.clear();
+            }
+            // o banco 0 em algumas cpus antigas é do bios -> nao mexe no CTL dele
+            if bank.index > 0 {
+                unsafe { msr::write(IA32_MC0_CTL + 4 * bank.index, u64::MAX) };
+            }
+        }
+    }
+    unsafe { Cr4::update(|flags| flags.insert(Cr4Flags::MACHINE_CHECK_EXCEPTION)) };
+    true
+}
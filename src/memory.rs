@@ -0,0 +1,278 @@
+// memoria fisica: toda a ram fica mapeada a partir de um offset virtual -> endereco fisico + offset = endereco
+// virtual que da pra acessar (tabelas de pagina, acpi, buffers de dma...)
+// o bootloader entrega um mapa desses (feature map_physical_memory) num offset que ele escolhe; o init usa ele só
+// pra montar o do kernel em PHYSICAL_MAP_BASE (mais o deslocamento do kaslr), com paginas de 1 GiB/2 MiB,
+// e troca o offset. o do bootloader continua mapeado mas ninguem mais usa
+// o mapa vai ate o fim da ultima regiao do mapa de memoria, buracos inclusive (com cache): mmio la em cima
+// (apic, ioapic, hpet) fica de fora e o que cai num buraco fica com cache -> map_physical mapeia o que falta
+// e tira o cache do que já estava, sempre de 4 KiB em 4 KiB
+use bootloader::bootinfo::{MemoryMap, MemoryRegionType};
+use bootloader::BootInfo;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Once;
+use x86_64::structures::paging::{PageSize, PageTableFlags, Size2MiB, Size4KiB};
+use x86_64::{PhysAddr, VirtAddr};
+
+pub mod frame;
+pub mod guard;
+pub mod mapper;
+pub mod memtest;
+pub mod stack;
+
+#[allow(unused_imports)]
+pub use mapper::{translate_addr, with_mapper, MappedPages, Mapper, Mapping};
+
+static PHYSICAL_MEMORY_OFFSET: AtomicU64 = AtomicU64::new(0);
+static MEMORY_MAP: Once<&'static MemoryMap> = Once::new();
+
+pub fn init(boot_info: &'static BootInfo) {
+    PHYSICAL_MEMORY_OFFSET.store(boot_info.physical_memory_offset, Ordering::Relaxed);
+    MEMORY_MAP.call_once(|| &boot_info.memory_map);
+    frame::init(&boot_info.memory_map);
+    mapper::init(boot_info);
+    match map_all_physical() {
+        Some(offset) => {
+            PHYSICAL_MEMORY_OFFSET.store(offset.as_u64(), Ordering::Relaxed);
+            mapper::reload(boot_info);
+        }
+        None => log::warn!("memoria fisica ficou no offset do bootloader"),
+    }
+}
+
+// onde o kernel mapeia a memoria fisica (com kaslr, o comeco da janela onde o offset é sorteado)
+pub const PHYSICAL_MAP_BASE: u64 = 0xffff_a000_0000_0000;
+
+// ram (e os buracos no meio) ate o fim da ultima regiao do mapa
+fn physical_end() -> u64 {
+    memory_map().iter().map(|region| region.range.end_addr()).max().unwrap_or(0)
+}
+
+// mapeia a ram inteira em PHYSICAL_MAP_BASE (+ kaslr, alinhado em 1 GiB) e devolve o offset novo
+// None se nao deu (espaco ocupado, sem frame pras tabelas): fica o mapa do bootloader
+fn map_all_physical() -> Option<VirtAddr> {
+    let len = VirtAddr::new(physical_end()).align_up(Size2MiB::SIZE).as_u64();
+    let window = crate::kaslr::PHYSICAL_WINDOW;
+    let flags = PageTableFlags::PRESENT
+        | PageTableFlags::WRITABLE
+        | PageTableFlags::NO_EXECUTE
+        | PageTableFlags::GLOBAL
+        | PageTableFlags::HUGE_PAGE;
+    let tries = if crate::kaslr::enabled() { 8 } else { 1 };
+    for _ in 0..tries {
+        let offset = VirtAddr::new(window.start + crate::kaslr::slide(window.end - window.start - len, 1 << 30));
+        let mut used = false;
+        with_mapper(|mapper| mapper.walk(offset, offset + len, |_| used = true));
+        if used {
+            continue;
+        }
+        return with_mapper(|mapper| unsafe { mapper.map_range(offset, PhysAddr::zero(), len, flags) })
+            .ok()
+            .map(|_| offset);
+    }
+    None
+}
+
+// regioes de memoria fisica que o bootloader passou (usavel, kernel, tabelas de pagina...)
+pub fn memory_map() -> &'static MemoryMap {
+    MEMORY_MAP.wait().expect("memory::init nao foi chamado")
+}
+
+// `size` bytes livres e alinhados em pagina abaixo de 1 MiB (onde uma cpu em modo real alcanca)
+pub fn find_low_memory(size: u64) -> Option<PhysAddr> {
+    memory_map()
+        .iter()
+        .filter(|region| region.region_type == MemoryRegionType::Usable)
+        .map(|region| {
+            // a pagina 0 nunca (BIOS/IVT)
+            let start = region.range.start_addr().max(0x1000);
+            (start, region.range.end_addr().min(0x10_0000))
+        })
+        .find(|&(start, end)| start + size <= end)
+        .map(|(start, _)| PhysAddr::new(start))
+}
+
+pub fn physical_memory_offset() -> VirtAddr {
+    VirtAddr::new(PHYSICAL_MEMORY_OFFSET.load(Ordering::Relaxed))
+}
+
+// ex: registradores mmio do apic, tabelas acpi
+pub fn phys_to_virt(addr: PhysAddr) -> VirtAddr {
+    physical_memory_offset() + addr.as_u64()
+}
+
+// garante que [phys, phys + len) aparece no offset sem cache (é pra mmio): o que falta entra em paginas de 4 KiB,
+// o que já estava (o mapa grande cobre os buracos entre as regioes) perde o cache; a pagina grande em volta é
+// dividida pelo protect -> a ram vizinha continua com cache
+pub fn map_physical(phys: PhysAddr, len: u64) -> Result<(), mapper::Error> {
+    let flags = PageTableFlags::PRESENT
+        | PageTableFlags::WRITABLE
+        | PageTableFlags::NO_EXECUTE
+        | PageTableFlags::NO_CACHE
+        | PageTableFlags::WRITE_THROUGH;
+    let end = (phys + len).align_up(Size4KiB::SIZE);
+    let mut addr = phys.align_down(Size4KiB::SIZE);
+    with_mapper(|mapper| {
+        while addr < end {
+            let virt = phys_to_virt(addr);
+            match mapper.translate(virt) {
+                Some((_, current)) if current.contains(PageTableFlags::NO_CACHE) => {}
+                Some(_) => unsafe { mapper.protect(virt, virt + Size4KiB::SIZE, flags)? },
+                None => unsafe { mapper.map_range(virt, addr, Size4KiB::SIZE, flags)? },
+            }
+            addr += Size4KiB::SIZE;
+        }
+        Ok(())
+    })
+}
+
+// retrato do uso de memoria (frames em 4 KiB) -> comando meminfo, e pra comparar antes/depois e ver se vazou frame
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub total_frames: usize,  // ram que o mapa do bootloader mostra (inclui kernel, tabelas...)
+    pub usable_frames: usize, // o que o alocador de frames gerencia
+    pub used_frames: usize,   // desses, quantos estao alocados
+    pub heap: crate::allocator::HeapStats,
+    pub mapped: MappedPages,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "frames: {} total, {} usaveis, {} usados ({} KiB livres)",
+            self.total_frames,
+            self.usable_frames,
+            self.used_frames,
+            (self.usable_frames - self.used_frames) * 4
+        )?;
+        writeln!(
+            f,
+            "heap: {} de {} bytes alocados, {} livres, pico {}",
+            self.heap.allocated, self.heap.size, self.heap.free, self.heap.peak
+        )?;
+        write!(
+            f,
+            "mapeado: {} paginas de 4 KiB, {} de 2 MiB, {} de 1 GiB ({} MiB)",
+            self.mapped.small,
+            self.mapped.huge,
+            self.mapped.giant,
+            self.mapped.bytes() >> 20
+        )
+    }
+}
+
+pub fn stats() -> Stats {
+    let total = memory_map()
+        .iter()
+        .filter(|region| {
+            !matches!(
+                region.region_type,
+                MemoryRegionType::Reserved | MemoryRegionType::AcpiNvs | MemoryRegionType::BadMemory | MemoryRegionType::Empty
+            )
+        })
+        .map(|region| (region.range.end_addr() - region.range.start_addr()) / frame::FRAME_SIZE)
+        .sum::<u64>();
+    let usable = frame::total_frames();
+    Stats {
+        total_frames: total as usize,
+        usable_frames: usable,
+        used_frames: usable - frame::free_frames(),
+        heap: crate::allocator::stats(),
+        mapped: with_mapper(|mapper| mapper.mapped_pages()),
+    }
+}
+
+// tabela das paginas mapeadas em `range` (pra tela ou serial, sem precisar decodificar o CR3 no gdb)
+// paginas seguidas, com o fisico seguido e as mesmas flags, viram uma linha só: "4K x 16"
+// flags: w (escrita) u (ring 3) x (executavel) g (global) c (sem cache)
+pub fn dump_mapping(range: Range<VirtAddr>, out: &mut dyn Write) -> fmt::Result {
+    writeln!(out, "{:<18} {:<18} {:<10} flags", "virtual", "fisico", "paginas")?;
+    let mut result = Ok(());
+    let mut run: Option<(Mapping, u64)> = None; // primeira pagina da sequencia e quantas
+    with_mapper(|mapper| {
+        mapper.walk(range.start, range.end, |mapping| {
+            if let Some((first, count)) = &mut run {
+                let length = first.size * *count;
+                if first.size == mapping.size
+                    && first.flags == mapping.flags
+                    && first.virt + length == mapping.virt
+                    && first.phys + length == mapping.phys
+                {
+                    *count += 1;
+                    return;
+                }
+            }
+            if let Some((first, count)) = run.replace((mapping, 1)) {
+                result = result.and_then(|_| dump_row(out, &first, count));
+            }
+        })
+    });
+    match run {
+        Some((first, count)) => result.and_then(|_| dump_row(out, &first, count)),
+        None => result.and_then(|_| writeln!(out, "nada mapeado")),
+    }
+}
+
+fn dump_row(out: &mut dyn Write, first: &Mapping, count: u64) -> fmt::Result {
+    let size = match first.size {
+        0x1000 => "4K",
+        0x20_0000 => "2M",
+        _ => "1G",
+    };
+    let flag = |flag: PageTableFlags, set: char| if first.flags.contains(flag) { set } else { '-' };
+    write!(out, "{:#018x} {:#018x} {} x {:<5} ", first.virt.as_u64(), first.phys.as_u64(), size, count)?;
+    out.write_char(flag(PageTableFlags::WRITABLE, 'w'))?;
+    out.write_char(flag(PageTableFlags::USER_ACCESSIBLE, 'u'))?;
+    out.write_char(if first.flags.contains(PageTableFlags::NO_EXECUTE) { '-' } else { 'x' })?;
+    out.write_char(flag(PageTableFlags::GLOBAL, 'g'))?;
+    out.write_char(flag(PageTableFlags::NO_CACHE, 'c'))?;
+    writeln!(out)
+}
+
+// troca as flags de tudo que esta mapeado em `range` (ex: tirar WRITABLE de uma tabela depois de montada)
+// escrevivel sem NO_EXECUTE da panic (W^X)
+pub fn protect(range: Range<VirtAddr>, flags: PageTableFlags) -> Result<(), mapper::Error> {
+    with_mapper(|mapper| unsafe { mapper.protect(range.start, range.end, flags) })
+}
+
+// W^X no que já estava mapeado antes do kernel: o bootloader deixa a stack do boot, o boot info e o mapa da
+// memoria fisica escreviveis e executaveis -> tudo isso vira NO_EXECUTE. o texto do kernel ele já mapeia só leitura
+// (segmento R+X do elf); se o codigo estiver num pedaco escrevivel nao tem o que fazer sem quebrar -> panic
+// devolve quantas paginas mudaram; depois do heap (usa Vec)
+pub fn enforce_wx() -> usize {
+    if !mapper::nx_enabled() {
+        return 0;
+    }
+    let wx = |flags: PageTableFlags| flags.contains(PageTableFlags::WRITABLE) && !flags.contains(PageTableFlags::NO_EXECUTE);
+    let everything = VirtAddr::zero()..VirtAddr::new(u64::MAX);
+    // as tabelas nao podem mudar no meio do walk -> junta primeiro
+    let mut found = Vec::new();
+    with_mapper(|mapper| {
+        mapper.walk(everything.start, everything.end, |mapping| {
+            if wx(mapping.flags) {
+                found.push(mapping);
+            }
+        })
+    });
+    let code = VirtAddr::new(enforce_wx as *const () as u64);
+    for mapping in &found {
+        let end = mapping.virt + mapping.size;
+        if (mapping.virt..end).contains(&code) {
+            panic!("W^X: o codigo do kernel em {:#x} esta numa pagina escrevivel", mapping.virt.as_u64());
+        }
+        if let Err(error) = protect(mapping.virt..end, mapping.flags | PageTableFlags::NO_EXECUTE) {
+            panic!("W^X: nao deu pra tirar o executavel de {:#x}: {}", mapping.virt.as_u64(), error);
+        }
+    }
+    with_mapper(|mapper| {
+        mapper.walk(everything.start, everything.end, |mapping| {
+            if wx(mapping.flags) {
+                panic!("W^X: {:#x} continua escrevivel e executavel", mapping.virt.as_u64());
+            }
+        })
+    });
+    found.len()
+}
@@ -0,0 +1,291 @@
+// alocador de frames fisicos (4 KiB) montado a partir do mapa de memoria do bootloader
+// sistema buddy: blocos de 2^ordem frames (ordem 0 = 1 frame ... ordem 10 = 4 MiB), sempre alinhados no proprio tamanho
+// o "buddy" de um bloco é o vizinho com quem ele forma o bloco da ordem de cima -> no free, se o buddy tambem
+// esta livre os dois juntam (e assim por diante), entao memoria liberada volta a ser contigua
+// um bitmap por ordem (bit = bloco livre) num array estatico: sem heap e nada escrito na memoria livre
+// o que o bootloader marca como kernel, tabelas de pagina ou bootinfo nao é Usable -> nunca entra
+// abaixo de 1 MiB fica reservado (bios, trampolim do smp, dma antigo)
+use crate::cpu;
+use bootloader::bootinfo::{MemoryMap, MemoryRegionType};
+use core::sync::atomic::{AtomicU16, Ordering};
+use spin::Mutex;
+use x86_64::structures::paging::{FrameAllocator, FrameDeallocator, PhysFrame, Size4KiB};
+use x86_64::PhysAddr;
+
+pub const FRAME_SIZE: u64 = 4096;
+// memoria acima disso é ignorada
+pub const MAX_MEMORY: u64 = 4 << 30;
+pub const MAX_ORDER: usize = 10;
+pub const ORDERS: usize = MAX_ORDER + 1;
+const MAX_FRAMES: usize = (MAX_MEMORY / FRAME_SIZE) as usize;
+const RESERVED_LOW: u64 = 0x10_0000;
+
+// os bitmaps de todas as ordens um atras do outro: a ordem k tem MAX_FRAMES >> k bits
+const fn bitmap_offset(order: usize) -> usize {
+    let mut offset = 0;
+    let mut k = 0;
+    while k < order {
+        offset += (MAX_FRAMES >> k) / 64;
+        k += 1;
+    }
+    offset
+}
+
+const BITMAP_WORDS: usize = bitmap_offset(ORDERS);
+const OFFSETS: [usize; ORDERS] = {
+    let mut offsets = [0; ORDERS];
+    let mut order = 0;
+    while order < ORDERS {
+        offsets[order] = bitmap_offset(order);
+        order += 1;
+    }
+    offsets
+};
+
+struct Buddy {
+    words: [u64; BITMAP_WORDS],
+    hints: [usize; ORDERS], // palavra mais baixa de cada ordem que pode ter bit livre
+    managed: [u64; MAX_FRAMES / 64], // bit = frame que veio de uma regiao Usable (só esses podem ser liberados)
+    free: usize,            // em frames
+    total: usize,
+}
+
+impl Buddy {
+    fn bit(&self, order: usize, block: usize) -> bool {
+        self.words[OFFSETS[order] + block / 64] & (1 << (block % 64)) != 0
+    }
+
+    fn set(&mut self, order: usize, block: usize) {
+        self.words[OFFSETS[order] + block / 64] |= 1 << (block % 64);
+        self.hints[order] = self.hints[order].min(block / 64);
+    }
+
+    fn clear(&mut self, order: usize, block: usize) {
+        self.words[OFFSETS[order] + block / 64] &= !(1 << (block % 64));
+    }
+
+    fn is_managed(&self, frame: usize) -> bool {
+        self.managed[frame / 64] & (1 << (frame % 64)) != 0
+    }
+
+    // algum bloco livre (de qualquer ordem) cobre esse frame?
+    fn covers(&self, frame: usize) -> bool {
+        (0..ORDERS).any(|order| self.bit(order, frame >> order))
+    }
+
+    // primeiro bloco livre dessa ordem (a partir da dica), ainda no bitmap
+    fn lowest(&mut self, order: usize) -> Option<usize> {
+        let base = OFFSETS[order];
+        let words = (MAX_FRAMES >> order) / 64;
+        let start = self.hints[order];
+        let found = (start..words).find(|&word| self.words[base + word] != 0);
+        self.hints[order] = found.unwrap_or(words);
+        let word = found?;
+        Some(word * 64 + self.words[base + word].trailing_zeros() as usize)
+    }
+
+    // devolve o primeiro frame de um bloco de 2^order frames
+    fn allocate(&mut self, order: usize) -> Option<usize> {
+        self.allocate_below(order, MAX_FRAMES)
+    }
+
+    // o mesmo, mas o bloco inteiro antes do frame `limit`. o bloco maior quebrado sempre entrega o pedaco de
+    // baixo -> basta o mais baixo livre de cada ordem: se nem ele serve, nenhum outro daquela ordem serve
+    fn allocate_below(&mut self, order: usize, limit: usize) -> Option<usize> {
+        let (mut current, mut block) = (order..ORDERS).find_map(|k| {
+            let block = self.lowest(k)?;
+            ((block << k) + (1 << order) <= limit).then_some((k, block))
+        })?;
+        self.clear(current, block);
+        // quebra ate a ordem pedida: a metade de cima de cada pedaco fica livre
+        while current > order {
+            current -= 1;
+            block *= 2;
+            self.set(current, block + 1);
+        }
+        self.free -= 1 << order;
+        Some(block << order)
+    }
+
+    // false se o bloco (ou parte dele) já estava livre ou esta fora do alocador (kernel, tabelas do bootloader,
+    // reservado, mmio: tudo que nao veio de regiao Usable)
+    fn deallocate(&mut self, frame: usize, order: usize) -> bool {
+        if order > MAX_ORDER || frame + (1 << order) > MAX_FRAMES || !frame.is_multiple_of(1 << order) {
+            return false;
+        }
+        if (frame..frame + (1 << order)).any(|f| !self.is_managed(f) || self.covers(f)) {
+            return false;
+        }
+        self.free += 1 << order;
+        self.insert(frame, order);
+        true
+    }
+
+    // marca livre juntando com os buddies livres
+    fn insert(&mut self, frame: usize, order: usize) {
+        let mut block = frame >> order;
+        let mut order = order;
+        while order < MAX_ORDER && self.bit(order, block ^ 1) {
+            self.clear(order, block ^ 1);
+            block /= 2;
+            order += 1;
+        }
+        self.set(order, block);
+    }
+
+    #[allow(dead_code)]
+    fn free_blocks(&self, order: usize) -> usize {
+        let base = OFFSETS[order];
+        let words = (MAX_FRAMES >> order) / 64;
+        self.words[base..base + words].iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+static BUDDY: Mutex<Buddy> = Mutex::new(Buddy {
+    words: [0; BITMAP_WORDS],
+    hints: [0; ORDERS],
+    managed: [0; MAX_FRAMES / 64],
+    free: 0,
+    total: 0,
+});
+
+// entrega cada regiao Usable em blocos o maior possivel; chamado pelo memory::init
+pub fn init(memory_map: &MemoryMap) {
+    let mut buddy = BUDDY.lock();
+    let usable = memory_map.iter().filter(|region| region.region_type == MemoryRegionType::Usable);
+    for region in usable {
+        let start = region.range.start_addr().max(RESERVED_LOW);
+        let end = region.range.end_addr().min(MAX_MEMORY);
+        // só frames inteiros
+        let mut frame = start.div_ceil(FRAME_SIZE) as usize;
+        let last = (end / FRAME_SIZE) as usize;
+        while frame < last {
+            let mut order = MAX_ORDER;
+            while !frame.is_multiple_of(1 << order) || frame + (1 << order) > last {
+                order -= 1;
+            }
+            buddy.insert(frame, order);
+            for managed in frame..frame + (1 << order) {
+                buddy.managed[managed / 64] |= 1 << (managed % 64);
+            }
+            buddy.free += 1 << order;
+            buddy.total += 1 << order;
+            frame += 1 << order;
+        }
+    }
+}
+
+fn to_frame(index: usize) -> PhysFrame {
+    PhysFrame::containing_address(PhysAddr::new(index as u64 * FRAME_SIZE))
+}
+
+fn to_index(frame: PhysFrame) -> usize {
+    (frame.start_address().as_u64() / FRAME_SIZE) as usize
+}
+
+pub fn allocate_frame() -> Option<PhysFrame> {
+    allocate_contiguous(0)
+}
+
+// 2^order frames fisicamente seguidos, o primeiro alinhado em 2^order frames (ex: dma, paginas de 2 MiB)
+pub fn allocate_contiguous(order: usize) -> Option<PhysFrame> {
+    if order > MAX_ORDER {
+        return None;
+    }
+    cpu::without_interrupts(|| BUDDY.lock().allocate(order)).map(to_frame)
+}
+
+// o mesmo, mas o bloco inteiro abaixo do endereco `limit` (dispositivo que nao enxerga a memoria toda)
+#[allow(dead_code)]
+pub fn allocate_contiguous_below(order: usize, limit: u64) -> Option<PhysFrame> {
+    if order > MAX_ORDER {
+        return None;
+    }
+    let limit = (limit.min(MAX_MEMORY) / FRAME_SIZE) as usize;
+    cpu::without_interrupts(|| BUDDY.lock().allocate_below(order, limit)).map(to_frame)
+}
+
+// false se o frame já estava livre (double free) ou nao é do alocador (fora das regioes Usable)
+pub fn deallocate_frame(frame: PhysFrame) -> bool {
+    deallocate_contiguous(frame, 0)
+}
+
+// tem que ser a mesma ordem do allocate_contiguous
+pub fn deallocate_contiguous(frame: PhysFrame, order: usize) -> bool {
+    cpu::without_interrupts(|| BUDDY.lock().deallocate(to_index(frame), order))
+}
+
+// frames livres agora
+pub fn free_frames() -> usize {
+    cpu::without_interrupts(|| BUDDY.lock().free)
+}
+
+// frames que o alocador gerencia (livres + usados)
+pub fn total_frames() -> usize {
+    cpu::without_interrupts(|| BUDDY.lock().total)
+}
+
+// quantos blocos livres de cada ordem (mostra a fragmentacao)
+#[allow(dead_code)]
+pub fn free_blocks() -> [usize; ORDERS] {
+    cpu::without_interrupts(|| {
+        let buddy = BUDDY.lock();
+        core::array::from_fn(|order| buddy.free_blocks(order))
+    })
+}
+
+// contagem de referencias de frames compartilhados (copy-on-write): uma entrada por frame num array estatico
+// (sem heap, da pra usar dentro da page fault) guardando os donos *alem* do primeiro -> 0 = um dono só.
+// se encher (u16::MAX) a contagem trava ali e o frame nunca mais volta: vaza, mas nao é liberado em uso
+static EXTRA_REFS: [AtomicU16; MAX_FRAMES] = [const { AtomicU16::new(0) }; MAX_FRAMES];
+
+fn extra_refs(frame: PhysFrame) -> Option<&'static AtomicU16> {
+    EXTRA_REFS.get((frame.start_address().as_u64() / FRAME_SIZE) as usize)
+}
+
+// mais um dono pro frame
+pub fn add_ref(frame: PhysFrame) {
+    if let Some(refs) = extra_refs(frame) {
+        let _ = refs.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| count.checked_add(1));
+    }
+}
+
+// quantos donos o frame tem (1 se nunca foi compartilhado)
+pub fn ref_count(frame: PhysFrame) -> usize {
+    extra_refs(frame).map_or(1, |refs| usize::from(refs.load(Ordering::Acquire)) + 1)
+}
+
+// um dono a menos; o ultimo devolve o frame pro alocador. devolve quantos donos sobraram
+pub fn release(frame: PhysFrame) -> usize {
+    let previous = extra_refs(frame).map_or(0, |refs| {
+        match refs.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| match count {
+            0 | u16::MAX => None,
+            count => Some(count - 1),
+        }) {
+            Ok(count) | Err(count) => count,
+        }
+    });
+    match previous {
+        0 => {
+            deallocate_frame(frame);
+            0
+        }
+        u16::MAX => usize::from(u16::MAX) + 1,
+        count => usize::from(count),
+    }
+}
+
+// pra passar pro x86_64 (ex: Mapper::map_to precisa de um FrameAllocator pras tabelas novas)
+pub struct GlobalFrameAllocator;
+
+unsafe impl FrameAllocator<Size4KiB> for GlobalFrameAllocator {
+    fn allocate_frame(&mut self) -> Option<PhysFrame> {
+        allocate_frame()
+    }
+}
+
+impl FrameDeallocator<Size4KiB> for GlobalFrameAllocator {
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame) {
+        deallocate_frame(frame);
+    }
+}
@@ -0,0 +1,56 @@
+// guard pages: uma pagina sem mapeamento logo abaixo de cada stack do kernel
+// (as stacks saem do memory::stack, que já deixa a guard)
+// sem ela um estouro de stack escreve por cima do que estiver embaixo (outra stack, um static) sem ninguem ver
+// com ela vira page fault no endereco da guard -> o handler (ou o double fault, se a cpu nem conseguiu
+// empilhar o frame) acha a guard aqui e mostra de quem é a stack em vez de um fault sem contexto
+use super::mapper;
+use core::fmt;
+use spin::Mutex;
+use x86_64::structures::paging::{Page, Size4KiB};
+use x86_64::VirtAddr;
+
+const MAX_GUARDS: usize = 64;
+
+// a stack de quem a guard protege, ex: "privilege" da cpu 1 ou "task" 7
+#[derive(Debug, Clone, Copy)]
+pub struct Guard {
+    page: Page<Size4KiB>,
+    pub owner: &'static str,
+    pub id: usize,
+}
+
+impl fmt::Display for Guard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} (guard em {:#x})", self.owner, self.id, self.page.start_address().as_u64())
+    }
+}
+
+static GUARDS: Mutex<heapless::Vec<Guard, MAX_GUARDS>> = Mutex::new(heapless::Vec::new());
+
+// lembra de quem é a pagina (que já tem que estar sem mapeamento, ex: a de baixo de uma memory::stack)
+pub fn register(page: Page<Size4KiB>, owner: &'static str, id: usize) {
+    let guard = Guard { page, owner, id };
+    if crate::cpu::without_interrupts(|| GUARDS.lock().push(guard)).is_err() {
+        log::warn!("sem espaco pra lembrar da guard page de {}", guard);
+    }
+}
+
+// tira o mapeamento da pagina e registra
+// o frame que estava la fica perdido (é do .bss ou de quem alocou) -> nao é devolvido
+#[allow(dead_code)]
+pub fn protect(page: Page<Size4KiB>, owner: &'static str, id: usize) -> Result<(), mapper::Error> {
+    super::with_mapper(|mapper| mapper.unmap(page))?;
+    register(page, owner, id);
+    Ok(())
+}
+
+// esquece a guard (a pagina continua sem mapeamento, quem chamou decide o que fazer com ela)
+pub fn release(page: Page<Size4KiB>) {
+    crate::cpu::without_interrupts(|| GUARDS.lock().retain(|guard| guard.page != page));
+}
+
+// de dentro do page fault/double fault: try_lock pq o fault pode ter vindo com o lock pego
+pub fn find(address: VirtAddr) -> Option<Guard> {
+    let page = Page::<Size4KiB>::containing_address(address);
+    GUARDS.try_lock()?.iter().find(|guard| guard.page == page).copied()
+}
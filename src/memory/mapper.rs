@@ -0,0 +1,553 @@
+// tabelas de pagina ativas (CR3), mexidas pelo offset da memoria fisica: cada tabela é um frame -> phys_to_virt
+// com a feature recursive_page_table o bootloader aponta uma entrada da P4 pra propria P4 e as tabelas passam a ser
+// acessadas por ela (indices r,r,r,r = a P4, r,r,r,i = a P3 da entrada i...), sem depender do offset
+// W^X: o mapper nao aceita pagina escrevivel e executavel ao mesmo tempo (panic), quem mapeia dados poe NO_EXECUTE
+// no recursivo só as tabelas saem do offset: o resto do kernel (frames zerados, dma, acpi, mmio) ainda usa o mapa
+// fisico em PHYSICAL_MAP_BASE, entao ele continua montado inteiro e o map_physical_memory do bootloader ligado
+// tabelas intermediarias novas saem do alocador de frames
+// obs: o flush do TLB é só da cpu atual; quando os APs rodarem codigo do kernel o unmap vai precisar de shootdown
+use super::frame::{self, GlobalFrameAllocator};
+use crate::cpu::{self, msr};
+use bootloader::BootInfo;
+use core::fmt;
+use spin::{Mutex, Once};
+#[cfg(not(feature = "recursive_page_table"))]
+use x86_64::registers::control::Cr3;
+use x86_64::structures::paging::mapper::{FlagUpdateError, MapToError, MappedFrame, TranslateResult, UnmapError};
+use x86_64::structures::paging::page_table::PageTableEntry;
+#[cfg(feature = "recursive_page_table")]
+use x86_64::structures::paging::RecursivePageTable;
+#[cfg(not(feature = "recursive_page_table"))]
+use x86_64::structures::paging::OffsetPageTable;
+use x86_64::structures::paging::{
+    Mapper as _, Page, PageSize, PageTable, PageTableFlags, PhysFrame, Size1GiB, Size2MiB, Size4KiB, Translate,
+};
+use x86_64::{PhysAddr, VirtAddr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    OutOfFrames,   // sem frame pra uma tabela intermediaria
+    AlreadyMapped, // a pagina já aponta pra algum frame
+    NotMapped,
+    HugePage,     // o endereco cai dentro de uma pagina de 2 MiB/1 GiB
+    InvalidFrame, // a entrada aponta pra um endereco fisico invalido
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Error::OutOfFrames => "sem frames livres",
+            Error::AlreadyMapped => "pagina ja mapeada",
+            Error::NotMapped => "pagina nao mapeada",
+            Error::HugePage => "dentro de uma pagina grande",
+            Error::InvalidFrame => "frame invalido",
+        })
+    }
+}
+
+impl<S: PageSize> From<MapToError<S>> for Error {
+    fn from(error: MapToError<S>) -> Error {
+        match error {
+            MapToError::FrameAllocationFailed => Error::OutOfFrames,
+            MapToError::ParentEntryHugePage => Error::HugePage,
+            MapToError::PageAlreadyMapped(_) => Error::AlreadyMapped,
+        }
+    }
+}
+
+impl From<UnmapError> for Error {
+    fn from(error: UnmapError) -> Error {
+        match error {
+            UnmapError::ParentEntryHugePage => Error::HugePage,
+            UnmapError::PageNotMapped => Error::NotMapped,
+            UnmapError::InvalidFrameAddress(_) => Error::InvalidFrame,
+        }
+    }
+}
+
+impl From<FlagUpdateError> for Error {
+    fn from(error: FlagUpdateError) -> Error {
+        match error {
+            FlagUpdateError::PageNotMapped => Error::NotMapped,
+            FlagUpdateError::ParentEntryHugePage => Error::HugePage,
+        }
+    }
+}
+
+// paginas mapeadas por tamanho
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MappedPages {
+    pub small: usize, // 4 KiB
+    pub huge: usize,  // 2 MiB
+    pub giant: usize, // 1 GiB
+}
+
+impl MappedPages {
+    // quanto tudo isso cobre em bytes
+    pub fn bytes(&self) -> u64 {
+        self.small as u64 * Size4KiB::SIZE + self.huge as u64 * Size2MiB::SIZE + self.giant as u64 * (1 << 30)
+    }
+}
+
+// uma entrada final das tabelas: a pagina (de qualquer tamanho) que comeca em `virt`
+#[derive(Debug, Clone, Copy)]
+pub struct Mapping {
+    pub virt: VirtAddr,
+    pub phys: PhysAddr,
+    pub size: u64,
+    pub flags: PageTableFlags,
+}
+
+// enderecos nao canonicos (bits 47..63 diferentes) nao existem nas tabelas
+const HOLE_START: u64 = 0x0000_8000_0000_0000;
+const HOLE_END: u64 = 0xffff_8000_0000_0000;
+
+// comeco da proxima pagina de `size` depois de `addr` (None no fim do espaco)
+fn next_boundary(addr: u64, size: u64) -> Option<u64> {
+    (addr & !(size - 1)).checked_add(size)
+}
+
+#[cfg(not(feature = "recursive_page_table"))]
+type Table = OffsetPageTable<'static>;
+#[cfg(feature = "recursive_page_table")]
+type Table = RecursivePageTable<'static>;
+
+pub struct Mapper {
+    table: Table,
+}
+
+impl Mapper {
+    // unsafe: só pode existir um Mapper por tabela, e o offset tem que mapear a memoria fisica toda
+    #[cfg(not(feature = "recursive_page_table"))]
+    unsafe fn active(_boot_info: &BootInfo) -> Mapper {
+        let (frame, _) = Cr3::read();
+        let table = &mut *super::phys_to_virt(frame.start_address()).as_mut_ptr::<PageTable>();
+        Mapper {
+            table: OffsetPageTable::new(table, super::physical_memory_offset()),
+        }
+    }
+
+    // unsafe: só pode existir um Mapper por tabela, e a entrada recursiva tem que estar no lugar
+    #[cfg(feature = "recursive_page_table")]
+    unsafe fn active(boot_info: &BootInfo) -> Mapper {
+        let table = &mut *(boot_info.recursive_page_table_addr as *mut PageTable);
+        Mapper {
+            table: RecursivePageTable::new(table).expect("P4 sem a entrada recursiva"),
+        }
+    }
+
+    // a tabela no fim do caminho (indices a partir da P4: [] = P4, [i] = P3 da entrada i, [i, j] = P2...)
+    // quem chama garante que as entradas do caminho estao presentes e nao sao paginas grandes
+    #[cfg(not(feature = "recursive_page_table"))]
+    fn table_ptr(&self, path: &[usize]) -> *mut PageTable {
+        let offset = self.table.phys_offset();
+        let mut table = self.table.level_4_table() as *const PageTable as *mut PageTable;
+        for &index in path {
+            let addr = unsafe { (&*table)[index].addr() };
+            table = (offset + addr.as_u64()).as_mut_ptr();
+        }
+        table
+    }
+
+    #[cfg(feature = "recursive_page_table")]
+    fn table_ptr(&self, path: &[usize]) -> *mut PageTable {
+        // cada indice recursivo na frente "sobe" um nivel
+        let recursive = self.recursive_index();
+        let mut indices = [recursive; 4];
+        indices[4 - path.len()..].copy_from_slice(path);
+        let addr = indices.iter().fold(0u64, |addr, &index| (addr << 9) | index as u64) << 12;
+        VirtAddr::new_truncate(addr).as_mut_ptr()
+    }
+
+    #[cfg(feature = "recursive_page_table")]
+    fn recursive_index(&self) -> usize {
+        let addr = self.table.level_4_table() as *const PageTable as u64;
+        ((addr >> 12) & 511) as usize
+    }
+
+    // unsafe: a referencia nao segura o lock nem a tabela (quem chama nao pode guardar nem ter duas da mesma)
+    unsafe fn table(&self, path: &[usize]) -> &'static mut PageTable {
+        &mut *self.table_ptr(path)
+    }
+
+    // a entrada da P4 que aponta pra propria P4 nao é mapeamento de verdade -> fica fora das contas
+    fn is_recursive_entry(&self, _index: usize) -> bool {
+        #[cfg(feature = "recursive_page_table")]
+        return _index == self.recursive_index();
+        #[cfg(not(feature = "recursive_page_table"))]
+        false
+    }
+
+    // pendura `table_frame` na entrada `index` da tabela no fim de `path` com as entradas que `entry` devolve
+    // no offset a tabela nova é preenchida antes de entrar no caminho; no recursivo ela só tem endereco depois de
+    // pendurada -> entra primeiro e é preenchida pelo endereco recursivo (o Mapper só roda sem interrupcao, mas
+    // quem chama nao pode estar usando a faixa que ela cobre ate o flush)
+    unsafe fn install_table<F: Fn(usize) -> (PhysAddr, PageTableFlags)>(
+        &mut self,
+        path: &[usize],
+        index: usize,
+        table_frame: PhysFrame,
+        parent: PageTableFlags,
+        entry: F,
+    ) {
+        #[cfg(not(feature = "recursive_page_table"))]
+        {
+            let table = &mut *super::phys_to_virt(table_frame.start_address()).as_mut_ptr::<PageTable>();
+            for (i, new) in table.iter_mut().enumerate() {
+                let (addr, flags) = entry(i);
+                new.set_addr(addr, flags);
+            }
+            self.table(path)[index].set_addr(table_frame.start_address(), parent);
+        }
+        #[cfg(feature = "recursive_page_table")]
+        {
+            let mut full = [0; 4];
+            full[..path.len()].copy_from_slice(path);
+            full[path.len()] = index;
+            self.table(path)[index].set_addr(table_frame.start_address(), parent);
+            // o endereco recursivo pode ter ficado no TLB apontando pro que estava na entrada antes
+            let table = self.table_ptr(&full[..=path.len()]);
+            x86_64::instructions::tlb::flush(VirtAddr::from_ptr(table));
+            for (i, new) in (&mut *table).iter_mut().enumerate() {
+                let (addr, flags) = entry(i);
+                new.set_addr(addr, flags);
+            }
+        }
+    }
+
+    // unsafe: mapear um frame que outra coisa usa (ou outra pagina já aponta) quebra as garantias do rust
+    pub unsafe fn map_page(&mut self, page: Page, frame: PhysFrame, flags: PageTableFlags) -> Result<(), Error> {
+        // as tabelas do caminho precisam deixar passar o que a pagina pede (ring 3 principalmente)
+        let parent = (flags & PageTableFlags::USER_ACCESSIBLE) | PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
+        let flags = check_wx(flags);
+        self.table
+            .map_to_with_table_flags(page, frame, flags, parent, &mut GlobalFrameAllocator)?
+            .flush();
+        Ok(())
+    }
+
+    // pagina de 2 MiB: uma entrada na P2 em vez de uma tabela P1 inteira -> 512x menos entradas no TLB
+    pub unsafe fn map_huge_page(
+        &mut self,
+        page: Page<Size2MiB>,
+        frame: PhysFrame<Size2MiB>,
+        flags: PageTableFlags,
+    ) -> Result<(), Error> {
+        let parent = (flags & PageTableFlags::USER_ACCESSIBLE) | PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
+        let flags = check_wx(flags - PageTableFlags::HUGE_PAGE); // o map_to poe o HUGE_PAGE
+        self.table
+            .map_to_with_table_flags(page, frame, flags, parent, &mut GlobalFrameAllocator)?
+            .flush();
+        Ok(())
+    }
+
+    // pagina de 1 GiB: uma entrada na P3 (só com features().page_1gb)
+    pub unsafe fn map_giant_page(
+        &mut self,
+        page: Page<Size1GiB>,
+        frame: PhysFrame<Size1GiB>,
+        flags: PageTableFlags,
+    ) -> Result<(), Error> {
+        let parent = (flags & PageTableFlags::USER_ACCESSIBLE) | PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
+        let flags = check_wx(flags - PageTableFlags::HUGE_PAGE);
+        self.table
+            .map_to_with_table_flags(page, frame, flags, parent, &mut GlobalFrameAllocator)?
+            .flush();
+        Ok(())
+    }
+
+    // `len` bytes a partir de `start` apontando pra `phys` em diante
+    // com HUGE_PAGE nas flags usa paginas de 1 GiB/2 MiB onde a cpu, os dois enderecos e o que falta deixarem
+    pub unsafe fn map_range(
+        &mut self,
+        start: VirtAddr,
+        phys: PhysAddr,
+        len: u64,
+        flags: PageTableFlags,
+    ) -> Result<(), Error> {
+        let huge = flags.contains(PageTableFlags::HUGE_PAGE) && huge_pages_supported();
+        let giant = huge && cpu::features().page_1gb;
+        let flags = flags - PageTableFlags::HUGE_PAGE;
+        let mut offset = 0;
+        while offset < len {
+            let (virt, physical) = (start + offset, phys + offset);
+            if giant && virt.is_aligned(Size1GiB::SIZE) && physical.is_aligned(Size1GiB::SIZE) && len - offset >= Size1GiB::SIZE {
+                self.map_giant_page(Page::containing_address(virt), PhysFrame::containing_address(physical), flags)?;
+                offset += Size1GiB::SIZE;
+            } else if huge && virt.is_aligned(Size2MiB::SIZE) && physical.is_aligned(Size2MiB::SIZE) && len - offset >= Size2MiB::SIZE {
+                self.map_huge_page(Page::containing_address(virt), PhysFrame::containing_address(physical), flags)?;
+                offset += Size2MiB::SIZE;
+            } else {
+                self.map_page(Page::containing_address(virt), PhysFrame::containing_address(physical), flags)?;
+                offset += Size4KiB::SIZE;
+            }
+        }
+        Ok(())
+    }
+
+    // troca uma pagina de 2 MiB por uma tabela com 512 de 4 KiB (mesmos frames, mesmas flags)
+    // -> depois da pra desmapear/proteger uma pagina só dentro dela. já dividida: nada a fazer
+    pub fn split_huge_page(&mut self, page: Page<Size2MiB>) -> Result<(), Error> {
+        let (i4, i3, i2) = (usize::from(page.p4_index()), usize::from(page.p3_index()), usize::from(page.p2_index()));
+        let p4_entry = &unsafe { self.table(&[]) }[i4];
+        if p4_entry.is_unused() || self.is_recursive_entry(i4) {
+            return Err(Error::NotMapped);
+        }
+        let p3_entry = &unsafe { self.table(&[i4]) }[i3];
+        if p3_entry.is_unused() {
+            return Err(Error::NotMapped);
+        }
+        if p3_entry.flags().contains(PageTableFlags::HUGE_PAGE) {
+            // dentro de 1 GiB: primeiro vira 512 de 2 MiB, depois divide a que interessa
+            self.split_giant_page(Page::containing_address(page.start_address()))?;
+        }
+        let entry = &unsafe { self.table(&[i4, i3]) }[i2];
+        if entry.is_unused() {
+            return Err(Error::NotMapped);
+        }
+        if !entry.flags().contains(PageTableFlags::HUGE_PAGE) {
+            return Ok(());
+        }
+        // o bit 12 da entrada grande é o PAT, nao endereco
+        let base = entry.addr().align_down(Size2MiB::SIZE);
+        let flags = entry.flags() - PageTableFlags::HUGE_PAGE;
+        let table_frame = frame::allocate_frame().ok_or(Error::OutOfFrames)?;
+        let parent = (flags & PageTableFlags::USER_ACCESSIBLE) | PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
+        unsafe {
+            self.install_table(&[i4, i3], i2, table_frame, parent, |index| (base + index as u64 * Size4KiB::SIZE, flags));
+        }
+        // um invlpg em qualquer endereco da pagina grande tira ela inteira do TLB
+        x86_64::instructions::tlb::flush(page.start_address());
+        Ok(())
+    }
+
+    // troca uma pagina de 1 GiB por uma tabela com 512 de 2 MiB (mesmos frames, mesmas flags). já dividida: nada
+    pub fn split_giant_page(&mut self, page: Page<Size1GiB>) -> Result<(), Error> {
+        let (i4, i3) = (usize::from(page.p4_index()), usize::from(page.p3_index()));
+        let p4_entry = &unsafe { self.table(&[]) }[i4];
+        if p4_entry.is_unused() || self.is_recursive_entry(i4) {
+            return Err(Error::NotMapped);
+        }
+        let entry = &unsafe { self.table(&[i4]) }[i3];
+        if entry.is_unused() {
+            return Err(Error::NotMapped);
+        }
+        if !entry.flags().contains(PageTableFlags::HUGE_PAGE) {
+            return Ok(());
+        }
+        // o PAT fica no bit 12 nas duas (1 GiB e 2 MiB) -> as flags passam iguais, com HUGE_PAGE
+        let base = entry.addr().align_down(Size1GiB::SIZE);
+        let flags = entry.flags();
+        let table_frame = frame::allocate_frame().ok_or(Error::OutOfFrames)?;
+        let parent = (flags & PageTableFlags::USER_ACCESSIBLE) | PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
+        unsafe {
+            self.install_table(&[i4], i3, table_frame, parent, |index| (base + index as u64 * Size2MiB::SIZE, flags));
+        }
+        x86_64::instructions::tlb::flush(page.start_address());
+        Ok(())
+    }
+
+    // anda pelas tabelas inteiras contando as entradas finais presentes (lento: 512 entradas por tabela)
+    pub fn mapped_pages(&self) -> MappedPages {
+        let table = |path: &[usize]| unsafe { self.table(path) };
+        let present = |entry: &PageTableEntry| entry.flags().contains(PageTableFlags::PRESENT);
+        let huge = |entry: &PageTableEntry| entry.flags().contains(PageTableFlags::HUGE_PAGE);
+        let mut count = MappedPages::default();
+        for (i4, p4_entry) in table(&[]).iter().enumerate() {
+            if !present(p4_entry) || self.is_recursive_entry(i4) {
+                continue;
+            }
+            for (i3, p3_entry) in table(&[i4]).iter().enumerate().filter(|(_, entry)| present(entry)) {
+                if huge(p3_entry) {
+                    count.giant += 1;
+                    continue;
+                }
+                for (i2, p2_entry) in table(&[i4, i3]).iter().enumerate().filter(|(_, entry)| present(entry)) {
+                    if huge(p2_entry) {
+                        count.huge += 1;
+                    } else {
+                        count.small += table(&[i4, i3, i2]).iter().filter(|entry| present(entry)).count();
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    // chama `f` pra cada pagina mapeada em [start, end), em ordem; a que contem `start` entra inteira
+    // pula de uma vez o que uma entrada vazia de P4/P3/P2 cobre -> da pra passar o espaco inteiro
+    pub fn walk<F: FnMut(Mapping)>(&self, start: VirtAddr, end: VirtAddr, mut f: F) {
+        let table = |path: &[usize]| unsafe { self.table(path) };
+        let present = |entry: &PageTableEntry| entry.flags().contains(PageTableFlags::PRESENT);
+        let huge = |entry: &PageTableEntry| entry.flags().contains(PageTableFlags::HUGE_PAGE);
+        let (mut addr, end) = (start.as_u64(), end.as_u64());
+        while addr < end {
+            if (HOLE_START..HOLE_END).contains(&addr) {
+                addr = HOLE_END;
+                continue;
+            }
+            let index = |level: u64| ((addr >> (12 + 9 * level)) & 511) as usize;
+            let (i4, i3, i2, i1) = (index(3), index(2), index(1), index(0));
+            let mut found = |size: u64, entry: &PageTableEntry| {
+                f(Mapping {
+                    virt: VirtAddr::new_truncate(addr & !(size - 1)),
+                    phys: entry.addr().align_down(size), // PAT no bit 12 das grandes
+                    size,
+                    flags: entry.flags(),
+                })
+            };
+            let p4_entry = &table(&[])[i4];
+            let size = if !present(p4_entry) || self.is_recursive_entry(i4) {
+                1 << 39
+            } else {
+                let p3_entry = &table(&[i4])[i3];
+                if !present(p3_entry) || huge(p3_entry) {
+                    if present(p3_entry) {
+                        found(1 << 30, p3_entry);
+                    }
+                    1 << 30
+                } else {
+                    let p2_entry = &table(&[i4, i3])[i2];
+                    if !present(p2_entry) || huge(p2_entry) {
+                        if present(p2_entry) {
+                            found(Size2MiB::SIZE, p2_entry);
+                        }
+                        Size2MiB::SIZE
+                    } else {
+                        let p1_entry = &table(&[i4, i3, i2])[i1];
+                        if present(p1_entry) {
+                            found(Size4KiB::SIZE, p1_entry);
+                        }
+                        Size4KiB::SIZE
+                    }
+                }
+            };
+            match next_boundary(addr, size) {
+                Some(next) => addr = next,
+                None => break,
+            }
+        }
+    }
+
+    // tira a pagina e devolve o frame (nao libera: quem mapeou decide)
+    pub fn unmap(&mut self, page: Page) -> Result<PhysFrame, Error> {
+        let (frame, flush) = self.table.unmap(page)?;
+        flush.flush();
+        Ok(frame)
+    }
+
+    // unsafe: tirar WRITABLE/PRESENT de algo que o kernel usa da page fault
+    pub unsafe fn update_flags(&mut self, page: Page, flags: PageTableFlags) -> Result<(), Error> {
+        self.table.update_flags(page, check_wx(flags))?.flush();
+        Ok(())
+    }
+
+    // troca as flags de tudo que esta mapeado em [start, end); pagina de 2 MiB/1 GiB que o intervalo pega só um
+    // pedaco é dividida antes. buraco no meio -> NotMapped (o que veio antes já mudou)
+    // unsafe: mesma coisa do update_flags
+    pub unsafe fn protect(&mut self, start: VirtAddr, end: VirtAddr, flags: PageTableFlags) -> Result<(), Error> {
+        let flags = check_wx(flags - PageTableFlags::HUGE_PAGE);
+        let mut addr = start.align_down(Size4KiB::SIZE);
+        while addr < end {
+            match self.table.translate(addr) {
+                TranslateResult::Mapped { frame: MappedFrame::Size4KiB(_), .. } => {
+                    self.table.update_flags(Page::<Size4KiB>::containing_address(addr), flags)?.flush();
+                    addr += Size4KiB::SIZE;
+                }
+                TranslateResult::Mapped { frame: MappedFrame::Size2MiB(_), .. } => {
+                    let page = Page::<Size2MiB>::containing_address(addr);
+                    if addr == page.start_address() && end >= addr + Size2MiB::SIZE {
+                        self.table.update_flags(page, flags)?.flush();
+                        addr += Size2MiB::SIZE;
+                    } else {
+                        self.split_huge_page(page)?; // e a volta de novo pega as de 4 KiB
+                    }
+                }
+                TranslateResult::Mapped { frame: MappedFrame::Size1GiB(_), .. } => {
+                    let page = Page::<Size1GiB>::containing_address(addr);
+                    if addr == page.start_address() && end >= addr + Size1GiB::SIZE {
+                        self.table.update_flags(page, flags)?.flush();
+                        addr += Size1GiB::SIZE;
+                    } else {
+                        self.split_giant_page(page)?; // a volta de novo pega as de 2 MiB
+                    }
+                }
+                _ => return Err(Error::NotMapped),
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn translate_addr(&self, addr: VirtAddr) -> Option<PhysAddr> {
+        self.table.translate_addr(addr)
+    }
+
+    // endereco fisico e flags da pagina (qualquer tamanho)
+    pub fn translate(&self, addr: VirtAddr) -> Option<(PhysAddr, PageTableFlags)> {
+        match self.table.translate(addr) {
+            TranslateResult::Mapped { frame, offset, flags } => Some((frame.start_address() + offset, flags)),
+            _ => None,
+        }
+    }
+}
+
+// o bit NX só vale com EFER.NXE (o bootloader já liga na cpu do boot, o vm::init_cpu nas outras)
+pub fn nx_enabled() -> bool {
+    msr::efer() & msr::EFER_NXE != 0
+}
+
+// sem NXE o NO_EXECUTE é bit reservado (page fault) -> sai das flags e o W^X fica sem garantia
+fn check_wx(flags: PageTableFlags) -> PageTableFlags {
+    if !nx_enabled() {
+        return flags - PageTableFlags::NO_EXECUTE;
+    }
+    if flags.contains(PageTableFlags::WRITABLE) && !flags.contains(PageTableFlags::NO_EXECUTE) {
+        panic!("W^X: mapeamento escrevivel e executavel ({:?})", flags);
+    }
+    flags
+}
+
+// no modo 64 bits os 2 MiB sempre existem, mas o cpuid ainda é quem manda
+pub fn huge_pages_supported() -> bool {
+    crate::cpu::features().pse
+}
+
+// como as tabelas sao acessadas (pro log do boot)
+pub fn mode() -> &'static str {
+    if cfg!(feature = "recursive_page_table") {
+        "pela entrada recursiva"
+    } else {
+        "pelo offset da memoria fisica"
+    }
+}
+
+static MAPPER: Once<Mutex<Mapper>> = Once::new();
+
+// chamado pelo memory::init
+pub fn init(boot_info: &BootInfo) {
+    MAPPER.call_once(|| Mutex::new(unsafe { Mapper::active(boot_info) }));
+}
+
+// o offset da memoria fisica mudou (kaslr): refaz o Mapper em cima dele
+pub fn reload(boot_info: &BootInfo) {
+    let mapper = MAPPER.wait().expect("memory::init nao foi chamado");
+    cpu::without_interrupts(|| *mapper.lock() = unsafe { Mapper::active(boot_info) });
+}
+
+// o lock é pego sem interrupcao (um handler pode querer mapear, ex: page fault)
+pub fn with_mapper<R, F: FnOnce(&mut Mapper) -> R>(f: F) -> R {
+    let mapper = MAPPER.wait().expect("memory::init nao foi chamado");
+    cpu::without_interrupts(|| f(&mut mapper.lock()))
+}
+
+// de dentro de um handler: None se alguem ja esta mexendo nas tabelas
+pub fn try_with_mapper<R, F: FnOnce(&mut Mapper) -> R>(f: F) -> Option<R> {
+    let mapper = MAPPER.wait()?;
+    mapper.try_lock().map(|mut mapper| f(&mut mapper))
+}
+
+#[allow(dead_code)]
+pub fn translate_addr(addr: VirtAddr) -> Option<PhysAddr> {
+    with_mapper(|mapper| mapper.translate_addr(addr))
+}
@@ -0,0 +1,170 @@
+// teste de ram nos frames livres agora (pra maquina de verdade, onde pente ruim acontece)
+// pega os frames livres do alocador (deixando RESERVE pra quem precisar no meio), e pra cada padrao
+// escreve em todos, esvazia o cache (wbinvd) e só depois le todos -> a leitura vem da ram, nao do cache
+// padroes: 0x55.., 0xaa.. (cada bit nos dois valores) e o proprio endereco em cada palavra (acha linha de
+// endereco trocada/curta). frame que falha fica de quarentena: nunca volta pro alocador
+// a palavra 0 de cada frame guarda o proximo da lista enquanto o teste roda (nao é testada; se ela estragar,
+// o resto da lista fica preso), e o bit BAD dela marca o frame que falhou
+use super::frame;
+use core::fmt;
+use spin::Mutex;
+use x86_64::structures::paging::PhysFrame;
+use x86_64::PhysAddr;
+
+const WORDS: usize = (frame::FRAME_SIZE / 8) as usize;
+// frames que ficam livres no alocador durante o teste (page fault sob demanda, dma...)
+const RESERVE: usize = 256;
+const MAX_FAILURES: usize = 16;
+const MAX_QUARANTINE: usize = 64;
+// o endereco do proximo é alinhado em 4 KiB -> os bits de baixo da palavra 0 sobram
+const BAD: u64 = 1;
+const ADDRESS_MASK: u64 = !(frame::FRAME_SIZE - 1);
+
+#[derive(Debug, Clone, Copy)]
+enum Pattern {
+    Fives,
+    As,
+    Address,
+}
+
+impl Pattern {
+    const ALL: [Pattern; 3] = [Pattern::Fives, Pattern::As, Pattern::Address];
+
+    fn value(self, address: u64) -> u64 {
+        match self {
+            Pattern::Fives => 0x5555_5555_5555_5555,
+            Pattern::As => 0xaaaa_aaaa_aaaa_aaaa,
+            Pattern::Address => address,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Pattern::Fives => "0x55",
+            Pattern::As => "0xaa",
+            Pattern::Address => "endereco",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Failure {
+    pub address: PhysAddr, // a palavra que falhou
+    pub pattern: &'static str,
+    pub expected: u64,
+    pub found: u64,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:#x} ({}): esperado {:#018x}, lido {:#018x}",
+            self.address.as_u64(),
+            self.pattern,
+            self.expected,
+            self.found
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub tested: usize,     // frames
+    pub bad_frames: usize, // frames que falharam (só as primeiras falhas ficam em failures)
+    pub leaked: usize,     // ruins que nao couberam na quarentena: ficam alocados pra sempre, sem registro
+    pub failures: heapless::Vec<Failure, MAX_FAILURES>,
+}
+
+// frames ruins tirados de circulacao
+static QUARANTINE: Mutex<heapless::Vec<PhysFrame, MAX_QUARANTINE>> = Mutex::new(heapless::Vec::new());
+
+pub fn quarantined() -> heapless::Vec<PhysFrame, MAX_QUARANTINE> {
+    crate::cpu::without_interrupts(|| QUARANTINE.lock().clone())
+}
+
+fn words(frame: PhysFrame) -> *mut u64 {
+    super::phys_to_virt(frame.start_address()).as_mut_ptr()
+}
+
+// proximo da lista (guardado na palavra 0); None no fim ou se o encadeamento estragou
+fn next(frame: PhysFrame) -> Option<PhysFrame> {
+    let next = unsafe { core::ptr::read_volatile(words(frame)) } & ADDRESS_MASK;
+    PhysFrame::from_start_address(PhysAddr::try_new(next).ok()?)
+        .ok()
+        .filter(|_| next != 0 && next < frame::MAX_MEMORY)
+}
+
+fn is_bad(frame: PhysFrame) -> bool {
+    unsafe { core::ptr::read_volatile(words(frame)) & BAD != 0 }
+}
+
+fn mark_bad(frame: PhysFrame) {
+    unsafe { core::ptr::write_volatile(words(frame), core::ptr::read_volatile(words(frame)) | BAD) };
+}
+
+// escreve de volta e invalida o cache inteiro -> a proxima leitura vai na ram
+fn flush_caches() {
+    unsafe { core::arch::asm!("wbinvd", options(nostack, preserves_flags)) };
+}
+
+fn chain(head: Option<PhysFrame>) -> impl Iterator<Item = PhysFrame> {
+    core::iter::successors(head, |&frame| next(frame))
+}
+
+// testa ate `limit` frames (None = todos os livres menos a reserva)
+pub fn run(limit: Option<usize>) -> Report {
+    let mut report = Report::default();
+    // 1. pega os frames, cada um apontando pro anterior
+    let mut head: Option<PhysFrame> = None;
+    while limit.is_none_or(|limit| report.tested < limit) && frame::free_frames() > RESERVE {
+        let Some(frame) = frame::allocate_frame() else { break };
+        let previous = head.map_or(0, |frame| frame.start_address().as_u64());
+        unsafe { core::ptr::write_volatile(words(frame), previous) };
+        head = Some(frame);
+        report.tested += 1;
+    }
+    // 2. cada padrao: escreve tudo, esvazia o cache, depois confere tudo
+    for pattern in Pattern::ALL {
+        for frame in chain(head) {
+            let base = frame.start_address().as_u64();
+            for word in 1..WORDS {
+                let value = pattern.value(base + word as u64 * 8);
+                unsafe { core::ptr::write_volatile(words(frame).add(word), value) };
+            }
+        }
+        flush_caches();
+        for frame in chain(head) {
+            let base = frame.start_address().as_u64();
+            for word in 1..WORDS {
+                let expected = pattern.value(base + word as u64 * 8);
+                let found = unsafe { core::ptr::read_volatile(words(frame).add(word)) };
+                if found == expected {
+                    continue;
+                }
+                let _ = report.failures.push(Failure {
+                    address: PhysAddr::new(base + word as u64 * 8),
+                    pattern: pattern.name(),
+                    expected,
+                    found,
+                });
+                if !is_bad(frame) {
+                    report.bad_frames += 1;
+                    mark_bad(frame);
+                }
+                break; // o resto do frame nao muda nada
+            }
+        }
+    }
+    // 3. os bons voltam, os ruins nunca: ficam de quarentena ou, sem espaco na lista, só alocados
+    let mut frame = head;
+    while let Some(current) = frame {
+        frame = next(current);
+        if !is_bad(current) {
+            frame::deallocate_frame(current);
+        } else if crate::cpu::without_interrupts(|| QUARANTINE.lock().push(current)).is_err() {
+            report.leaked += 1;
+        }
+    }
+    report
+}
@@ -0,0 +1,127 @@
+// stacks do kernel (uma por tarefa, mais as de cada cpu: double fault, privilege, syscall)
+// saem de uma regiao virtual só pra isso (reservada no vm), dividida em slots de 64 KiB; a stack fica no fim do slot
+// e o resto do slot nunca é mapeado -> embaixo de toda stack tem pelo menos uma guard page
+// com kaslr a regiao cai num lugar sorteado (vm) e cada stack pega um slot livre sorteado
+// ex: let stack = stack::allocate(4, "task", id)?; ... (o drop desmapeia e devolve os frames quando a tarefa acaba)
+use super::{frame, guard, mapper};
+use crate::cpu;
+use crate::vm;
+use spin::Mutex;
+use x86_64::structures::paging::{Page, PageTableFlags, Size4KiB};
+use x86_64::VirtAddr;
+
+const SLOT_SIZE: u64 = 64 * 1024;
+const MAX_STACKS: usize = 256;
+// uma pagina do slot sempre fica pra guard
+pub const MAX_PAGES: usize = (SLOT_SIZE / 4096) as usize - 1;
+#[allow(dead_code)]
+pub const DEFAULT_PAGES: usize = 5;
+
+// a regiao inteira é reservada no vm na primeira stack; se nao deu (vm cheio) fica None e a proxima tenta de novo
+static REGION: Mutex<Option<u64>> = Mutex::new(None);
+
+fn region_start() -> Result<u64, vm::Error> {
+    cpu::without_interrupts(|| {
+        let mut region = REGION.lock();
+        if let Some(start) = *region {
+            return Ok(start);
+        }
+        let size = SLOT_SIZE * MAX_STACKS as u64;
+        let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+        let backing = vm::Backing::Anonymous;
+        let start = vm::with_kernel(|space| space.reserve(size, SLOT_SIZE, flags, backing, "stacks do kernel"))?;
+        *region = Some(start.as_u64());
+        Ok(start.as_u64())
+    })
+}
+
+// bit = slot em uso
+static SLOTS: Mutex<[u64; MAX_STACKS / 64]> = Mutex::new([0; MAX_STACKS / 64]);
+
+// o primeiro livre a partir de um slot sorteado (0 sem kaslr)
+fn take_slot() -> Option<usize> {
+    let first = crate::kaslr::slide(MAX_STACKS as u64 - 1, 1) as usize;
+    cpu::without_interrupts(|| {
+        let mut slots = SLOTS.lock();
+        let slot = (0..MAX_STACKS)
+            .map(|i| (first + i) % MAX_STACKS)
+            .find(|&slot| slots[slot / 64] & (1 << (slot % 64)) == 0)?;
+        slots[slot / 64] |= 1 << (slot % 64);
+        Some(slot)
+    })
+}
+
+fn free_slot(slot: usize) {
+    cpu::without_interrupts(|| SLOTS.lock()[slot / 64] &= !(1 << (slot % 64)));
+}
+
+#[derive(Debug)]
+pub struct KernelStack {
+    region: u64,
+    slot: usize,
+    pages: usize,
+}
+
+impl KernelStack {
+    fn slot_end(&self) -> VirtAddr {
+        VirtAddr::new(self.region + (self.slot as u64 + 1) * SLOT_SIZE)
+    }
+
+    // o rsp inicial (alinhado em 16)
+    pub fn top(&self) -> VirtAddr {
+        self.slot_end()
+    }
+
+    // endereco mais baixo que a stack pode usar
+    pub fn bottom(&self) -> VirtAddr {
+        self.slot_end() - self.pages as u64 * 4096
+    }
+
+    fn pages(&self) -> impl Iterator<Item = Page<Size4KiB>> {
+        let first = Page::containing_address(self.bottom());
+        Page::range(first, first + self.pages as u64)
+    }
+
+    fn guard_page(&self) -> Page<Size4KiB> {
+        Page::containing_address(self.bottom() - 1u64)
+    }
+
+    // stack que nunca vai ser liberada (a de cada cpu), devolve o topo
+    pub fn leak(self) -> VirtAddr {
+        let top = self.top();
+        core::mem::forget(self);
+        top
+    }
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        for page in self.pages() {
+            if let Ok(frame) = super::with_mapper(|mapper| mapper.unmap(page)) {
+                frame::deallocate_frame(frame);
+            }
+        }
+        guard::release(self.guard_page());
+        free_slot(self.slot);
+    }
+}
+
+// `pages` paginas mapeadas (sem guard), com a guard registrada em nome de `owner` `id`
+pub fn allocate(pages: usize, owner: &'static str, id: usize) -> Result<KernelStack, vm::Error> {
+    assert!(pages > 0 && pages <= MAX_PAGES, "stack de {} paginas", pages);
+    let region = region_start()?;
+    let slot = take_slot().ok_or(vm::Error::NoSpace)?;
+    // mapeia de cima pra baixo: se der erro no meio, o drop desfaz só o que já foi mapeado
+    let mut stack = KernelStack { region, slot, pages: 0 };
+    let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+    while stack.pages < pages {
+        let page = stack.guard_page();
+        let frame = frame::allocate_frame().ok_or(mapper::Error::OutOfFrames)?;
+        super::with_mapper(|mapper| unsafe { mapper.map_page(page, frame, flags) }).inspect_err(|_| {
+            frame::deallocate_frame(frame);
+        })?;
+        stack.pages += 1;
+    }
+    guard::register(stack.guard_page(), owner, id);
+    Ok(stack)
+}
@@ -0,0 +1,196 @@
+// "tela vermelha" do panic: limpa tudo, mostra a mensagem, onde foi e o estado dos registradores
+// as excecoes fatais (double fault...) usam a mesma tela com o frame da excecao
+use crate::graphics::GRAPHICS;
+use crate::serial::SERIAL1;
+use crate::vga_buffer::{Color, ColorCode, Writer, WRITER};
+use core::arch::asm;
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use spin::{Mutex, MutexGuard};
+use x86_64::instructions::interrupts;
+use x86_64::registers::control::{Cr0, Cr2, Cr3, Cr4};
+use x86_64::registers::rflags;
+use x86_64::structures::idt::InterruptStackFrame;
+
+const PANIC_COLOR: ColorCode = ColorCode::new(Color::White, Color::Red);
+const TITLE_COLOR: ColorCode = ColorCode::new(Color::Red, Color::White);
+
+// quantas palavras de 8 bytes da stack aparecem no dump
+const STACK_WORDS: usize = 8;
+
+// registradores lidos logo no comeco, antes de chamar mais coisa e bagunçar a stack
+struct Registers {
+    rsp: u64,
+    rbp: u64,
+    rflags: u64,
+    cr0: u64,
+    cr2: u64,
+    cr3: u64,
+    cr4: u64,
+}
+
+impl Registers {
+    #[inline(always)]
+    fn capture() -> Registers {
+        let rsp: u64;
+        let rbp: u64;
+        unsafe {
+            asm!("mov {}, rsp", out(reg) rsp, options(nomem, nostack, preserves_flags));
+            asm!("mov {}, rbp", out(reg) rbp, options(nomem, nostack, preserves_flags));
+        }
+        Registers {
+            rsp,
+            rbp,
+            rflags: rflags::read_raw(),
+            cr0: Cr0::read_raw(),
+            cr2: Cr2::read_raw(),
+            cr3: Cr3::read_raw().0.start_address().as_u64(),
+            cr4: Cr4::read_raw(),
+        }
+    }
+}
+
+pub fn show(info: &PanicInfo) -> ! {
+    interrupts::disable();
+    let registers = Registers::capture();
+
+    let mut writer = take_screen();
+    writer.set_blink_enabled(false);
+    writer.take_over_screen(PANIC_COLOR);
+
+    // titulo centralizado
+    const TITLE: &str = " KERNEL PANIC ";
+    let col = (writer.width() - TITLE.len()) / 2;
+    writer.write_at(1, col, TITLE, Some(TITLE_COLOR));
+    writer.write_string("\n\n\n");
+
+    let _ = writeln!(writer, "  {}", info.message());
+    writer.write_string("\n");
+    match info.location() {
+        Some(location) => {
+            let _ = writeln!(
+                writer,
+                "  em {}:{}:{}",
+                location.file(),
+                location.line(),
+                location.column()
+            );
+        }
+        None => writer.write_string("  local desconhecido\n"),
+    }
+
+    writer.write_string("\n  registradores:\n");
+    let _ = writeln!(writer, "    RSP {:#018x}    RBP {:#018x}", registers.rsp, registers.rbp);
+    let _ = writeln!(writer, "    RFLAGS {:#015x} CR0 {:#018x}", registers.rflags, registers.cr0);
+    let _ = writeln!(writer, "    CR2 {:#018x}    CR3 {:#018x}", registers.cr2, registers.cr3);
+    let _ = writeln!(writer, "    CR4 {:#018x}", registers.cr4);
+
+    writer.write_string("\n  stack:\n");
+    dump_stack(&mut writer, registers.rsp);
+
+    writer.write_string("\n  sistema parado.");
+    writer.hide_cursor();
+    drop(writer);
+
+    crate::cpu::hlt_loop(|| {})
+}
+
+// excecao sem volta: mostra o frame e o error code e para
+// `details` -> linha extra de explicacao (ex: o endereco e o motivo do page fault)
+pub fn show_exception(
+    name: &str,
+    stack_frame: &InterruptStackFrame,
+    error_code: Option<u64>,
+    details: Option<fmt::Arguments>,
+) -> ! {
+    interrupts::disable();
+    let registers = Registers::capture();
+
+    let mut writer = take_screen();
+    writer.set_blink_enabled(false);
+    writer.take_over_screen(PANIC_COLOR);
+
+    const TITLE: &str = " EXCEPTION ";
+    let col = (writer.width() - TITLE.len()) / 2;
+    writer.write_at(1, col, TITLE, Some(TITLE_COLOR));
+    writer.write_string("\n\n\n");
+
+    let _ = writeln!(writer, "  {}", name);
+    if let Some(code) = error_code {
+        let _ = writeln!(writer, "  error code {:#x}", code);
+    }
+    if let Some(details) = details {
+        let _ = writeln!(writer, "  {}", details);
+    }
+
+    writer.write_string("\n  frame:\n");
+    let _ = writeln!(
+        writer,
+        "    RIP {:#018x}    CS  {:#06x}",
+        stack_frame.instruction_pointer.as_u64(),
+        stack_frame.code_segment.0
+    );
+    let _ = writeln!(
+        writer,
+        "    RSP {:#018x}    SS  {:#06x}",
+        stack_frame.stack_pointer.as_u64(),
+        stack_frame.stack_segment.0
+    );
+    let _ = writeln!(writer, "    RFLAGS {:#015x}", stack_frame.cpu_flags.bits());
+    let _ = writeln!(writer, "    CR2 {:#018x}    CR3 {:#018x}", registers.cr2, registers.cr3);
+
+    // sem dump da stack: num stack overflow o RSP aponta pra pagina de guarda e ler ali seria outra excecao
+
+    writer.write_string("\n  sistema parado.");
+    writer.hide_cursor();
+
+    // com o mirror ligado a serial ja recebeu tudo; senao manda um resumo
+    if writer.mirror().is_none() {
+        let mut serial = force_lock(&SERIAL1);
+        let _ = writeln!(
+            serial,
+            "\nEXCEPTION: {} (error code {:?})\n{:#?}",
+            name, error_code, stack_frame
+        );
+        if let Some(details) = details {
+            let _ = writeln!(serial, "{}", details);
+        }
+    }
+    drop(writer);
+
+    crate::cpu::hlt_loop(|| {})
+}
+
+// o panic pode ter acontecido com algum desses locks pego (inclusive pelo proprio codigo que deu panic)
+// como nada mais vai rodar depois disso, é seguro forcar o unlock
+fn take_screen() -> MutexGuard<'static, Writer> {
+    // o leave() pega o WRITER, entao ele tem que estar livre antes
+    // a serial tambem: o mirror do WRITER escreve nela
+    drop(force_lock(&SERIAL1));
+    drop(force_lock(&WRITER));
+    force_lock(&GRAPHICS).leave();
+    force_lock(&WRITER)
+}
+
+fn force_lock<T>(mutex: &'static Mutex<T>) -> MutexGuard<'static, T> {
+    if let Some(guard) = mutex.try_lock() {
+        return guard;
+    }
+    unsafe { mutex.force_unlock() };
+    mutex.lock()
+}
+
+fn dump_stack(writer: &mut Writer, rsp: u64) {
+    let stack = rsp as *const u64;
+    for i in 0..STACK_WORDS / 2 {
+        let first = unsafe { core::ptr::read_volatile(stack.add(i * 2)) };
+        let second = unsafe { core::ptr::read_volatile(stack.add(i * 2 + 1)) };
+        let _ = writeln!(
+            writer,
+            "    rsp+{:#04x}: {:#018x} {:#018x}",
+            i * 16,
+            first,
+            second
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// dados de cada cpu, achados pelo GS: o GS base de cada core aponta pro bloco dele
+// e o primeiro campo do bloco é o proprio endereco -> `mov rax, gs:[0]` da o bloco da cpu atual
+// os stubs em assembly (syscall) usam os offsets direto: gs:[offset_of!(PerCpu, campo)]
+// obs: dos handlers, só o syscall e o int 0x80 fazem swapgs -> nos outros o current() só vale se a interrupcao veio do kernel
+use crate::cpu::msr;
+use crate::smp::MAX_CPUS;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use spin::Mutex;
+use x86_64::VirtAddr;
+
+// tarefas esperando a vez nessa cpu (ids)
+pub const RUN_QUEUE_SIZE: usize = 32;
+
+pub struct Stats {
+    pub interrupts: AtomicU64,
+    pub syscalls: AtomicU64,
+    #[allow(dead_code)]
+    pub context_switches: AtomicU64,
+}
+
+#[repr(C)]
+pub struct PerCpu {
+    this: AtomicU64, // tem que ser o primeiro
+    // usados pelo stub do syscall
+    syscall_kernel_rsp: AtomicU64,
+    syscall_user_rsp: AtomicU64,
+    cpu: AtomicUsize,
+    apic_id: AtomicU32,
+    current_task: AtomicUsize, // 0 -> nenhuma (idle)
+    pub run_queue: Mutex<heapless::Deque<usize, RUN_QUEUE_SIZE>>,
+    pub stats: Stats,
+}
+
+impl PerCpu {
+    const fn new() -> PerCpu {
+        PerCpu {
+            this: AtomicU64::new(0),
+            syscall_kernel_rsp: AtomicU64::new(0),
+            syscall_user_rsp: AtomicU64::new(0),
+            cpu: AtomicUsize::new(0),
+            apic_id: AtomicU32::new(0),
+            current_task: AtomicUsize::new(0),
+            run_queue: Mutex::new(heapless::Deque::new()),
+            stats: Stats {
+                interrupts: AtomicU64::new(0),
+                syscalls: AtomicU64::new(0),
+                context_switches: AtomicU64::new(0),
+            },
+        }
+    }
+
+    // indice da cpu (0 = BSP), o mesmo do smp e da gdt
+    pub fn cpu(&self) -> usize {
+        self.cpu.load(Ordering::Relaxed)
+    }
+
+    #[allow(dead_code)]
+    pub fn apic_id(&self) -> u32 {
+        self.apic_id.load(Ordering::Relaxed)
+    }
+
+    #[allow(dead_code)]
+    pub fn current_task(&self) -> Option<usize> {
+        match self.current_task.load(Ordering::Relaxed) {
+            0 => None,
+            task => Some(task),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_current_task(&self, task: Option<usize>) {
+        self.current_task.store(task.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    // topo da stack que o syscall pega nessa cpu
+    pub fn set_syscall_stack(&self, top: VirtAddr) {
+        self.syscall_kernel_rsp.store(top.as_u64(), Ordering::Relaxed);
+    }
+}
+
+// pros stubs em assembly
+pub const SYSCALL_KERNEL_RSP: usize = core::mem::offset_of!(PerCpu, syscall_kernel_rsp);
+pub const SYSCALL_USER_RSP: usize = core::mem::offset_of!(PerCpu, syscall_user_rsp);
+
+static CPUS: [PerCpu; MAX_CPUS] = [const { PerCpu::new() }; MAX_CPUS];
+static READY: AtomicBool = AtomicBool::new(false);
+
+// apic id inicial pelo cpuid (funciona mesmo sem o local apic ligado nem o GS no lugar, ex: fault vindo do ring 3)
+pub fn initial_apic_id() -> u32 {
+    core::arch::x86_64::__cpuid(1).ebx >> 24
+}
+
+// liga o GS da cpu atual no bloco `cpu`; primeira coisa no kernel_main e no ap_entry
+pub fn init(cpu: usize) {
+    let block = &CPUS[cpu];
+    let address = block as *const PerCpu as u64;
+    block.this.store(address, Ordering::Relaxed);
+    block.cpu.store(cpu, Ordering::Relaxed);
+    block.apic_id.store(initial_apic_id(), Ordering::Relaxed);
+    // o bloco é estatico e dessa cpu (ninguem mais chama init com esse `cpu`); o do usuario comeca zerado e
+    // aparece no GS depois do swapgs
+    unsafe {
+        msr::set_gs_base(VirtAddr::new(address));
+        msr::set_kernel_gs_base(VirtAddr::zero());
+    }
+    READY.store(true, Ordering::Release);
+}
+
+// false antes do init do BSP (excecao muito cedo no boot)
+pub fn is_ready() -> bool {
+    READY.load(Ordering::Acquire)
+}
+
+pub fn current() -> &'static PerCpu {
+    let address: u64;
+    unsafe {
+        core::arch::asm!("mov {}, gs:[0]", out(reg) address, options(nostack, readonly, preserves_flags));
+        &*(address as *const PerCpu)
+    }
+}
+
+pub fn cpu_id() -> usize {
+    current().cpu()
+}
+
+// bloco de outra cpu (ex: colocar uma tarefa na fila dela)
+#[allow(dead_code)]
+pub fn get(cpu: usize) -> Option<&'static PerCpu> {
+    CPUS.get(cpu).filter(|block| block.this.load(Ordering::Relaxed) != 0)
+}
+
+// cpus que já passaram pelo init
+#[allow(dead_code)]
+pub fn all() -> impl Iterator<Item = &'static PerCpu> {
+    (0..MAX_CPUS).filter_map(get)
+}
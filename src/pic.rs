@@ -0,0 +1,167 @@
+// os dois 8259 (master e slave em cascata)
+// por padrao as irqs caem nos vetores 0-15, em cima das excecoes da cpu -> remapeia pra 32-47
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+pub const PIC_1_OFFSET: u8 = 32;
+pub const PIC_2_OFFSET: u8 = PIC_1_OFFSET + 8;
+
+const MASTER_COMMAND: u16 = 0x20;
+const MASTER_DATA: u16 = 0x21;
+const SLAVE_COMMAND: u16 = 0xA0;
+const SLAVE_DATA: u16 = 0xA1;
+
+const ICW1_INIT: u8 = 0x11; // comeca a inicializacao, vai ter ICW4
+const ICW4_8086: u8 = 0x01;
+const EOI: u8 = 0x20;
+const READ_ISR: u8 = 0x0B; // OCW3: proxima leitura do comando devolve o in-service register
+
+const CASCADE_IRQ: u8 = 2; // o slave fica pendurado na irq 2 do master
+
+struct Pic {
+    offset: u8,
+    command: Port<u8>,
+    data: Port<u8>,
+}
+
+impl Pic {
+    fn handles(&self, vector: u8) -> bool {
+        (self.offset..self.offset + 8).contains(&vector)
+    }
+}
+
+pub struct ChainedPics {
+    master: Pic,
+    slave: Pic,
+}
+
+impl ChainedPics {
+    pub const fn new(master_offset: u8, slave_offset: u8) -> ChainedPics {
+        ChainedPics {
+            master: Pic {
+                offset: master_offset,
+                command: Port::new(MASTER_COMMAND),
+                data: Port::new(MASTER_DATA),
+            },
+            slave: Pic {
+                offset: slave_offset,
+                command: Port::new(SLAVE_COMMAND),
+                data: Port::new(SLAVE_DATA),
+            },
+        }
+    }
+
+    // remapeia e deixa todas as linhas mascaradas (menos a cascata); cada driver libera a sua com unmask()
+    pub fn initialize(&mut self) {
+        // escrever na porta 0x80 (POST) demora o suficiente pro pic antigo processar o comando
+        let mut wait_port: Port<u8> = Port::new(0x80);
+        let mut io_wait = || unsafe { wait_port.write(0) };
+
+        unsafe {
+            self.master.command.write(ICW1_INIT);
+            io_wait();
+            self.slave.command.write(ICW1_INIT);
+            io_wait();
+            // ICW2: vetor base
+            self.master.data.write(self.master.offset);
+            io_wait();
+            self.slave.data.write(self.slave.offset);
+            io_wait();
+            // ICW3: onde esta a cascata
+            self.master.data.write(1 << CASCADE_IRQ);
+            io_wait();
+            self.slave.data.write(CASCADE_IRQ);
+            io_wait();
+            self.master.data.write(ICW4_8086);
+            io_wait();
+            self.slave.data.write(ICW4_8086);
+            io_wait();
+        }
+        self.write_masks(!(1u16 << CASCADE_IRQ));
+    }
+
+    pub fn handles(&self, vector: u8) -> bool {
+        self.master.handles(vector) || self.slave.handles(vector)
+    }
+
+    // no fim de todo handler de irq; a do slave precisa avisar os dois
+    pub fn notify_end_of_interrupt(&mut self, vector: u8) {
+        if !self.handles(vector) {
+            return;
+        }
+        unsafe {
+            if self.slave.handles(vector) {
+                self.slave.command.write(EOI);
+            }
+            self.master.command.write(EOI);
+        }
+    }
+
+    // irq 7/15 espuria: o pic levantou a linha e desistiu; nao pode mandar EOI pro master/slave que nao tinha irq
+    // retorna true se foi espuria (o handler só retorna)
+    pub fn is_spurious(&mut self, vector: u8) -> bool {
+        let irq = match vector.checked_sub(self.master.offset) {
+            Some(7) => 7,
+            _ => match vector.checked_sub(self.slave.offset) {
+                Some(7) => 15,
+                _ => return false,
+            },
+        };
+        let in_service = self.in_service();
+        if in_service & (1 << irq) != 0 {
+            return false;
+        }
+        // espuria no slave: o master recebeu a cascata de verdade e precisa do EOI
+        if irq == 15 {
+            unsafe { self.master.command.write(EOI) };
+        }
+        true
+    }
+
+    // bit n -> irq n sendo tratada agora
+    pub fn in_service(&mut self) -> u16 {
+        unsafe {
+            self.master.command.write(READ_ISR);
+            self.slave.command.write(READ_ISR);
+            (self.slave.command.read() as u16) << 8 | self.master.command.read() as u16
+        }
+    }
+
+    // bit n = 1 -> irq n mascarada
+    pub fn masks(&mut self) -> u16 {
+        unsafe { (self.slave.data.read() as u16) << 8 | self.master.data.read() as u16 }
+    }
+
+    pub fn write_masks(&mut self, masks: u16) {
+        unsafe {
+            self.master.data.write(masks as u8);
+            self.slave.data.write((masks >> 8) as u8);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn mask(&mut self, irq: u8) {
+        let masks = self.masks();
+        self.write_masks(masks | 1 << (irq & 15));
+    }
+
+    // irq do slave só chega se a cascata tambem estiver liberada
+    pub fn unmask(&mut self, irq: u8) {
+        let mut masks = self.masks() & !(1 << (irq & 15));
+        if irq >= 8 {
+            masks &= !(1 << CASCADE_IRQ);
+        }
+        self.write_masks(masks);
+    }
+
+    // 0xff nos dois: usado quando for trocar pro apic
+    pub fn disable(&mut self) {
+        self.write_masks(0xffff);
+    }
+}
+
+pub static PICS: Mutex<ChainedPics> = Mutex::new(ChainedPics::new(PIC_1_OFFSET, PIC_2_OFFSET));
+
+pub fn init() {
+    PICS.lock().initialize();
+}
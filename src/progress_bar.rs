@@ -0,0 +1,73 @@
+// barra de progresso numa posicao fixa da tela: [█████░░░░░]  42%
+// redesenha no lugar a cada set_progress (boot, init da memoria, scan de disco...)
+use crate::vga_buffer::{ColorCode, WRITER};
+
+#[allow(dead_code)]
+const FILLED: u8 = 0xdb; // █ no cp437
+#[allow(dead_code)]
+const EMPTY: u8 = 0xb0; // ░
+
+#[allow(dead_code)]
+pub struct ProgressBar {
+    row: usize,
+    col: usize,
+    width: usize, // celulas da barra, sem contar os colchetes e a porcentagem
+    color_code: ColorCode,
+    progress: f32,
+}
+
+#[allow(dead_code)]
+impl ProgressBar {
+    // ocupa width + 7 colunas: [ + barra + ] + " 100%"
+    pub fn new(row: usize, col: usize, width: usize, color_code: ColorCode) -> ProgressBar {
+        let bar = ProgressBar {
+            row,
+            col,
+            width: width.max(1),
+            color_code,
+            progress: 0.0,
+        };
+        bar.draw();
+        bar
+    }
+
+    // de 0.0 a 1.0, fora disso é limitado
+    pub fn set_progress(&mut self, progress: f32) {
+        let progress = if progress.is_nan() { 0.0 } else { progress.clamp(0.0, 1.0) };
+        self.progress = progress;
+        self.draw();
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    fn draw(&self) {
+        let filled = (self.progress * self.width as f32) as usize;
+        let percent = (self.progress * 100.0) as usize;
+
+        let mut writer = WRITER.lock();
+        let mut col = self.col;
+        let mut put = |byte| {
+            writer.put_char_at(self.row, col, byte, self.color_code);
+            col += 1;
+        };
+        put(b'[');
+        for i in 0..self.width {
+            put(if i < filled { FILLED } else { EMPTY });
+        }
+        put(b']');
+        put(b' ');
+        // porcentagem alinhada a direita em 3 digitos
+        for divisor in [100, 10, 1] {
+            let digit = percent / divisor % 10;
+            if percent < divisor && divisor > 1 {
+                put(b' ');
+            } else {
+                put(b'0' + digit as u8);
+            }
+        }
+        put(b'%');
+        writer.flush_if_auto();
+    }
+}
@@ -0,0 +1,94 @@
+// numeros aleatorios pro kernel (aslr, numero de sequencia do tcp, chaves no futuro)
+// com RDRAND/RDSEED vem da cpu; sem eles mistura o TSC com o momento das teclas/bytes da serial
+// (o fallback nao serve pra cripto, só evita que todo boot tenha os mesmos valores)
+use core::arch::x86_64::{_rdrand64_step, _rdseed64_step};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+// a intel recomenda 10 tentativas pro RDRAND; o RDSEED demora mais pra encher
+const RDRAND_RETRIES: usize = 10;
+const RDSEED_RETRIES: usize = 100;
+
+const GOLDEN: u64 = 0x9E37_79B9_7F4A_7C15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Rdrand,
+    Rdseed,
+    Jitter,
+}
+
+// estado do fallback: tudo que chega pelo add_entropy é misturado aqui
+static POOL: AtomicU64 = AtomicU64::new(GOLDEN);
+
+// finalizador do splitmix64: espalha cada bit de entrada pela saida inteira
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+pub fn rdrand() -> Option<u64> {
+    if !crate::cpu::features().rdrand {
+        return None;
+    }
+    let mut value = 0;
+    for _ in 0..RDRAND_RETRIES {
+        // 1 -> valor valido; 0 -> a cpu ainda nao tinha entropia, tenta de novo
+        if unsafe { _rdrand64_step(&mut value) } == 1 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+// mais lento que o RDRAND, mas cada valor vem direto da fonte -> bom pra semente
+pub fn rdseed() -> Option<u64> {
+    if !crate::cpu::features().rdseed {
+        return None;
+    }
+    let mut value = 0;
+    for _ in 0..RDSEED_RETRIES {
+        if unsafe { _rdseed64_step(&mut value) } == 1 {
+            return Some(value);
+        }
+        crate::cpu::spin_hint();
+    }
+    None
+}
+
+// de onde sai o next_u64 nessa maquina
+pub fn source() -> Source {
+    let features = crate::cpu::features();
+    if features.rdrand {
+        Source::Rdrand
+    } else if features.rdseed {
+        Source::Rdseed
+    } else {
+        Source::Jitter
+    }
+}
+
+// sem lock: chamado dentro das interrupcoes (teclado, serial) com o TSC do momento
+pub fn add_entropy(value: u64) {
+    let _ = POOL.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pool| {
+        Some(mix(pool ^ value).rotate_left(17))
+    });
+}
+
+fn jitter() -> u64 {
+    // cada chamada anda o estado (dois pedidos seguidos nunca dao o mesmo valor) e mistura o TSC atual
+    let state = POOL.fetch_add(GOLDEN, Ordering::Relaxed).wrapping_add(GOLDEN);
+    mix(state ^ crate::time::rdtsc())
+}
+
+pub fn next_u64() -> u64 {
+    rdrand().or_else(rdseed).unwrap_or_else(jitter)
+}
+
+#[allow(dead_code)]
+pub fn fill_bytes(buffer: &mut [u8]) {
+    for chunk in buffer.chunks_mut(8) {
+        let bytes = next_u64().to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
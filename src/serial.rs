@@ -0,0 +1,322 @@
+// driver da UART 16550 (porta serial COM1)
+// no qemu com `-serial stdio` tudo que sai aqui aparece no terminal do host, mesmo sem tela
+use crate::console::Console;
+use crate::vga_buffer::{Color, WRITER};
+use core::fmt;
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use lazy_static::lazy_static;
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+pub const COM1: u16 = 0x3F8;
+pub const COM2: u16 = 0x2F8;
+pub const COM3: u16 = 0x3E8;
+pub const COM4: u16 = 0x2E8;
+
+// enderecos padrao das portas; COMn -> COM_BASES[n - 1]
+pub const COM_BASES: [u16; 4] = [COM1, COM2, COM3, COM4];
+
+const CLOCK: u32 = 115200; // clock da UART dividido por 16 -> maior baud possivel
+const DEFAULT_BAUD: u32 = 38400;
+
+// registradores (offset da porta base)
+const DATA: u16 = 0; // com DLAB=1 vira o byte baixo do divisor
+const INTERRUPT_ENABLE: u16 = 1; // com DLAB=1 vira o byte alto do divisor
+const FIFO_CONTROL: u16 = 2;
+const LINE_CONTROL: u16 = 3;
+const MODEM_CONTROL: u16 = 4;
+const LINE_STATUS: u16 = 5;
+
+const IER_RX_AVAILABLE: u8 = 0x01; // interrupcao quando chega byte
+
+const LCR_DLAB: u8 = 0x80; // liga o acesso ao divisor do baud
+const LCR_8N1: u8 = 0x03; // 8 bits, sem paridade, 1 stop bit
+const FCR_ENABLE: u8 = 0xC7; // liga a fifo, limpa rx/tx, interrupcao com 14 bytes
+const MCR_NORMAL: u8 = 0x0B; // DTR + RTS + OUT2 (OUT2 libera a interrupcao pro PIC)
+const MCR_LOOPBACK: u8 = 0x1E; // loopback pra testar se tem uart de verdade ali
+const LSR_DATA_READY: u8 = 0x01;
+const LSR_TRANSMIT_EMPTY: u8 = 0x20;
+
+pub struct SerialPort {
+    base: u16,
+    present: bool, // falhou no teste de loopback -> ignora as escritas (senao travava esperando o THR)
+    foreground: Color, // cor atual pro Console (vira sequencia ansi no terminal do host)
+    background: Color,
+}
+
+impl SerialPort {
+    // unsafe: `base` tem que ser a porta de uma UART 16550
+    pub const unsafe fn new(base: u16) -> SerialPort {
+        SerialPort {
+            base,
+            present: false,
+            foreground: Color::LightGray,
+            background: Color::Black,
+        }
+    }
+
+    fn outb(&self, register: u16, value: u8) {
+        unsafe { Port::new(self.base + register).write(value) }
+    }
+
+    fn inb(&self, register: u16) -> u8 {
+        unsafe { Port::new(self.base + register).read() }
+    }
+
+    // configura baud, formato 8N1 e fifo; retorna false se nao tem uart respondendo
+    pub fn init(&mut self, baud: u32) -> bool {
+        let divisor = (CLOCK / baud.clamp(1, CLOCK)) as u16;
+        self.outb(INTERRUPT_ENABLE, 0x00);
+        self.outb(LINE_CONTROL, LCR_DLAB);
+        self.outb(DATA, (divisor & 0xff) as u8);
+        self.outb(INTERRUPT_ENABLE, (divisor >> 8) as u8);
+        self.outb(LINE_CONTROL, LCR_8N1);
+        self.outb(FIFO_CONTROL, FCR_ENABLE);
+
+        // loopback: o que é enviado volta na leitura
+        self.outb(MODEM_CONTROL, MCR_LOOPBACK);
+        self.outb(DATA, 0xAE);
+        self.present = self.inb(DATA) == 0xAE;
+
+        self.outb(MODEM_CONTROL, MCR_NORMAL);
+        self.present
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.present
+    }
+
+    pub fn base(&self) -> u16 {
+        self.base
+    }
+
+    pub fn send(&mut self, byte: u8) {
+        if !self.present {
+            return;
+        }
+        while self.inb(LINE_STATUS) & LSR_TRANSMIT_EMPTY == 0 {
+            crate::cpu::spin_hint();
+        }
+        self.outb(DATA, byte);
+    }
+
+    // None se nao chegou nada
+    pub fn try_receive(&mut self) -> Option<u8> {
+        if !self.present || self.inb(LINE_STATUS) & LSR_DATA_READY == 0 {
+            return None;
+        }
+        Some(self.inb(DATA))
+    }
+
+    // a partir daqui cada byte recebido gera a IRQ4 (COM1) -> handle_interrupt()
+    pub fn enable_rx_interrupt(&mut self) {
+        if self.present {
+            self.outb(INTERRUPT_ENABLE, IER_RX_AVAILABLE);
+        }
+    }
+
+    pub fn write_string(&mut self, s: &str) {
+        for byte in s.bytes() {
+            self.send(byte);
+        }
+    }
+}
+
+impl fmt::Write for SerialPort {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_string(s);
+        Ok(())
+    }
+}
+
+// cores do VGA -> codigos ansi (30-37, +60 pras versoes claras)
+const VGA_TO_ANSI: [u8; 8] = [0, 4, 2, 6, 1, 5, 3, 7];
+
+fn ansi_code(color: Color) -> u8 {
+    let index = color as u8;
+    let base = VGA_TO_ANSI[(index & 0x07) as usize];
+    if index & 0x08 != 0 {
+        base + 60
+    } else {
+        base
+    }
+}
+
+impl Console for SerialPort {
+    fn write_str(&mut self, s: &str) {
+        self.write_string(s);
+    }
+
+    fn set_color(&mut self, foreground: Color, background: Color) {
+        use core::fmt::Write;
+        self.foreground = foreground;
+        self.background = background;
+        let _ = write!(self, "\x1b[{};{}m", 30 + ansi_code(foreground), 40 + ansi_code(background));
+    }
+
+    fn color(&self) -> (Color, Color) {
+        (self.foreground, self.background)
+    }
+
+    fn clear(&mut self) {
+        self.write_string("\x1b[2J\x1b[H");
+    }
+
+    // terminal do outro lado tem tamanho desconhecido
+    fn dimensions(&self) -> (usize, usize) {
+        (0, 0)
+    }
+}
+
+fn probe(base: u16) -> Mutex<SerialPort> {
+    let mut port = unsafe { SerialPort::new(base) };
+    port.init(DEFAULT_BAUD);
+    Mutex::new(port)
+}
+
+// cada porta é testada (loopback) na primeira vez que é usada
+lazy_static! {
+    pub static ref SERIAL1: Mutex<SerialPort> = probe(COM1);
+    pub static ref SERIAL2: Mutex<SerialPort> = probe(COM2);
+    pub static ref SERIAL3: Mutex<SerialPort> = probe(COM3);
+    pub static ref SERIAL4: Mutex<SerialPort> = probe(COM4);
+}
+
+// numero da porta (1..=4) -> porta; None se o numero nao existe
+pub fn port(number: usize) -> Option<&'static Mutex<SerialPort>> {
+    match number {
+        1 => Some(&SERIAL1),
+        2 => Some(&SERIAL2),
+        3 => Some(&SERIAL3),
+        4 => Some(&SERIAL4),
+        _ => None,
+    }
+}
+
+// portas que responderam no probe, com o numero delas
+pub fn enumerate() -> impl Iterator<Item = (usize, &'static Mutex<SerialPort>)> {
+    (1..=COM_BASES.len())
+        .filter_map(|number| Some((number, port(number)?)))
+        .filter(|(_, port)| port.lock().is_present())
+}
+
+// a COM1 vira o mirror do vga: tudo que vai pro WRITER (println!, shell, tela de panic, barra de status) sai
+// aqui tambem. o console::for_each deixa de escrever nela direto -> nao duplica
+pub fn init() {
+    lazy_static::initialize(&SERIAL1);
+    SERIAL1.lock().enable_rx_interrupt();
+    WRITER.lock().set_mirror(Some(&*SERIAL1));
+}
+
+// bytes recebidos: o handler da interrupcao coloca, read_byte/try_read tiram
+// um produtor (handler) e um consumidor -> só atomics, sem lock
+// cheio -> o byte novo é descartado
+const RX_SIZE: usize = 256;
+
+struct RxBuffer {
+    bytes: [AtomicU8; RX_SIZE],
+    head: AtomicUsize, // proximo slot a escrever
+    tail: AtomicUsize, // proximo slot a ler
+    dropped: AtomicUsize,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_BYTE: AtomicU8 = AtomicU8::new(0);
+
+static RX: RxBuffer = RxBuffer {
+    bytes: [EMPTY_BYTE; RX_SIZE],
+    head: AtomicUsize::new(0),
+    tail: AtomicUsize::new(0),
+    dropped: AtomicUsize::new(0),
+};
+
+impl RxBuffer {
+    fn push(&self, byte: u8) {
+        let head = self.head.load(Ordering::Relaxed);
+        if head.wrapping_sub(self.tail.load(Ordering::Acquire)) == RX_SIZE {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.bytes[head % RX_SIZE].store(byte, Ordering::Relaxed);
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+    }
+
+    fn pop(&self) -> Option<u8> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail == self.head.load(Ordering::Acquire) {
+            return None;
+        }
+        let byte = self.bytes[tail % RX_SIZE].load(Ordering::Relaxed);
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Some(byte)
+    }
+}
+
+// tira tudo que esta na fifo da uart e poe no buffer
+// nao pega o lock do SERIAL1: a interrupcao pode ter chegado no meio de um serial_print!
+// (ler LSR/DATA nao atrapalha quem esta transmitindo)
+fn receive_pending() {
+    let mut status: Port<u8> = Port::new(COM1 + LINE_STATUS);
+    let mut data: Port<u8> = Port::new(COM1 + DATA);
+    unsafe {
+        loop {
+            let lsr = status.read();
+            // 0xff -> nao tem uart nessa porta (barramento flutuando)
+            if lsr == 0xff || lsr & LSR_DATA_READY == 0 {
+                break;
+            }
+            RX.push(data.read());
+        }
+    }
+}
+
+// chamado pelo handler da IRQ4
+pub fn handle_interrupt() {
+    crate::rand::add_entropy(crate::time::rdtsc());
+    receive_pending();
+    crate::deferred::raise(crate::deferred::Softirq::Serial);
+}
+
+// None se nao tem nada pra ler
+// com interrupcao desligada ninguem enche o buffer -> olha a uart direto
+pub fn try_read() -> Option<u8> {
+    if !x86_64::instructions::interrupts::are_enabled() {
+        receive_pending();
+    }
+    RX.pop()
+}
+
+// espera ate chegar um byte
+#[allow(dead_code)]
+pub fn read_byte() -> u8 {
+    loop {
+        if let Some(byte) = try_read() {
+            return byte;
+        }
+        crate::cpu::wait_for_interrupt();
+    }
+}
+
+// bytes perdidos com o buffer cheio desde o boot
+#[allow(dead_code)]
+pub fn rx_dropped() -> usize {
+    RX.dropped.load(Ordering::Relaxed)
+}
+
+#[macro_export]
+macro_rules! serial_print {
+    ($($arg:tt)*) => ($crate::serial::_print(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! serial_println {
+    () => ($crate::serial_print!("\n"));
+    ($($arg:tt)*) => ($crate::serial_print!("{}\n", format_args!($($arg)*)));
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use core::fmt::Write;
+    let _irq = crate::cpu::IrqGuard::new();
+    SERIAL1.lock().write_fmt(args).unwrap();
+}
@@ -0,0 +1,141 @@
+// shell pela serial pra quando nao tem tela (qemu headless)
+// o terminal do outro lado manda os bytes crus: aqui faz o eco e a edicao com os caracteres de controle
+//   backspace/DEL apaga, ctrl-u apaga a linha, ctrl-c cancela, enter executa
+// sequencias de escape (setas...) sao ignoradas
+use crate::line_editor::{Line, MAX_LINE};
+use crate::serial::SERIAL1;
+use crate::shell;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7f;
+const CTRL_C: u8 = 0x03;
+const CTRL_U: u8 = 0x15;
+const ESCAPE: u8 = 0x1b;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    Start, // recebeu ESC
+    Csi,   // recebeu ESC [ -> ignora ate a letra final
+}
+
+struct SerialShell {
+    line: Line,
+    escape: Escape,
+    last_was_cr: bool, // "\r\n" conta como um enter só
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SHELL: Mutex<SerialShell> = Mutex::new(SerialShell {
+    line: Line::new(),
+    escape: Escape::None,
+    last_was_cr: false,
+});
+
+// o terminal serial em modo raw precisa de "\r\n"
+struct Out;
+
+impl fmt::Write for Out {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut port = SERIAL1.lock();
+        for byte in s.bytes() {
+            if byte == b'\n' {
+                port.send(b'\r');
+            }
+            port.send(byte);
+        }
+        Ok(())
+    }
+}
+
+fn send(s: &str) {
+    let _ = fmt::Write::write_str(&mut Out, s);
+}
+
+pub fn set_enabled(enabled: bool) {
+    let was = ENABLED.swap(enabled, Ordering::Relaxed);
+    if enabled && !was {
+        send("\nshell serial (help lista os comandos)\n");
+        send(shell::PROMPT);
+    }
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// softirq da serial (roda no loop principal): trata o que chegou desde a ultima vez
+pub fn poll() {
+    if !enabled() {
+        return;
+    }
+    while let Some(byte) = crate::serial::try_read() {
+        let line = SHELL.lock().handle_byte(byte);
+        // o lock do SHELL é solto antes de rodar o comando
+        if let Some(line) = line {
+            shell::dispatch(&line, &mut Out);
+            send(shell::PROMPT);
+        }
+    }
+}
+
+impl SerialShell {
+    // retorna a linha quando chega o enter
+    fn handle_byte(&mut self, byte: u8) -> Option<Line> {
+        let was_cr = core::mem::replace(&mut self.last_was_cr, byte == b'\r');
+        match self.escape {
+            Escape::Start => {
+                self.escape = if byte == b'[' { Escape::Csi } else { Escape::None };
+                return None;
+            }
+            Escape::Csi => {
+                if (0x40..=0x7e).contains(&byte) {
+                    self.escape = Escape::None;
+                }
+                return None;
+            }
+            Escape::None => {}
+        }
+        match byte {
+            b'\n' if was_cr => None,
+            b'\r' | b'\n' => {
+                send("\n");
+                Some(core::mem::take(&mut self.line))
+            }
+            BACKSPACE | DELETE => {
+                if self.line.pop().is_some() {
+                    send("\x08 \x08");
+                }
+                None
+            }
+            CTRL_U => {
+                while self.line.pop().is_some() {
+                    send("\x08 \x08");
+                }
+                None
+            }
+            CTRL_C => {
+                self.line.clear();
+                send("^C\n");
+                send(shell::PROMPT);
+                None
+            }
+            ESCAPE => {
+                self.escape = Escape::Start;
+                None
+            }
+            // só ascii imprimivel (utf-8 vindo byte a byte ficaria quebrado)
+            0x20..=0x7e => {
+                if self.line.len() < MAX_LINE && self.line.push(byte as char).is_ok() {
+                    let mut buffer = [0u8; 1];
+                    send((byte as char).encode_utf8(&mut buffer));
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,466 @@
+// comandos do shell: a mesma tabela serve pro shell da tela e pro da serial
+// cada um le a linha do seu jeito e chama dispatch() com a saida dele
+// ex: shell::dispatch("theme dark", &mut out);
+use crate::keyboard::{Key, KeyEvent};
+use crate::line_editor::{EditKey, LineEditor};
+use crate::vga_buffer::WRITER;
+use core::fmt::{self, Write};
+use log::LevelFilter;
+use spin::Mutex;
+
+pub type Output<'a> = &'a mut dyn Write;
+
+pub struct Command {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub run: fn(args: &str, out: Output),
+}
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        help: "lista os comandos",
+        run: help,
+    },
+    Command {
+        name: "echo",
+        help: "echo <texto> - repete o texto",
+        run: echo,
+    },
+    Command {
+        name: "version",
+        help: "nome e versao do kernel",
+        run: version,
+    },
+    Command {
+        name: "ticks",
+        help: "ticks do timer desde o boot",
+        run: ticks,
+    },
+    Command {
+        name: "theme",
+        help: "theme [nome] - troca o tema de cores (sem nome lista os temas)",
+        run: theme,
+    },
+    Command {
+        name: "rx",
+        help: "rx [porta] - recebe um arquivo por xmodem (padrao COM1)",
+        run: rx,
+    },
+    Command {
+        name: "irqstat",
+        help: "quantas vezes cada interrupcao disparou",
+        run: irqstat,
+    },
+    Command {
+        name: "syscall",
+        help: "testa as chamadas de sistema pelo int 0x80",
+        run: syscall,
+    },
+    Command {
+        name: "slabinfo",
+        help: "caches de objetos (slab) e quanto cada um usa",
+        run: slabinfo,
+    },
+    Command {
+        name: "translate",
+        help: "translate <endereco> - endereco fisico e flags de um endereco virtual",
+        run: translate,
+    },
+    Command {
+        name: "vmas",
+        help: "regioes do espaco de enderecamento do kernel",
+        run: vmas,
+    },
+    Command {
+        name: "demand",
+        help: "testa o demand paging: reserva 64 KiB sob demanda e encosta em 3 paginas",
+        run: demand,
+    },
+    Command {
+        name: "cow",
+        help: "testa o copy-on-write: clona uma regiao e escreve na copia",
+        run: cow,
+    },
+    Command {
+        name: "ptdump",
+        help: "ptdump <inicio> [fim] - paginas mapeadas no intervalo (tabelas de pagina)",
+        run: ptdump,
+    },
+    Command {
+        name: "oom",
+        help: "hooks de falta de memoria e as ultimas vezes que faltou",
+        run: oom,
+    },
+    Command {
+        name: "memtest",
+        help: "memtest [MiB] - testa a ram livre (0x55/0xaa/endereco) e tira os frames ruins de uso",
+        run: memtest,
+    },
+    Command {
+        name: "meminfo",
+        help: "frames fisicos, heap e paginas mapeadas",
+        run: meminfo,
+    },
+    Command {
+        name: "loglevel",
+        help: "loglevel [off|error|warn|info|debug|trace]",
+        run: loglevel,
+    },
+];
+
+pub const PROMPT: &str = "> ";
+
+// retorna false se o comando nao existe (a mensagem de erro ja foi escrita em `out`)
+pub fn dispatch(line: &str, out: Output) -> bool {
+    let line = line.trim();
+    if line.is_empty() {
+        return true;
+    }
+    let (name, args) = match line.find(' ') {
+        Some(space) => (&line[..space], line[space + 1..].trim_start()),
+        None => (line, ""),
+    };
+    match COMMANDS.iter().find(|command| command.name == name) {
+        Some(command) => {
+            (command.run)(args, out);
+            true
+        }
+        None => {
+            let _ = writeln!(out, "comando desconhecido: {} (help lista os comandos)", name);
+            false
+        }
+    }
+}
+
+// shell da tela: as teclas chegam do driver de teclado e a linha é editada pelo LineEditor
+static EDITOR: Mutex<LineEditor> = Mutex::new(LineEditor::new());
+
+// saida dos comandos no shell da tela vai pelo print! normal (todos os consoles)
+struct Screen;
+
+impl fmt::Write for Screen {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        crate::print!("{}", s);
+        Ok(())
+    }
+}
+
+pub fn start() {
+    EDITOR.lock().begin(PROMPT);
+    crate::keyboard::set_consumer(Some(handle_key_event));
+}
+
+// Alt+F1..F4 troca de terminal, Shift+PageUp/PageDown rola o historico, o resto vai pro editor
+pub fn handle_key_event(event: KeyEvent) {
+    if !event.pressed {
+        return;
+    }
+    let modifiers = event.modifiers;
+    match event.key {
+        Key::F(n) if modifiers.alt => {
+            crate::tty::handle_function_key(n);
+            return;
+        }
+        Key::PageUp if modifiers.shift => {
+            WRITER.lock().page_up();
+            return;
+        }
+        Key::PageDown if modifiers.shift => {
+            WRITER.lock().page_down();
+            return;
+        }
+        _ => {}
+    }
+    let key = match event.key {
+        Key::Char(c) if !modifiers.ctrl && !modifiers.alt => EditKey::Char(c),
+        Key::Backspace => EditKey::Backspace,
+        Key::Delete => EditKey::Delete,
+        Key::Left => EditKey::Left,
+        Key::Right => EditKey::Right,
+        Key::Home => EditKey::Home,
+        Key::End => EditKey::End,
+        Key::Enter => EditKey::Enter,
+        _ => return,
+    };
+    // o lock do EDITOR é solto antes de rodar o comando
+    let line = EDITOR.lock().handle_key(key);
+    if let Some(line) = line {
+        dispatch(&line, &mut Screen);
+        EDITOR.lock().begin(PROMPT);
+    }
+}
+
+fn help(_args: &str, out: Output) {
+    for command in COMMANDS {
+        let _ = writeln!(out, "  {:<10} {}", command.name, command.help);
+    }
+}
+
+fn echo(args: &str, out: Output) {
+    let _ = writeln!(out, "{}", args);
+}
+
+fn version(_args: &str, out: Output) {
+    let _ = writeln!(out, "{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+}
+
+fn ticks(_args: &str, out: Output) {
+    let _ = writeln!(out, "{} ticks ({} ms)", crate::time::ticks(), crate::time::uptime_ms());
+}
+
+fn theme(args: &str, out: Output) {
+    if args.is_empty() {
+        for theme in crate::theme::THEMES.iter() {
+            let marker = if theme.name == crate::theme::current().name { '*' } else { ' ' };
+            let _ = writeln!(out, " {} {}", marker, theme.name);
+        }
+    } else if !crate::theme::set_theme(args) {
+        let _ = writeln!(out, "tema nao existe: {}", args);
+    }
+}
+
+fn loglevel(args: &str, out: Output) {
+    let level = match args {
+        "" => {
+            let _ = writeln!(out, "{}", crate::logger::level());
+            return;
+        }
+        "off" => LevelFilter::Off,
+        "error" => LevelFilter::Error,
+        "warn" => LevelFilter::Warn,
+        "info" => LevelFilter::Info,
+        "debug" => LevelFilter::Debug,
+        "trace" => LevelFilter::Trace,
+        _ => {
+            let _ = writeln!(out, "nivel invalido: {}", args);
+            return;
+        }
+    };
+    crate::logger::set_level(level);
+}
+
+fn rx(args: &str, out: Output) {
+    let number = match args {
+        "" => 1,
+        _ => match args.parse() {
+            Ok(number) => number,
+            Err(_) => {
+                let _ = writeln!(out, "porta invalida: {}", args);
+                return;
+            }
+        },
+    };
+    let _ = writeln!(out, "esperando xmodem na COM{}...", number);
+    let mut payload = crate::xmodem::PAYLOAD.lock();
+    let payload = &mut *payload;
+    match crate::xmodem::receive(number, &mut payload.bytes) {
+        Ok(len) => {
+            payload.len = len;
+            let _ = writeln!(out, "{} bytes em {:p}", len, payload.bytes.as_ptr());
+        }
+        Err(error) => {
+            let _ = writeln!(out, "xmodem falhou: {}", error);
+        }
+    }
+}
+
+fn syscall(_args: &str, out: Output) {
+    use crate::syscall::{int80, SYS_UPTIME, SYS_WRITE};
+    use x86_64::structures::paging::PageTableFlags;
+    let _ = writeln!(out, "uptime: {} ms", int80(SYS_UPTIME, [0; 3]));
+    // o write só aceita memoria do ring 3 -> o texto vai pra uma pagina USER; o da imagem do kernel tem que falhar
+    let text = "write pelo int 0x80\n";
+    let flags = PageTableFlags::PRESENT
+        | PageTableFlags::WRITABLE
+        | PageTableFlags::NO_EXECUTE
+        | PageTableFlags::USER_ACCESSIBLE;
+    let page = match crate::vm::with_kernel(|space| space.reserve_lazy(4096, 4096, flags, "teste syscall")) {
+        Ok(page) => page,
+        Err(error) => {
+            let _ = writeln!(out, "reserve falhou: {}", error);
+            return;
+        }
+    };
+    unsafe { core::ptr::copy_nonoverlapping(text.as_ptr(), page.as_mut_ptr::<u8>(), text.len()) };
+    let written = int80(SYS_WRITE, [page.as_u64(), text.len() as u64, 0]);
+    let _ = writeln!(out, "write: {}", written);
+    let kernel = int80(SYS_WRITE, [text.as_ptr() as u64, text.len() as u64, 0]);
+    let _ = writeln!(out, "write de memoria do kernel: {}", kernel);
+    if let Err(error) = crate::vm::with_kernel(|space| space.unmap_region(page)) {
+        let _ = writeln!(out, "unmap falhou: {}", error);
+    }
+    let _ = writeln!(out, "numero invalido: {}", int80(u64::MAX, [0; 3]));
+}
+
+fn irqstat(_args: &str, out: Output) {
+    for (vector, count) in crate::interrupts::stats() {
+        let _ = writeln!(out, "  {:>3} {:>12}  {}", vector, count, crate::interrupts::vector_name(vector));
+    }
+}
+
+fn slabinfo(_args: &str, out: Output) {
+    let caches = crate::allocator::slab::caches();
+    if caches.is_empty() {
+        let _ = writeln!(out, "nenhum cache em uso");
+    }
+    for stats in caches {
+        let _ = writeln!(out, "  {}", stats);
+    }
+}
+
+fn vmas(_args: &str, out: Output) {
+    crate::vm::with_kernel(|space| {
+        for region in space.regions() {
+            let _ = writeln!(out, "  {}", region);
+        }
+    });
+}
+
+fn demand(_args: &str, out: Output) {
+    use x86_64::structures::paging::PageTableFlags;
+    let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+    let start = match crate::vm::with_kernel(|space| space.reserve_lazy(64 * 1024, 4096, flags, "teste")) {
+        Ok(start) => start,
+        Err(error) => {
+            let _ = writeln!(out, "reserve falhou: {}", error);
+            return;
+        }
+    };
+    let before = crate::vm::demand_faults();
+    let free = crate::memory::frame::free_frames();
+    for page in [0u64, 5, 15] {
+        let ptr = (start + page * 4096).as_mut_ptr::<u64>();
+        unsafe {
+            // pagina nova tem que vir zerada
+            let old = ptr.read_volatile();
+            ptr.write_volatile(page);
+            let _ = writeln!(out, "pagina {:>2}: lido {} escrito {}", page, old, ptr.read_volatile());
+        }
+    }
+    let _ = writeln!(
+        out,
+        "{} faults, {} frames usados",
+        crate::vm::demand_faults() - before,
+        free - crate::memory::frame::free_frames()
+    );
+    if let Err(error) = crate::vm::with_kernel(|space| space.unmap_region(start)) {
+        let _ = writeln!(out, "unmap falhou: {}", error);
+    }
+}
+
+fn cow(_args: &str, out: Output) {
+    use crate::vm;
+    use x86_64::structures::paging::PageTableFlags;
+    let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+    // a escrita fica fora do with_kernel: o page fault da pagina sob demanda precisa do lock
+    let setup = vm::with_kernel(|space| space.reserve_lazy(2 * 4096, 4096, flags, "cow original")).and_then(|original| {
+        unsafe { original.as_mut_ptr::<u64>().write_volatile(42) };
+        let copy = vm::with_kernel(|space| space.clone_region_cow(original, "cow copia"))?;
+        Ok((original, copy))
+    });
+    let (original, copy) = match setup {
+        Ok(regions) => regions,
+        Err(error) => {
+            let _ = writeln!(out, "clone falhou: {}", error);
+            return;
+        }
+    };
+    let before = vm::cow_copies();
+    unsafe {
+        let _ = writeln!(out, "copia antes da escrita: {}", copy.as_ptr::<u64>().read_volatile());
+        copy.as_mut_ptr::<u64>().write_volatile(7);
+        let _ = writeln!(
+            out,
+            "original {} copia {} ({} frame copiado)",
+            original.as_ptr::<u64>().read_volatile(),
+            copy.as_ptr::<u64>().read_volatile(),
+            vm::cow_copies() - before
+        );
+    }
+    vm::with_kernel(|space| {
+        let _ = space.unmap_region(copy);
+        let _ = space.unmap_region(original);
+    });
+}
+
+fn meminfo(_args: &str, out: Output) {
+    let _ = writeln!(out, "{}", crate::memory::stats());
+}
+
+fn memtest(args: &str, out: Output) {
+    let limit = match args.trim() {
+        "" => None,
+        text => match text.parse::<usize>().ok().and_then(|mib| mib.checked_mul(256)) {
+            Some(frames) => Some(frames), // frames de 4 KiB
+            None => {
+                let _ = writeln!(out, "uso: memtest [MiB]");
+                return;
+            }
+        },
+    };
+    let _ = writeln!(out, "testando...");
+    let report = crate::memory::memtest::run(limit);
+    let _ = writeln!(out, "{} KiB testados, {} frames ruins", report.tested * 4, report.bad_frames);
+    for failure in &report.failures {
+        let _ = writeln!(out, "  {}", failure);
+    }
+    let quarantined = crate::memory::memtest::quarantined();
+    if !quarantined.is_empty() {
+        let _ = writeln!(out, "{} frames de quarentena desde o boot", quarantined.len());
+    }
+    if report.leaked > 0 {
+        let _ = writeln!(out, "{} frames ruins fora da lista (lotada), presos sem registro", report.leaked);
+    }
+}
+
+fn oom(_args: &str, out: Output) {
+    let hooks = crate::allocator::oom::hooks();
+    let _ = writeln!(out, "hooks: {}", if hooks.is_empty() { "nenhum" } else { "" });
+    for (order, name) in hooks.iter().enumerate() {
+        let _ = writeln!(out, "  {}. {}", order + 1, name);
+    }
+    let _ = writeln!(out, "faltou memoria {} vezes", crate::allocator::oom::count());
+    for event in crate::allocator::oom::events() {
+        let _ = writeln!(out, "  {}", event);
+    }
+}
+
+// aceita com ou sem 0x, sempre em hexa
+fn parse_address(text: &str) -> Option<u64> {
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+fn translate(args: &str, out: Output) {
+    let addr = match parse_address(args).and_then(|addr| x86_64::VirtAddr::try_new(addr).ok()) {
+        Some(addr) => addr,
+        None => {
+            let _ = writeln!(out, "endereco invalido: {}", args);
+            return;
+        }
+    };
+    match crate::memory::with_mapper(|mapper| mapper.translate(addr)) {
+        Some((phys, flags)) => {
+            let _ = writeln!(out, "{:#x} -> {:#x} {:?}", addr.as_u64(), phys.as_u64(), flags);
+        }
+        None => {
+            let _ = writeln!(out, "{:#x} nao mapeado", addr.as_u64());
+        }
+    }
+}
+
+fn ptdump(args: &str, out: Output) {
+    let mut words = args.split_whitespace();
+    let mut address = || words.next().and_then(parse_address).and_then(|addr| x86_64::VirtAddr::try_new(addr).ok());
+    let start = match address() {
+        Some(start) => start,
+        None => {
+            let _ = writeln!(out, "uso: ptdump <inicio> [fim]");
+            return;
+        }
+    };
+    // sem fim: só a pagina do inicio
+    let end = address().unwrap_or(start + 1u64);
+    let _ = crate::memory::dump_mapping(start..end, out);
+}
@@ -0,0 +1,303 @@
+// acorda as outras cpus (APs): a MADT diz quais existem, e cada uma acorda em modo real
+// no endereco do SIPI -> um trampolim de 16 bits copiado pra memoria baixa leva ela direto pro modo 64 bits
+// e chama ap_entry, que carrega GDT/IDT/apic dela e estaciona num hlt esperando o escalonador
+// precisa do local apic ligado (feature apic): o INIT e o SIPI saem por ele
+use crate::{acpi, apic, memory, time};
+use core::arch::global_asm;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
+use x86_64::registers::control::Cr3;
+use x86_64::{PhysAddr, VirtAddr};
+
+// cpus que o kernel sabe usar (o resto da MADT fica dormindo)
+pub const MAX_CPUS: usize = 8;
+
+// stack em que a AP roda o ap_entry (a do BSP é a do bootloader)
+const STACK_PAGES: usize = 4;
+
+// trampolim + tabelas de pagina temporarias (PML4, PDPT, PD), uma pagina cada
+const TRAMPOLINE_PAGES: u64 = 4;
+
+const PRESENT: u64 = 1 << 0;
+const WRITABLE: u64 = 1 << 1;
+const HUGE: u64 = 1 << 7;
+const ADDRESS_MASK: u64 = 0x000f_ffff_ffff_f000;
+
+static ONLINE: AtomicUsize = AtomicUsize::new(1);
+// a AP da o sinal aqui quando chega no ap_entry (uma acorda de cada vez)
+static STARTED: AtomicBool = AtomicBool::new(false);
+static KERNEL_CR3: AtomicU64 = AtomicU64::new(0);
+
+// modo real -> modo longo direto: PAE + LME e depois PE e PG juntos no CR0, sem passar pelo modo protegido
+// o codigo roda no endereco onde for copiado, por isso tudo é relativo ao inicio e os enderecos
+// absolutos (base da GDT, destino do far jump) sao escritos pelo kernel antes do SIPI
+global_asm!(
+    ".pushsection .rodata.smp_trampoline, \"a\"",
+    ".global smp_trampoline_start",
+    ".global smp_trampoline_long",
+    ".global smp_trampoline_gdt",
+    ".global smp_trampoline_gdtr",
+    ".global smp_trampoline_far",
+    ".global smp_trampoline_cr3",
+    ".global smp_trampoline_stack",
+    ".global smp_trampoline_arg",
+    ".global smp_trampoline_entry",
+    ".global smp_trampoline_end",
+    ".code16",
+    "smp_trampoline_start:",
+    "cli",
+    "cld",
+    "mov ax, cs",
+    "mov ds, ax",
+    // mov eax, [cr3] (o montador nao aceita a diferenca de simbolos como endereco)
+    ".byte 0x66, 0xa1",
+    ".word smp_trampoline_cr3 - smp_trampoline_start",
+    "mov cr3, eax",
+    "mov eax, cr4",
+    "or eax, 1 << 5", // PAE
+    "mov cr4, eax",
+    "mov ecx, 0xC0000080", // EFER
+    "rdmsr",
+    "or eax, (1 << 8) | (1 << 11)", // LME + NXE (as tabelas copiadas do kernel já tem NX)
+    "wrmsr",
+    // lgdt com prefixo 0x66: base de 32 bits
+    ".byte 0x66, 0x0f, 0x01, 0x16",
+    ".word smp_trampoline_gdtr - smp_trampoline_start",
+    "mov eax, cr0",
+    "or eax, 0x80000001", // PG + PE
+    "mov cr0, eax",
+    // jmp far [m16:32] pro segmento de 64 bits
+    ".byte 0x66, 0xff, 0x2e",
+    ".word smp_trampoline_far - smp_trampoline_start",
+    ".code64",
+    "smp_trampoline_long:",
+    "xor eax, eax",
+    "mov ds, ax",
+    "mov es, ax",
+    "mov ss, ax",
+    "mov fs, ax",
+    "mov gs, ax",
+    "mov rsp, [rip + smp_trampoline_stack]",
+    "mov rdi, [rip + smp_trampoline_arg]",
+    "mov rax, [rip + smp_trampoline_entry]",
+    "call rax",
+    "2:",
+    "hlt",
+    "jmp 2b",
+    ".balign 16",
+    "smp_trampoline_gdt:",
+    ".quad 0",
+    ".quad 0x00209a0000000000", // 0x08: codigo 64 bits
+    ".quad 0x0000920000000000", // 0x10: dados
+    "smp_trampoline_gdtr:",
+    ".word 23",
+    ".long 0",
+    ".balign 8",
+    "smp_trampoline_far:",
+    ".long 0",
+    ".word 0x08",
+    ".balign 8",
+    "smp_trampoline_cr3: .quad 0",
+    "smp_trampoline_stack: .quad 0",
+    "smp_trampoline_arg: .quad 0",
+    "smp_trampoline_entry: .quad 0",
+    "smp_trampoline_end:",
+    ".popsection",
+);
+
+extern "C" {
+    static smp_trampoline_start: u8;
+    static smp_trampoline_long: u8;
+    static smp_trampoline_gdt: u8;
+    static smp_trampoline_gdtr: u8;
+    static smp_trampoline_far: u8;
+    static smp_trampoline_cr3: u8;
+    static smp_trampoline_stack: u8;
+    static smp_trampoline_arg: u8;
+    static smp_trampoline_entry: u8;
+    static smp_trampoline_end: u8;
+}
+
+// trampolim copiado em `base` (fisico, abaixo de 1 MiB)
+struct Trampoline {
+    base: u64,
+}
+
+impl Trampoline {
+    fn offset(symbol: *const u8) -> u64 {
+        symbol as u64 - core::ptr::addr_of!(smp_trampoline_start) as u64
+    }
+
+    fn virt(&self, offset: u64) -> *mut u8 {
+        memory::phys_to_virt(PhysAddr::new(self.base + offset)).as_mut_ptr()
+    }
+
+    fn write_u32(&self, symbol: *const u8, value: u32) {
+        unsafe { core::ptr::write_unaligned(self.virt(Self::offset(symbol)) as *mut u32, value) };
+    }
+
+    fn write_u64(&self, symbol: *const u8, value: u64) {
+        unsafe { core::ptr::write_unaligned(self.virt(Self::offset(symbol)) as *mut u64, value) };
+    }
+
+    fn install(base: u64) -> Trampoline {
+        let trampoline = Trampoline { base };
+        unsafe {
+            let start = core::ptr::addr_of!(smp_trampoline_start);
+            let len = core::ptr::addr_of!(smp_trampoline_end) as usize - start as usize;
+            core::ptr::copy_nonoverlapping(start, trampoline.virt(0), len);
+
+            let gdt = base + Self::offset(core::ptr::addr_of!(smp_trampoline_gdt));
+            trampoline.write_u32(core::ptr::addr_of!(smp_trampoline_gdtr).add(2), gdt as u32);
+            let long = base + Self::offset(core::ptr::addr_of!(smp_trampoline_long));
+            trampoline.write_u32(core::ptr::addr_of!(smp_trampoline_far), long as u32);
+            let pml4 = build_page_tables(base + 0x1000);
+            trampoline.write_u64(core::ptr::addr_of!(smp_trampoline_cr3), pml4);
+            trampoline.write_u64(core::ptr::addr_of!(smp_trampoline_entry), ap_entry as *const () as u64);
+        }
+        trampoline
+    }
+
+    fn prepare(&self, cpu: usize, stack: VirtAddr) {
+        self.write_u64(core::ptr::addr_of!(smp_trampoline_stack), stack.as_u64());
+        self.write_u64(core::ptr::addr_of!(smp_trampoline_arg), cpu as u64);
+    }
+
+    // vetor do SIPI: a pagina onde a cpu comeca a executar
+    fn vector(&self) -> u32 {
+        (self.base >> 12) as u32
+    }
+}
+
+// copia das tabelas do kernel + os primeiros 2 MiB mapeados 1:1 (o trampolim roda no endereco fisico)
+// o ap_entry troca pro CR3 do kernel logo de cara, entao isso só vive alguns instantes
+// `base`: 3 paginas fisicas livres abaixo de 4 GiB (o CR3 é carregado em modo 16 bits)
+unsafe fn build_page_tables(base: u64) -> u64 {
+    let table = |phys: u64| memory::phys_to_virt(PhysAddr::new(phys)).as_mut_ptr::<u64>();
+    let (pml4, pdpt, pd) = (base, base + 0x1000, base + 0x2000);
+    let kernel_pml4 = Cr3::read().0.start_address().as_u64();
+    core::ptr::copy_nonoverlapping(table(kernel_pml4), table(pml4), 512);
+    core::ptr::write_bytes(table(pdpt), 0, 512);
+    core::ptr::write_bytes(table(pd), 0, 512);
+
+    let pml4_entry = *table(kernel_pml4);
+    if pml4_entry & PRESENT != 0 {
+        core::ptr::copy_nonoverlapping(table(pml4_entry & ADDRESS_MASK), table(pdpt), 512);
+        let pdpt_entry = *table(pdpt);
+        if pdpt_entry & PRESENT != 0 && pdpt_entry & HUGE != 0 {
+            // pagina de 1 GiB: quebra em 512 de 2 MiB com os mesmos flags
+            let flags = pdpt_entry & !ADDRESS_MASK;
+            for i in 0..512 {
+                *table(pd).add(i) = ((pdpt_entry & ADDRESS_MASK) + (i as u64) * 0x20_0000) | flags;
+            }
+        } else if pdpt_entry & PRESENT != 0 {
+            core::ptr::copy_nonoverlapping(table(pdpt_entry & ADDRESS_MASK), table(pd), 512);
+        }
+    }
+    *table(pd) = PRESENT | WRITABLE | HUGE; // 0..2 MiB -> 0..2 MiB
+    *table(pdpt) = pd | PRESENT | WRITABLE;
+    *table(pml4) = pdpt | PRESENT | WRITABLE;
+    pml4
+}
+
+// primeira coisa em Rust numa AP
+extern "C" fn ap_entry(cpu: u64) -> ! {
+    let (frame, flags) = Cr3::read();
+    let kernel = KERNEL_CR3.load(Ordering::Relaxed);
+    if frame.start_address().as_u64() != kernel {
+        unsafe {
+            Cr3::write(x86_64::structures::paging::PhysFrame::containing_address(PhysAddr::new(kernel)), flags);
+        }
+    }
+    crate::percpu::init(cpu as usize);
+    crate::gdt::init_ap(cpu as usize);
+    crate::interrupts::init_idt();
+    apic::init();
+    crate::cpu::fpu::init();
+    crate::vm::init_cpu();
+    crate::syscall::init();
+    ONLINE.fetch_add(1, Ordering::Relaxed);
+    STARTED.store(true, Ordering::Release);
+    x86_64::instructions::interrupts::enable();
+    crate::cpu::hlt_loop(|| {})
+}
+
+fn delay(duration: Duration) {
+    let deadline = time::Instant::now() + duration;
+    while !deadline.has_passed() {
+        crate::cpu::spin_hint();
+    }
+}
+
+fn wait_started(timeout: Duration) -> bool {
+    let deadline = time::Instant::now() + timeout;
+    while !deadline.has_passed() {
+        if STARTED.load(Ordering::Acquire) {
+            return true;
+        }
+        crate::cpu::spin_hint();
+    }
+    STARTED.load(Ordering::Acquire)
+}
+
+// INIT, 10 ms, SIPI, e um segundo SIPI se ela nao respondeu (a sequencia do manual da intel)
+// a stack tem que estar na mesma entrada da PML4 que as do BSP (é uma copia dela que a AP ve no começo)
+// -> ok pq o gdt::init do BSP já alocou as dele do memory::stack
+// nunca é liberada: mesmo se a AP nao responder ela ainda pode acordar depois
+fn start_cpu(trampoline: &Trampoline, cpu: usize, apic_id: u32) -> bool {
+    let stack = match memory::stack::allocate(STACK_PAGES, "ap boot", cpu) {
+        Ok(stack) => stack.leak(),
+        Err(error) => {
+            log::warn!("sem stack pra cpu {}: {}", cpu, error);
+            return false;
+        }
+    };
+    trampoline.prepare(cpu, stack);
+    STARTED.store(false, Ordering::Release);
+    apic::send_ipi(apic_id, apic::IPI_INIT | apic::IPI_ASSERT);
+    delay(Duration::from_millis(10));
+    for _ in 0..2 {
+        apic::send_ipi(apic_id, apic::IPI_STARTUP | trampoline.vector());
+        if wait_started(Duration::from_millis(100)) {
+            return true;
+        }
+    }
+    false
+}
+
+// acorda as APs da MADT; retorna quantas cpus ficaram online (contando o BSP)
+// chamado com as interrupcoes ligadas (os tempos de espera usam o Instant)
+pub fn init() -> usize {
+    let madt = match acpi::madt() {
+        Some(madt) if apic::is_enabled() => madt,
+        _ => return online(),
+    };
+    let base = match memory::find_low_memory(TRAMPOLINE_PAGES * 4096) {
+        Some(base) => base.as_u64(),
+        None => {
+            log::warn!("smp: sem memoria livre abaixo de 1 MiB pro trampolim");
+            return online();
+        }
+    };
+    KERNEL_CR3.store(Cr3::read().0.start_address().as_u64(), Ordering::Relaxed);
+    let trampoline = Trampoline::install(base);
+
+    let bsp = apic::id();
+    let mut cpu = 1;
+    for info in madt.local_apics.iter().filter(|info| info.enabled && info.apic_id as u32 != bsp) {
+        if cpu == MAX_CPUS {
+            log::warn!("smp: mais de {} cpus, o resto fica dormindo", MAX_CPUS);
+            break;
+        }
+        if start_cpu(&trampoline, cpu, info.apic_id as u32) {
+            cpu += 1;
+        } else {
+            log::warn!("smp: cpu com apic id {} nao respondeu", info.apic_id);
+        }
+    }
+    online()
+}
+
+pub fn online() -> usize {
+    ONLINE.load(Ordering::Relaxed)
+}
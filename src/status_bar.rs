@@ -0,0 +1,164 @@
+// barra de status: linha fixa com campos tipo "uptime: 12s | mem: 30MB | tty: 1"
+// cada subsistema atualiza o proprio campo pelo nome e a linha inteira é redesenhada
+use crate::theme::{self, Role};
+use crate::vga_buffer::{StatusPosition, MAX_WIDTH, WRITER};
+use core::fmt;
+use spin::Mutex;
+
+const MAX_FIELDS: usize = 8;
+const VALUE_LEN: usize = 24;
+
+// valor formatado sem alocar (ainda nao tem heap)
+#[derive(Clone, Copy)]
+struct FieldValue {
+    bytes: [u8; VALUE_LEN],
+    len: usize,
+}
+
+impl FieldValue {
+    #[allow(dead_code)]
+    const fn new() -> FieldValue {
+        FieldValue {
+            bytes: [0; VALUE_LEN],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or("")
+    }
+}
+
+// o que nao cabe é cortado (sem quebrar um char utf-8 no meio)
+impl fmt::Write for FieldValue {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            let mut buf = [0; 4];
+            let encoded = c.encode_utf8(&mut buf).as_bytes();
+            if self.len + encoded.len() > VALUE_LEN {
+                break;
+            }
+            self.bytes[self.len..self.len + encoded.len()].copy_from_slice(encoded);
+            self.len += encoded.len();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Field {
+    name: &'static str,
+    value: FieldValue,
+}
+
+pub struct StatusBar {
+    fields: [Option<Field>; MAX_FIELDS],
+}
+
+static STATUS_BAR: Mutex<StatusBar> = Mutex::new(StatusBar {
+    fields: [None; MAX_FIELDS],
+});
+
+impl StatusBar {
+    // cria o campo se ainda nao existe; retorna false se nao tem mais espaco
+    #[allow(dead_code)]
+    pub fn set_field(&mut self, name: &'static str, args: fmt::Arguments) -> bool {
+        use core::fmt::Write;
+        let mut value = FieldValue::new();
+        let _ = value.write_fmt(args);
+
+        if let Some(field) = self.fields.iter_mut().flatten().find(|f| f.name == name) {
+            field.value = value;
+            return true;
+        }
+        match self.fields.iter_mut().find(|f| f.is_none()) {
+            Some(slot) => {
+                *slot = Some(Field { name, value });
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn remove_field(&mut self, name: &'static str) {
+        for slot in self.fields.iter_mut() {
+            if matches!(slot, Some(field) if field.name == name) {
+                *slot = None;
+            }
+        }
+    }
+
+    // monta a linha "nome: valor | nome: valor"
+    fn render(&self, line: &mut FieldLine) {
+        use core::fmt::Write;
+        let mut first = true;
+        for field in self.fields.iter().flatten() {
+            if !first {
+                let _ = line.write_str(" | ");
+            }
+            first = false;
+            let _ = write!(line, "{}: {}", field.name, field.value.as_str());
+        }
+    }
+}
+
+// buffer da linha inteira, mesmo esquema do FieldValue
+struct FieldLine {
+    bytes: [u8; MAX_WIDTH * 4],
+    len: usize,
+}
+
+impl fmt::Write for FieldLine {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let n = s.len().min(self.bytes.len() - self.len);
+        let n = (0..=n).rev().find(|&i| s.is_char_boundary(i)).unwrap_or(0);
+        self.bytes[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+fn redraw(bar: &StatusBar) {
+    // um espaco antes pra nao colar na borda
+    let mut line = FieldLine {
+        bytes: [b' '; MAX_WIDTH * 4],
+        len: 1,
+    };
+    bar.render(&mut line);
+    let text = core::str::from_utf8(&line.bytes[..line.len]).unwrap_or("");
+    WRITER.lock().set_status_line(text, theme::color(Role::Status));
+}
+
+// reserva a linha e desenha os campos que ja existem
+#[allow(dead_code)]
+pub fn enable(position: StatusPosition) {
+    WRITER.lock().reserve_status_row(Some(position));
+    redraw(&STATUS_BAR.lock());
+}
+
+#[allow(dead_code)]
+pub fn disable() {
+    WRITER.lock().reserve_status_row(None);
+}
+
+// ex: status_bar::set_field("uptime", format_args!("{}s", secs));
+#[allow(dead_code)]
+pub fn set_field(name: &'static str, args: fmt::Arguments) -> bool {
+    let mut bar = STATUS_BAR.lock();
+    let ok = bar.set_field(name, args);
+    redraw(&bar);
+    ok
+}
+
+// redesenha com as cores do tema atual
+pub fn refresh() {
+    redraw(&STATUS_BAR.lock());
+}
+
+#[allow(dead_code)]
+pub fn remove_field(name: &'static str) {
+    let mut bar = STATUS_BAR.lock();
+    bar.remove_field(name);
+    redraw(&bar);
+}
@@ -0,0 +1,297 @@
+// chamadas de sistema pelo syscall/sysret (o jeito rapido do x86_64, sem passar pela IDT)
+// convencao igual a do linux: numero em rax, argumentos em rdi, rsi, rdx, r10, r8, r9, retorno em rax
+// o syscall nao troca de stack sozinho -> o stub em assembly guarda o rsp do usuario e pula pra stack do kernel
+use crate::cpu::msr;
+use crate::gdt;
+use crate::memory::{self, stack};
+use crate::percpu;
+use core::arch::global_asm;
+use core::sync::atomic::{AtomicU64, Ordering};
+use x86_64::structures::paging::{Page, PageTableFlags, Size4KiB};
+use x86_64::VirtAddr;
+
+// bits do RFLAGS zerados na entrada: IF (entra sem interrupcao ate trocar de stack), TF e DF
+const FMASK: u64 = (1 << 9) | (1 << 8) | (1 << 10);
+
+const STACK_PAGES: usize = 4;
+
+// numeros das chamadas
+pub const SYS_WRITE: u64 = 0; // write(ptr, len) -> bytes escritos, na tela
+pub const SYS_UPTIME: u64 = 1; // uptime() -> ms desde o boot
+
+// erros voltam negativos em rax, igual o linux
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    NoSys,   // numero que nao existe
+    Fault,   // ponteiro invalido
+    Invalid, // argumento invalido
+}
+
+impl Error {
+    pub fn code(self) -> i64 {
+        match self {
+            Error::NoSys => -38,
+            Error::Fault => -14,
+            Error::Invalid => -22,
+        }
+    }
+}
+
+// vetor do gate antigo (int 0x80), aceito a partir do ring 3
+pub const INT80_VECTOR: u8 = 0x80;
+
+// numero e argumentos, na ordem em que os stubs empilham (o ultimo push fica no comeco)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Arguments {
+    pub r9: u64,
+    pub r8: u64,
+    pub r10: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub rax: u64,
+}
+
+impl Arguments {
+    fn dispatch(&self) -> u64 {
+        let args = [self.rdi, self.rsi, self.rdx, self.r10, self.r8, self.r9];
+        syscall_handler(self.rax, args) as u64
+    }
+}
+
+// o que o stub do syscall guarda do usuario
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    pub args: Arguments,
+    pub rflags: u64, // o syscall guarda em r11
+    pub rip: u64,    // e o endereco de volta em rcx
+    pub rsp: u64,
+}
+
+// seletores que o sysret usaria, pro retorno pelo iretq (o init preenche)
+static USER_CS: AtomicU64 = AtomicU64::new(0);
+static USER_SS: AtomicU64 = AtomicU64::new(0);
+
+// swapgs: o GS do usuario sai e entra o bloco percpu dessa cpu, onde ficam a stack do kernel e o rsp do usuario
+// 10 pushes = 80 bytes -> a stack continua alinhada em 16 no call
+// volta: rip fora da metade de baixo (syscall nos ultimos bytes dela -> rcx = 0x8000_0000_0000) faz o sysretq dar
+// #GP ainda no ring 0, só que com a stack e o GS do usuario. nesse caso volta pelo iretq, montado aqui na stack
+// do kernel (o #GP, se vier, vem com a stack e o GS do kernel)
+global_asm!(
+    ".global syscall_entry",
+    "syscall_entry:",
+    "swapgs",
+    "mov gs:[{user_rsp}], rsp",
+    "mov rsp, gs:[{kernel_rsp}]",
+    "push qword ptr gs:[{user_rsp}]",
+    "push rcx",
+    "push r11",
+    "push rax",
+    "push rdi",
+    "push rsi",
+    "push rdx",
+    "push r10",
+    "push r8",
+    "push r9",
+    "mov rdi, rsp",
+    "call {dispatch}",
+    "mov rcx, [rsp + 8 * 8]", // rip de volta (o rcx sai da stack de qualquer jeito)
+    "shr rcx, 47",
+    "jnz 4f",
+    "pop r9",
+    "pop r8",
+    "pop r10",
+    "pop rdx",
+    "pop rsi",
+    "pop rdi",
+    "add rsp, 8", // rax: fica o retorno do dispatch
+    "pop r11",
+    "pop rcx",
+    "pop rsp",
+    "swapgs",
+    "sysretq",
+    // frame do iretq (ss, rsp, rflags, cs, rip) embaixo do Frame; cada push anda 8 o offset do Frame
+    "4:",
+    "push qword ptr [rip + {user_ss}]",
+    "push qword ptr [rsp + 8 + 9 * 8]", // rsp do usuario
+    "push qword ptr [rsp + 16 + 7 * 8]", // rflags
+    "push qword ptr [rip + {user_cs}]",
+    "push qword ptr [rsp + 32 + 8 * 8]", // rip
+    "mov r9, [rsp + 40]",
+    "mov r8, [rsp + 40 + 8]",
+    "mov r10, [rsp + 40 + 16]",
+    "mov rdx, [rsp + 40 + 24]",
+    "mov rsi, [rsp + 40 + 32]",
+    "mov rdi, [rsp + 40 + 40]",
+    "mov r11, [rsp + 40 + 56]",
+    "mov rcx, [rsp + 40 + 64]",
+    "swapgs",
+    "iretq",
+    dispatch = sym dispatch,
+    user_cs = sym USER_CS,
+    user_ss = sym USER_SS,
+    user_rsp = const percpu::SYSCALL_USER_RSP,
+    kernel_rsp = const percpu::SYSCALL_KERNEL_RSP,
+);
+
+// int 0x80: a cpu já trocou de stack (RSP0 da TSS, se veio do ring 3) e empilhou o frame do iret
+// guarda rcx e r11 tambem, pq aqui o usuario espera que só o rax mude
+// swapgs só se veio do ring 3 (RPL do CS salvo no frame), senao o GS já é o do kernel
+// frame da cpu (40 bytes, com o rsp alinhado em 16 antes) + 9 pushes -> alinhado no call
+global_asm!(
+    ".global syscall_int80_entry",
+    "syscall_int80_entry:",
+    "test byte ptr [rsp + 8], 3",
+    "jz 2f",
+    "swapgs",
+    "2:",
+    "push r11",
+    "push rcx",
+    "push rax",
+    "push rdi",
+    "push rsi",
+    "push rdx",
+    "push r10",
+    "push r8",
+    "push r9",
+    "mov rdi, rsp",
+    "call {dispatch}",
+    "pop r9",
+    "pop r8",
+    "pop r10",
+    "pop rdx",
+    "pop rsi",
+    "pop rdi",
+    "add rsp, 8",
+    "pop rcx",
+    "pop r11",
+    "test byte ptr [rsp + 8], 3",
+    "jz 3f",
+    "swapgs",
+    "3:",
+    "iretq",
+    dispatch = sym int80_dispatch,
+);
+
+extern "C" {
+    fn syscall_entry();
+    fn syscall_int80_entry();
+}
+
+extern "C" fn dispatch(frame: &mut Frame) -> u64 {
+    percpu::current().stats.syscalls.fetch_add(1, Ordering::Relaxed);
+    frame.args.dispatch()
+}
+
+extern "C" fn int80_dispatch(args: &mut Arguments) -> u64 {
+    crate::interrupts::count(INT80_VECTOR);
+    args.dispatch()
+}
+
+// pra IDT (o gate com DPL 3 é montado no interrupts)
+pub fn int80_entry() -> VirtAddr {
+    VirtAddr::from_ptr(syscall_int80_entry as *const ())
+}
+
+// o despacho em si, dos dois caminhos (syscall e int 0x80)
+pub fn syscall_handler(number: u64, args: [u64; 6]) -> i64 {
+    let result = match number {
+        SYS_WRITE => write(args[0], args[1]),
+        SYS_UPTIME => Ok(crate::time::uptime_ms()),
+        _ => Err(Error::NoSys),
+    };
+    match result {
+        Ok(value) => value as i64,
+        Err(error) => error.code(),
+    }
+}
+
+// fim da metade de baixo (nao canonico dali ate a metade do kernel)
+const USER_END: u64 = 0x0000_8000_0000_0000;
+
+// o buffer inteiro tem que ser memoria do ring 3: sem overflow, na metade de baixo e cada pagina mapeada com
+// USER_ACCESSIBLE (o kernel tambem mora na metade de baixo, mas sem esse bit). pagina sob demanda que ainda nao
+// foi tocada conta como nao mapeada
+fn check_user(ptr: u64, len: u64) -> Result<(), Error> {
+    let end = ptr.checked_add(len).ok_or(Error::Fault)?;
+    if ptr == 0 || end > USER_END {
+        return Err(Error::Fault);
+    }
+    if len == 0 {
+        return Ok(());
+    }
+    let first = Page::<Size4KiB>::containing_address(VirtAddr::new(ptr));
+    let last = Page::<Size4KiB>::containing_address(VirtAddr::new(end - 1));
+    let user = memory::with_mapper(|mapper| {
+        Page::range_inclusive(first, last).all(|page| {
+            mapper
+                .translate(page.start_address())
+                .is_some_and(|(_, flags)| flags.contains(PageTableFlags::USER_ACCESSIBLE))
+        })
+    });
+    if user {
+        Ok(())
+    } else {
+        Err(Error::Fault)
+    }
+}
+
+fn write(ptr: u64, len: u64) -> Result<u64, Error> {
+    check_user(ptr, len)?;
+    let bytes = unsafe { core::slice::from_raw_parts(ptr as *const u8, len as usize) };
+    let text = core::str::from_utf8(bytes).map_err(|_| Error::Invalid)?;
+    crate::print!("{}", text);
+    Ok(len)
+}
+
+// faz a chamada pelo int 0x80 (do ring 0 tambem funciona: a cpu nem troca de stack) -> serve pra testar
+pub fn int80(number: u64, args: [u64; 3]) -> i64 {
+    let result: i64;
+    unsafe {
+        core::arch::asm!(
+            "int 0x80",
+            inlateout("rax") number as i64 => result,
+            in("rdi") args[0],
+            in("rsi") args[1],
+            in("rdx") args[2],
+        );
+    }
+    result
+}
+
+// stack usada pela proxima entrada nessa cpu (a troca de tarefa vai chamar isso)
+pub fn set_kernel_stack(top: VirtAddr) {
+    percpu::current().set_syscall_stack(top);
+}
+
+// liga o syscall na cpu atual: EFER.SCE, seletores no STAR, entrada no LSTAR, mascara no FMASK
+// precisa do percpu::init antes; false se a cpu nao tem syscall
+pub fn init() -> bool {
+    if !msr::has_syscall() {
+        return false;
+    }
+    let cpu = percpu::cpu_id();
+    // stack do kernel de cada cpu enquanto nao tem uma por tarefa
+    match stack::allocate(STACK_PAGES, "syscall", cpu) {
+        Ok(stack) => set_kernel_stack(stack.leak()),
+        Err(error) => {
+            log::error!("sem stack pro syscall na cpu {}: {}", cpu, error);
+            return false;
+        }
+    }
+
+    // sysret: SS = base + 8, CS = base + 16 (os dois com RPL 3) -> base é o seletor antes dos dados do usuario
+    let user_base = (gdt::user_data_selector().0 & !3) - 8;
+    debug_assert_eq!(user_base + 16, gdt::user_code_selector().0 & !3);
+    USER_SS.store(u64::from(user_base + 8) | 3, Ordering::Relaxed);
+    USER_CS.store(u64::from(user_base + 16) | 3, Ordering::Relaxed);
+    unsafe {
+        msr::enable_efer(msr::EFER_SCE);
+        msr::set_star(gdt::kernel_code_selector().0, user_base);
+        msr::set_lstar(VirtAddr::from_ptr(syscall_entry as *const ()));
+        msr::set_fmask(FMASK);
+    }
+    true
+}
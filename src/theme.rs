@@ -0,0 +1,114 @@
+// temas de cores: cada subsistema pede a cor pelo papel (normal, aviso, erro...) e nao pela cor fixa
+// ex: println_role!(Role::Warning, "memoria baixa: {} KiB", free);
+use crate::vga_buffer::{Color, ColorCode, WRITER};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Normal,
+    Warning,
+    Error,
+    Prompt,
+    Status,
+}
+
+pub struct Theme {
+    pub name: &'static str,
+    pub normal: ColorCode,
+    pub warning: ColorCode,
+    pub error: ColorCode,
+    pub prompt: ColorCode,
+    pub status: ColorCode,
+}
+
+impl Theme {
+    pub fn color(&self, role: Role) -> ColorCode {
+        match role {
+            Role::Normal => self.normal,
+            Role::Warning => self.warning,
+            Role::Error => self.error,
+            Role::Prompt => self.prompt,
+            Role::Status => self.status,
+        }
+    }
+}
+
+pub const THEMES: [Theme; 4] = [
+    // as cores que o kernel sempre usou
+    Theme {
+        name: "default",
+        normal: ColorCode::new(Color::Yellow, Color::Black),
+        warning: ColorCode::new(Color::Brown, Color::Black),
+        error: ColorCode::new(Color::LightRed, Color::Black),
+        prompt: ColorCode::new(Color::LightGreen, Color::Black),
+        status: ColorCode::new(Color::Black, Color::LightGray),
+    },
+    Theme {
+        name: "dark",
+        normal: ColorCode::new(Color::LightGray, Color::Black),
+        warning: ColorCode::new(Color::Yellow, Color::Black),
+        error: ColorCode::new(Color::Red, Color::Black),
+        prompt: ColorCode::new(Color::LightCyan, Color::Black),
+        status: ColorCode::new(Color::LightGray, Color::DarkGray),
+    },
+    // o mais perto do solarized que da com 16 cores
+    Theme {
+        name: "solarized",
+        normal: ColorCode::new(Color::LightCyan, Color::Blue),
+        warning: ColorCode::new(Color::Yellow, Color::Blue),
+        error: ColorCode::new(Color::LightRed, Color::Blue),
+        prompt: ColorCode::new(Color::LightGreen, Color::Blue),
+        status: ColorCode::new(Color::Blue, Color::Cyan),
+    },
+    Theme {
+        name: "high-contrast",
+        normal: ColorCode::new(Color::White, Color::Black),
+        warning: ColorCode::new(Color::Black, Color::Yellow),
+        error: ColorCode::new(Color::White, Color::Red),
+        prompt: ColorCode::new(Color::White, Color::Blue),
+        status: ColorCode::new(Color::Black, Color::White),
+    },
+];
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn current() -> &'static Theme {
+    &THEMES[CURRENT.load(Ordering::Relaxed)]
+}
+
+pub fn color(role: Role) -> ColorCode {
+    current().color(role)
+}
+
+// troca o tema pelo nome; retorna false se nao existe
+// o texto que ja esta na tela continua com as cores antigas, só o que for escrito depois muda
+pub fn set_theme(name: &str) -> bool {
+    let index = match THEMES.iter().position(|theme| theme.name == name) {
+        Some(index) => index,
+        None => return false,
+    };
+    CURRENT.store(index, Ordering::Relaxed);
+
+    let theme = current();
+    {
+        let mut writer = WRITER.lock();
+        writer.set_default_color_code(theme.normal);
+        writer.set_color_code(theme.normal);
+    }
+    crate::console::set_error_color(theme.error.foreground());
+    crate::status_bar::refresh();
+    true
+}
+
+#[macro_export]
+macro_rules! print_role {
+    ($role:expr, $($arg:tt)*) => ({
+        let color = $crate::theme::color($role);
+        $crate::console::_print_colored(color.foreground(), Some(color.background()), format_args!($($arg)*))
+    });
+}
+
+#[macro_export]
+macro_rules! println_role {
+    ($role:expr, $($arg:tt)*) => ($crate::print_role!($role, "{}\n", format_args!($($arg)*)));
+}
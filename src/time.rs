@@ -0,0 +1,177 @@
+// contador de ticks desde o boot, incrementado pela irq 0 (PIT)
+// ex: let start = time::uptime_ms(); ... time::uptime_ms() - start
+// pra medir coisa curta: let start = Instant::now(); ... start.elapsed() (pelo TSC, resolucao de ns)
+use crate::vga_buffer::WRITER;
+use core::ops::{Add, Sub};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::time::Duration;
+use x86_64::instructions::port::Port;
+
+// frequencia da irq do timer
+pub const HZ: u64 = 100;
+
+const PIT_FREQUENCY: u64 = 1_193_182;
+const PIT_CHANNEL0: u16 = 0x40;
+const PIT_CHANNEL2: u16 = 0x42;
+const PIT_COMMAND: u16 = 0x43;
+const PIT_RATE_GENERATOR: u8 = 0x34; // canal 0, byte baixo e alto, modo 2
+const PIT_ONE_SHOT_CHANNEL2: u8 = 0xB0; // canal 2, byte baixo e alto, modo 0
+const SPEAKER_PORT: u16 = 0x61; // bit 0: gate do canal 2, bit 1: alto-falante, bit 5: saida do canal 2
+
+const TSC_CALIBRATION_MS: u64 = 50;
+
+static TICKS: AtomicU64 = AtomicU64::new(0);
+static HEARTBEAT: AtomicBool = AtomicBool::new(false);
+
+// programa o PIT pra gerar HZ interrupcoes por segundo
+pub fn init() {
+    let divisor = (PIT_FREQUENCY / HZ) as u16;
+    unsafe {
+        Port::<u8>::new(PIT_COMMAND).write(PIT_RATE_GENERATOR);
+        let mut data = Port::<u8>::new(PIT_CHANNEL0);
+        data.write(divisor as u8);
+        data.write((divisor >> 8) as u8);
+    }
+}
+
+// espera `ms` (ate ~54) pelo canal 2 do PIT, sem interrupcao -> serve pra calibrar outros timers
+pub fn pit_wait_ms(ms: u64) {
+    let count = (PIT_FREQUENCY * ms / 1000).min(0xffff) as u16;
+    let mut speaker = Port::<u8>::new(SPEAKER_PORT);
+    unsafe {
+        // gate desligado e alto-falante mudo enquanto programa
+        let value = speaker.read() & !0b11;
+        speaker.write(value);
+        Port::<u8>::new(PIT_COMMAND).write(PIT_ONE_SHOT_CHANNEL2);
+        let mut data = Port::<u8>::new(PIT_CHANNEL2);
+        data.write(count as u8);
+        data.write((count >> 8) as u8);
+        // subir o gate comeca a contagem; a saida vai pra 1 quando chega em 0
+        speaker.write(value | 1);
+        while speaker.read() & (1 << 5) == 0 {
+            crate::cpu::spin_hint();
+        }
+        speaker.write(value);
+    }
+}
+
+// chamado pelo handler da irq 0 (ou do timer do apic)
+pub fn tick() {
+    let ticks = TICKS.fetch_add(1, Ordering::Relaxed) + 1;
+    if HEARTBEAT.load(Ordering::Relaxed) && ticks.is_multiple_of(HZ / 4) {
+        heartbeat(ticks / (HZ / 4));
+    }
+}
+
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+pub fn uptime_ms() -> u64 {
+    ticks() * 1000 / HZ
+}
+
+// ligado -> um |/-\ gira no canto superior direito (da pra ver se a irq do timer esta chegando)
+#[allow(dead_code)]
+pub fn set_heartbeat(enabled: bool) {
+    HEARTBEAT.store(enabled, Ordering::Relaxed);
+}
+
+// roda dentro da interrupcao: se o WRITER estiver pego pelo codigo interrompido, pula essa volta
+fn heartbeat(step: u64) {
+    const SPINNER: &[u8; 4] = b"|/-\\";
+    if let Some(mut writer) = WRITER.try_lock() {
+        let col = writer.width() - 1;
+        let color_code = writer.color_code();
+        writer.put_char_at(0, col, SPINNER[(step % 4) as usize], color_code);
+        writer.flush_if_auto();
+    }
+}
+
+// TSC: contador de ciclos da cpu (rdtsc), bem mais fino que o tick mas a frequencia muda de maquina pra maquina
+// 0 -> ainda nao calibrado
+static TSC_HZ: AtomicU64 = AtomicU64::new(0);
+static TSC_BASE: AtomicU64 = AtomicU64::new(0);
+
+pub fn rdtsc() -> u64 {
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+// mede quantos ciclos o TSC anda em 50ms do PIT; retorna a frequencia em Hz (0 se a cpu nao tem TSC)
+// tem que rodar com interrupcoes desligadas, igual o apic_timer::calibrate
+pub fn calibrate_tsc() -> u64 {
+    if !crate::cpu::features().tsc {
+        return 0;
+    }
+    let start = rdtsc();
+    pit_wait_ms(TSC_CALIBRATION_MS);
+    let hz = (rdtsc() - start) * 1000 / TSC_CALIBRATION_MS;
+    TSC_BASE.store(start, Ordering::Relaxed);
+    TSC_HZ.store(hz, Ordering::Relaxed);
+    hz
+}
+
+pub fn tsc_hz() -> u64 {
+    TSC_HZ.load(Ordering::Relaxed)
+}
+
+// sem invariant TSC a frequencia muda com o estado de energia da cpu e o nanos() deixa de ser confiavel
+// (em vm quase nunca aparece, mesmo o host tendo)
+pub fn tsc_is_invariant() -> bool {
+    crate::cpu::features().invariant_tsc
+}
+
+// nanossegundos desde a calibracao; sem TSC cai pro hpet e depois pros ticks
+pub fn nanos() -> u64 {
+    let hz = tsc_hz();
+    if hz != 0 {
+        let cycles = rdtsc().wrapping_sub(TSC_BASE.load(Ordering::Relaxed));
+        return (cycles as u128 * 1_000_000_000 / hz as u128) as u64;
+    }
+    if crate::hpet::is_present() {
+        return crate::hpet::now_ns();
+    }
+    uptime_ms() * 1_000_000
+}
+
+// um ponto no tempo (monotonico, em ns pelo nanos())
+// ex: let deadline = Instant::now() + Duration::from_millis(100); while !deadline.has_passed() { ... }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instant(u64);
+
+impl Instant {
+    pub fn now() -> Instant {
+        Instant(nanos())
+    }
+
+    #[allow(dead_code)]
+    pub fn elapsed(&self) -> Duration {
+        Instant::now() - *self
+    }
+
+    pub fn has_passed(&self) -> bool {
+        Instant::now() >= *self
+    }
+
+    #[allow(dead_code)]
+    pub fn as_nanos(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        Instant(self.0.saturating_add(duration.as_nanos() as u64))
+    }
+}
+
+// satura em 0 se `earlier` for depois
+impl Sub for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Instant) -> Duration {
+        Duration::from_nanos(self.0.saturating_sub(earlier.0))
+    }
+}
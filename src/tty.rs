@@ -0,0 +1,45 @@
+// gerenciador dos terminais virtuais (tipo Alt+F1..F4 no linux)
+// o estado de cada terminal fica no vga_buffer, aqui é só a interface pro resto do kernel
+use crate::vga_buffer::{TERMINAL_COUNT, WRITER};
+use core::fmt;
+
+pub const TTY_COUNT: usize = TERMINAL_COUNT;
+
+// terminal que esta aparecendo na tela
+#[allow(dead_code)]
+pub fn active() -> usize {
+    WRITER.lock().active_terminal()
+}
+
+// retorna false se o terminal nao existe
+pub fn switch_to(tty: usize) -> bool {
+    if tty >= TTY_COUNT {
+        return false;
+    }
+    WRITER.lock().switch_terminal(tty);
+    true
+}
+
+// atalho de teclado: F1 -> tty 0, F2 -> tty 1, ...
+// o driver de teclado chama isso quando ve Alt + F<n>
+pub fn handle_function_key(n: u8) -> bool {
+    match n {
+        1..=4 => switch_to((n - 1) as usize),
+        _ => false,
+    }
+}
+
+// escreve num terminal mesmo que ele esteja em segundo plano
+#[allow(dead_code)]
+pub fn write_to(tty: usize, s: &str) {
+    WRITER.lock().with_terminal(tty, |writer| writer.write_string(s));
+}
+
+#[allow(dead_code)]
+pub fn print_to(tty: usize, args: fmt::Arguments) {
+    use core::fmt::Write;
+    WRITER
+        .lock()
+        .with_terminal(tty, |writer| writer.write_fmt(args))
+        .unwrap();
+}
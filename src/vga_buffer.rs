@@ -24,17 +24,59 @@ pub enum Color {
     White = 15,
 }
 
+impl Color {
+    // só os 4 bits de baixo importam
+    pub fn from_u8(value: u8) -> Color {
+        match value & 0x0f {
+            0 => Color::Black,
+            1 => Color::Blue,
+            2 => Color::Green,
+            3 => Color::Cyan,
+            4 => Color::Red,
+            5 => Color::Magenta,
+            6 => Color::Brown,
+            7 => Color::LightGray,
+            8 => Color::DarkGray,
+            9 => Color::LightBlue,
+            10 => Color::LightGreen,
+            11 => Color::LightCyan,
+            12 => Color::LightRed,
+            13 => Color::Pink,
+            14 => Color::Yellow,
+            _ => Color::White,
+        }
+    }
+}
+
 // contem o byte completo da cor (foreground e background)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(transparent)]
-struct ColorCode(u8);
+pub struct ColorCode(u8);
 
 impl ColorCode {
-    fn new(foreground: Color, background: Color) -> ColorCode {
+    pub const fn new(foreground: Color, background: Color) -> ColorCode {
         ColorCode((background as u8) << 4 | (foreground as u8))
     }
+
+    pub fn foreground(self) -> Color {
+        Color::from_u8(self.0)
+    }
+
+    pub fn background(self) -> Color {
+        Color::from_u8(self.0 >> 4)
+    }
+
+    // com blink ligado (padrao) o bit 7 faz o texto piscar e o background só tem 8 cores
+    // ex: ColorCode::new(Color::White, Color::Red).blinking()
+    #[allow(dead_code)]
+    pub fn blinking(self) -> ColorCode {
+        ColorCode(self.0 | 0x80)
+    }
 }
 
+// cor padrao do writer no boot (o tema pode trocar a cor do reset \x1b[0m com set_default_color_code)
+const DEFAULT_COLOR_CODE: ColorCode = ColorCode::new(Color::Yellow, Color::Black);
+
 // garante que os fields da struct serao exatamente como uma struct em C -> garante ordem correta
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(C)]
@@ -43,58 +85,178 @@ struct ScreenChar {
     color_code: ColorCode,
 }
 
-const BUFFER_HEIGHT: usize = 25;
-const BUFFER_WIDTH: usize = 80;
+const BACKSPACE: u8 = 0x08;
+
+const DEFAULT_TAB_WIDTH: usize = 8;
+
+// tamanho do modo texto padrao (o que o bootloader deixa)
+const DEFAULT_WIDTH: usize = 80;
+const DEFAULT_HEIGHT: usize = 25;
+
+// maior modo suportado -> os arrays (shadow, historico, terminais) sao desse tamanho
+// e o writer usa só o pedaco width x height do modo atual
+pub const MAX_WIDTH: usize = 90;
+pub const MAX_HEIGHT: usize = 60;
 
 // repr(transparent) garente que vai ter o mesmo layout de memoria
 use volatile::Volatile;
 
+// na memoria do VGA as linhas ficam uma atras da outra, cada uma com `width` celulas
+// entao a posicao de (linha, coluna) depende do modo -> indice linear em vez de matriz
+// a janela do modo texto (0xb8000-0xbffff) tem 32KB, bem mais que uma tela:
+// o scroll anda o inicio da tela (start address do CRTC) por essa memoria em vez de copiar tudo
+const VGA_CELLS: usize = 32 * 1024 / 2;
+
 struct Buffer {
-    chars: [[Volatile<ScreenChar>; BUFFER_WIDTH]; BUFFER_HEIGHT],
+    chars: [Volatile<ScreenChar>; VGA_CELLS],
 }
 
 // para escrever na tela
 pub struct Writer {
-    column_position: usize, // mantem qual foi a última posição na última linha
+    column_position: usize, // mantem qual foi a última posição na linha atual
+    row_position: usize, // linha onde o writer esta escrevendo (comeca na ultima)
     color_code: ColorCode, // cores do foreground e background, referencia do VGA buffer armazenada no buffer
     buffer: &'static mut Buffer, // necessario deixar explicito o tempo de vida da referencia
     // static lifetime -> referencia é valida por toda a execucao do programa
+    // copia da tela na RAM -> o writer mexe só aqui e o flush copia as linhas alteradas pro VGA
+    shadow: [[ScreenChar; MAX_WIDTH]; MAX_HEIGHT],
+    dirty: [bool; MAX_HEIGHT], // linhas do shadow que ainda nao foram copiadas pro VGA
+    auto_flush: bool, // flush depois de cada write_byte/write_string
+    scroll_offset: usize, // quantas linhas pra cima no historico a tela esta mostrando (0 = saida atual)
+    ansi: AnsiParser, // estado das sequencias de escape no meio do write_string
+    tab_width: usize, // distancia entre as paradas de tab
+    wrap: WordWrap, // quebra de linha por palavra
+    scroll_margins: Option<(usize, usize)>, // linhas (inclusive) que fazem scroll, None = area de texto toda
+    state_stack: [SavedState; STATE_STACK_DEPTH], // push_state/pop_state
+    state_depth: usize,
+    default_color_code: ColorCode, // cor do reset (ansi \x1b[0m e reset())
+    mirror: Option<ConsoleRef>, // recebe uma copia de tudo que é escrito (ex: serial)
+    origin: usize, // linha da memoria do VGA onde a tela comeca (start address / width)
+    pending_scroll: usize, // scrolls da tela inteira que o VGA ainda nao viu (o flush anda o origin)
+    terminal: usize, // terminal virtual cujo estado esta carregado no writer
+    displayed_terminal: usize, // terminal que aparece na tela
+    status_position: Option<StatusPosition>, // linha reservada pra barra de status
+    mode: Mode, // modo texto atual
+    width: usize, // colunas e linhas do modo (copiadas pra nao fazer match toda hora)
+    height: usize,
 }
 
 impl Writer {
      // ao printar o byte -> writer olha se a linha atual esta cheia
      // se sim -> chama método new_line
      // entao, escreve um novo ScreenChar -> coluna da current position avança
+     #[allow(dead_code)]
      pub fn write_byte(&mut self, byte: u8) {
+        if let Some(mirror) = self.mirror {
+            // o byte é cp437 (o que vai pra tela) -> a copia vai com o char unicode do mesmo glifo
+            let c = if byte.is_ascii() { byte as char } else { cp437::to_char(byte).unwrap_or('\u{fffd}') };
+            mirror.lock().write_str(c.encode_utf8(&mut [0; 4]));
+        }
+        self.put_byte(byte);
+        self.auto_flush();
+    }
+
+    // igual o write_byte mas sem flush (o write_string faz um flush só no final)
+    fn put_byte(&mut self, byte: u8) {
         match byte {
-            b'\n' => self.new_line(),
-            byte => {
-                if self.column_position >= BUFFER_WIDTH {
-                    self.new_line();
+            b'\n' => {
+                self.wrap.continuation = false;
+                self.new_line();
+            }
+            BACKSPACE => self.backspace(),
+            b'\t' => self.tab(),
+            // \r só volta pro comeco da linha, sem scroll -> da pra reescrever a linha (progresso, spinner)
+            b'\r' => self.column_position = 0,
+            byte => self.put_glyph(byte),
+        }
+    }
+
+    // escreve o glifo `byte` da fonte sem interpretar como controle
+    // (no cp437 os codigos de controle tambem tem desenho, ex: 0x0A é ◙)
+    fn put_glyph(&mut self, byte: u8) {
+        if self.column_position >= self.width {
+            if self.wrap.enabled {
+                // espaco bem na quebra nao aparece no comeco da linha de baixo
+                if byte == b' ' {
+                    self.wrap_line();
+                    return;
                 }
+                self.wrap_word();
+            } else {
+                self.new_line();
+            }
+        }
 
-                let row = BUFFER_HEIGHT - 1;
-                let col = self.column_position;
+        let row = self.row_position;
+        let col = self.column_position;
 
-                let color_code = self.color_code;
-                self.buffer.chars[row][col].write(ScreenChar {
-                    ascii_character: byte,
-                    color_code,
-                });
-                self.column_position += 1;
-            }
+        let color_code = self.color_code;
+        self.write_cell(row, col, ScreenChar {
+            ascii_character: byte,
+            color_code,
+        });
+        self.column_position += 1;
+    }
+
+    // volta uma coluna e apaga a celula
+    // no comeco da linha volta pro fim da linha de cima (a linha quebrou ali)
+    fn backspace(&mut self) {
+        if self.column_position > 0 {
+            self.column_position -= 1;
+        } else if self.row_position > self.first_text_row() {
+            self.row_position -= 1;
+            self.column_position = self.width - 1;
+        } else {
+            return;
+        }
+        let blank = ScreenChar {
+            ascii_character: b' ',
+            color_code: self.color_code,
+        };
+        self.write_cell(self.row_position, self.column_position, blank);
+    }
+
+    // anda ate a proxima parada de tab preenchendo com espaco
+    // se a parada passa do fim da linha, vai só ate o fim e o proximo caractere quebra a linha
+    fn tab(&mut self) {
+        if self.column_position >= self.width {
+            self.new_line();
         }
+        let width = self.tab_width.max(1);
+        let next_stop = (self.column_position / width + 1) * width;
+        let end = next_stop.min(self.width);
+        self.clear_line_range(self.row_position, self.column_position, end);
+        self.column_position = end;
     }
 
     fn new_line(&mut self) {
-        for row in 1..BUFFER_HEIGHT {
-            for col in 0..BUFFER_WIDTH {
-                let character = self.buffer.chars[row][col].read();
-                self.buffer.chars[row - 1][col].write(character);
+        let (top, bottom) = self.scroll_region();
+        self.column_position = 0;
+        // só tem scroll quando o cursor esta na ultima linha da regiao
+        // fora disso (cursor movido pra cima, ou abaixo da regiao) só desce uma linha
+        if self.row_position != bottom {
+            if self.row_position < self.last_text_row() {
+                self.row_position += 1;
             }
+            return;
         }
-        self.clear_row(BUFFER_HEIGHT - 1);
-        self.column_position = 0;
+        // antes de perder a primeira linha, guarda ela no historico
+        // (regiao que nao comeca no topo é tipo um painel, o que sai dela nao vai pro historico)
+        if top == self.first_text_row() {
+            self.push_to_scrollback();
+        }
+        // a linha de status (se tiver) e o que estiver fora da regiao ficam parados
+        self.shadow.copy_within(top + 1..=bottom, top);
+        if self.can_pan(top, bottom) {
+            // o VGA vai andar uma linha tambem -> as linhas ja copiadas continuam certas, só a sujeira sobe junto
+            self.dirty.copy_within(top + 1..=bottom, top);
+            self.pending_scroll += 1;
+        } else {
+            for row in top..=bottom {
+                self.dirty[row] = true;
+            }
+        }
+        self.clear_row(bottom);
     }
 
     fn clear_row(&mut self, row: usize) {
@@ -102,49 +264,1520 @@ impl Writer {
             ascii_character: b' ',
             color_code: self.color_code,
         };
-        for col in 0..BUFFER_WIDTH {
-            self.buffer.chars[row][col].write(blank);
+        for col in 0..self.width {
+            self.write_cell(row, col, blank);
         }
     }
+
+    // toda escrita vai pro shadow, o VGA só é atualizado no flush
+    fn write_cell(&mut self, row: usize, col: usize, character: ScreenChar) {
+        self.shadow[row][col] = character;
+        self.dirty[row] = true;
+    }
+
+    // escrita direto na memoria do VGA (flush, historico)
+    fn write_screen(&mut self, row: usize, col: usize, character: ScreenChar) {
+        self.buffer.chars[(self.origin + row) * self.width + col].write(character);
+    }
+
+    fn read_screen(&self, row: usize, col: usize) -> ScreenChar {
+        self.buffer.chars[(self.origin + row) * self.width + col].read()
+    }
 }
 
-// vga text buffer só suporta ascii
-// strings rust são utf-8, entao podem conter bytes que não são suportados pelo VGA text buffer
-// usando o match byte diferenciamos ascii printáveis de não printáveis
-// caso for não printável, é colocado um ■ (0xfe)
+// double buffering: escrever direto no 0xb8000 a cada caractere faz a tela piscar no scroll rapido
+// com o shadow, varias escritas viram uma copia só das linhas alteradas
+#[allow(dead_code)]
 impl Writer {
-    // converte cada parte da string em byte e escreve um a um 
-    pub fn write_string(&mut self, s: &str) {
-        for byte in s.bytes() {
-            match byte {
-                // ASCII byte printável ou nova linha
-                0x20..=0x7e | b'\n' => self.write_byte(byte),
-                // não é parte do escopo printável do ASCII
-                _ => self.write_byte(0xfe),
+    // copia as linhas sujas do shadow pro VGA e atualiza o cursor
+    // enquanto o historico esta sendo exibido nao faz nada (as linhas continuam sujas pro proximo flush)
+    pub fn flush(&mut self) {
+        // terminal em segundo plano tambem nao vai pra tela
+        if self.scroll_offset > 0 || self.terminal != self.displayed_terminal {
+            return;
+        }
+        self.apply_pending_scroll();
+        for row in 0..self.height {
+            if !self.dirty[row] {
+                continue;
             }
+            for col in 0..self.width {
+                self.write_screen(row, col, self.shadow[row][col]);
+            }
+            self.dirty[row] = false;
+        }
+        self.update_cursor();
+    }
+
+    // com auto flush desligado, quem chama é responsavel por chamar flush() (ex: redesenho de tela cheia)
+    pub fn set_auto_flush(&mut self, enabled: bool) {
+        self.auto_flush = enabled;
+        if enabled {
+            self.flush();
+        }
+    }
+
+    // copia a tela inteira de novo (ex: depois de voltar do modo grafico)
+    // a troca de modo zera o start address, entao a tela volta pro comeco da memoria
+    pub fn refresh(&mut self) {
+        self.reset_origin();
+        self.dirty = [true; MAX_HEIGHT];
+        self.flush();
+    }
+
+    pub fn auto_flush_enabled(&self) -> bool {
+        self.auto_flush
+    }
+
+    fn auto_flush(&mut self) {
+        if self.auto_flush {
+            self.flush();
         }
     }
 }
 
-// função temporaria de teste
-pub fn print_something() {
-    use core::fmt::Write;
-    let mut writer = Writer {
-        column_position: 0,
-        color_code: ColorCode::new(Color::Yellow, Color::Black),
-        buffer: unsafe { &mut *(0xb8000 as *mut Buffer) },
-    };
+// scroll por start address: em vez de copiar as 25 linhas a cada \n, o inicio da tela anda uma linha
+// na memoria do VGA e só a linha nova é escrita
+// quando chega no fim da memoria volta pro comeco copiando a tela inteira (o jeito antigo)
+// só da pra usar quando a tela inteira rola junto: com linha de status ou margens a parte fixa andaria junto
+const CRTC_START_ADDRESS_HIGH: u8 = 0x0C;
+const CRTC_START_ADDRESS_LOW: u8 = 0x0D;
 
-    writer.write_byte(b'H');
-    writer.write_string("ello! ");
-    write!(writer, "The numbers are {} and {}", 42, 1.0/3.0).unwrap();
+impl Writer {
+    fn can_pan(&self, top: usize, bottom: usize) -> bool {
+        self.terminal == self.displayed_terminal
+            && self.status_position.is_none()
+            && top == 0
+            && bottom == self.height - 1
+    }
+
+    fn virtual_rows(&self) -> usize {
+        VGA_CELLS / self.width
+    }
+
+    fn apply_pending_scroll(&mut self) {
+        if self.pending_scroll == 0 {
+            return;
+        }
+        let scroll = self.pending_scroll;
+        self.pending_scroll = 0;
+        if scroll < self.height && self.origin + scroll + self.height <= self.virtual_rows() {
+            self.origin += scroll;
+        } else {
+            // passou do fim da memoria (ou rolou mais que uma tela) -> redesenha tudo no comeco
+            self.origin = 0;
+            self.dirty = [true; MAX_HEIGHT];
+        }
+        self.write_start_address();
+    }
+
+    fn reset_origin(&mut self) {
+        self.origin = 0;
+        self.pending_scroll = 0;
+        self.write_start_address();
+    }
+
+    fn write_start_address(&self) {
+        let start = (self.origin * self.width) as u16;
+        crtc_write(CRTC_START_ADDRESS_HIGH, (start >> 8) as u8);
+        crtc_write(CRTC_START_ADDRESS_LOW, (start & 0xff) as u8);
+    }
 }
 
-use core::fmt;
+// historico das linhas que sairam pelo topo da tela
+// fica num static separado pq é grande demais pra ser criado na stack dentro do lazy_static do WRITER
+// cada terminal virtual tem o seu
+const SCROLLBACK_LINES: usize = 500;
 
-impl fmt::Write for Writer {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        self.write_string(s);
-        Ok(())
+const BLANK: ScreenChar = ScreenChar {
+    ascii_character: b' ',
+    color_code: ColorCode(0x07),
+};
+
+// celula zerada -> o historico comeca vazio e vai pro .bss em vez de ocupar espaco no binario
+const EMPTY: ScreenChar = ScreenChar {
+    ascii_character: 0,
+    color_code: ColorCode(0),
+};
+
+struct Scrollback {
+    lines: [[ScreenChar; MAX_WIDTH]; SCROLLBACK_LINES], // buffer circular
+    head: usize, // proxima posicao a ser escrita
+    len: usize,
+}
+
+impl Scrollback {
+    const fn new() -> Scrollback {
+        Scrollback {
+            lines: [[EMPTY; MAX_WIDTH]; SCROLLBACK_LINES],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, line: [ScreenChar; MAX_WIDTH]) {
+        self.lines[self.head] = line;
+        self.head = (self.head + 1) % SCROLLBACK_LINES;
+        if self.len < SCROLLBACK_LINES {
+            self.len += 1;
+        }
+    }
+
+    // index 0 = linha mais antiga ainda guardada
+    #[allow(dead_code)]
+    fn line(&self, index: usize) -> &[ScreenChar; MAX_WIDTH] {
+        let oldest = (self.head + SCROLLBACK_LINES - self.len) % SCROLLBACK_LINES;
+        &self.lines[(oldest + index) % SCROLLBACK_LINES]
+    }
+}
+
+const EMPTY_SCROLLBACK: Scrollback = Scrollback::new();
+
+static SCROLLBACK: Mutex<[Scrollback; TERMINAL_COUNT]> = Mutex::new([EMPTY_SCROLLBACK; TERMINAL_COUNT]);
+
+impl Writer {
+    fn push_to_scrollback(&mut self) {
+        let mut scrollbacks = SCROLLBACK.lock();
+        let scrollback = &mut scrollbacks[self.terminal];
+        scrollback.push(self.shadow[self.first_text_row()]);
+        // se esta olhando o historico, a visao fica parada no mesmo lugar
+        // entao a distancia ate a parte "ao vivo" aumenta uma linha
+        if self.scroll_offset > 0 && self.terminal == self.displayed_terminal {
+            self.scroll_offset = (self.scroll_offset + 1).min(scrollback.len);
+        }
+    }
+}
+
+// ainda nao tem teclado pra chamar isso (PageUp/PageDown), por isso o allow(dead_code)
+#[allow(dead_code)]
+impl Writer {
+    // volta `lines` linhas no historico, pausando a saida na tela
+    // a saida continua indo pro shadow, só nao aparece ate voltar pro fim
+    pub fn scroll_up(&mut self, lines: usize) {
+        // antes de sair da tela atual, garante que o VGA tem tudo q ja foi escrito
+        if self.scroll_offset == 0 {
+            self.flush();
+        }
+        let scrollbacks = SCROLLBACK.lock();
+        let scrollback = &scrollbacks[self.terminal];
+        if scrollback.len == 0 {
+            return;
+        }
+        self.scroll_offset = (self.scroll_offset + lines).min(scrollback.len);
+        self.redraw(scrollback);
+    }
+
+    // avanca `lines` linhas em direcao a saida atual, quando chega em 0 volta a mostrar o shadow
+    pub fn scroll_down(&mut self, lines: usize) {
+        if self.scroll_offset == 0 {
+            return;
+        }
+        self.scroll_offset = self.scroll_offset.saturating_sub(lines);
+        if self.scroll_offset == 0 {
+            self.dirty = [true; MAX_HEIGHT];
+            self.flush();
+        } else {
+            let scrollbacks = SCROLLBACK.lock();
+            self.redraw(&scrollbacks[self.terminal]);
+        }
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.text_rows() - 1);
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.text_rows() - 1);
+    }
+
+    pub fn is_scrolled_back(&self) -> bool {
+        self.scroll_offset > 0
+    }
+
+    // historico + shadow formam uma lista "virtual" de linhas
+    // a janela visivel termina `scroll_offset` linhas acima do fim dessa lista
+    // a linha de status continua aparecendo no lugar dela
+    fn redraw(&mut self, scrollback: &Scrollback) {
+        let first = self.first_text_row();
+        for row in 0..self.text_rows() {
+            let virtual_row = scrollback.len + row - self.scroll_offset;
+            for col in 0..self.width {
+                let character = if virtual_row < scrollback.len {
+                    scrollback.line(virtual_row)[col]
+                } else {
+                    self.shadow[first + virtual_row - scrollback.len][col]
+                };
+                self.write_screen(first + row, col, character);
+            }
+        }
+        if let Some(row) = self.status_row() {
+            for col in 0..self.width {
+                self.write_screen(row, col, self.shadow[row][col]);
+            }
+        }
+    }
+}
+
+// api de cores pra quem quiser mudar o foreground/background em tempo de execucao
+#[allow(dead_code)]
+impl Writer {
+    pub fn set_color(&mut self, foreground: Color, background: Color) {
+        self.color_code = ColorCode::new(foreground, background);
+    }
+
+    pub fn set_color_code(&mut self, color_code: ColorCode) {
+        self.color_code = color_code;
     }
-}
\ No newline at end of file
+
+    // retorna (foreground, background)
+    pub fn color(&self) -> (Color, Color) {
+        (self.color_code.foreground(), self.color_code.background())
+    }
+
+    pub fn color_code(&self) -> ColorCode {
+        self.color_code
+    }
+
+    pub fn set_default_color_code(&mut self, color_code: ColorCode) {
+        self.default_color_code = color_code;
+    }
+
+    pub fn default_color_code(&self) -> ColorCode {
+        self.default_color_code
+    }
+
+    // escolhe entre texto piscando (bit 7 = blink) e 16 cores de background (bit 7 = intensidade)
+    pub fn set_blink_enabled(&mut self, enabled: bool) {
+        vga_registers::set_blink(enabled);
+    }
+
+    pub fn blink_enabled(&self) -> bool {
+        vga_registers::blink_enabled()
+    }
+
+    // troca a cor só durante a closure e depois volta pra cor anterior
+    // ex: writer.with_color(Color::Red, Color::Black, |w| w.write_string("erro"));
+    pub fn with_color<F, R>(&mut self, foreground: Color, background: Color, f: F) -> R
+    where
+        F: FnOnce(&mut Writer) -> R,
+    {
+        let previous = self.color_code;
+        self.color_code = ColorCode::new(foreground, background);
+        let result = f(self);
+        self.color_code = previous;
+        result
+    }
+}
+
+#[allow(dead_code)]
+impl Writer {
+    // 0 é tratado como 1 (tab vira um espaco)
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.tab_width = width.clamp(1, self.width);
+    }
+
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+}
+
+// quebra por palavra: em vez de cortar no meio quando a linha enche, a palavra inteira vai pra linha de baixo
+// as linhas de continuacao comecam com `indent` espacos (hanging indent), ex com indent 4:
+// [INFO] mensagem bem comprida que nao
+//     cabe numa linha so
+#[derive(Clone, Copy)]
+struct WordWrap {
+    enabled: bool,
+    indent: usize,
+    continuation: bool, // a linha atual foi criada por uma quebra (comeca com o indent)
+}
+
+impl WordWrap {
+    const fn new() -> WordWrap {
+        WordWrap {
+            enabled: false,
+            indent: 0,
+            continuation: false,
+        }
+    }
+}
+
+impl Writer {
+    // linha nova de continuacao, já com o indent
+    fn wrap_line(&mut self) {
+        self.new_line();
+        self.wrap.continuation = true;
+        let indent = self.wrap.indent;
+        self.clear_line_range(self.row_position, 0, indent);
+        self.column_position = indent;
+    }
+
+    // a linha encheu no meio de uma palavra -> move o pedaco depois do ultimo espaco pra linha de baixo
+    // palavra maior que a linha (sem espaco) é cortada igual sem o wrap
+    fn wrap_word(&mut self) {
+        let row = self.row_position;
+        let start = if self.wrap.continuation { self.wrap.indent } else { 0 };
+        let space = (start + 1..self.width)
+            .rev()
+            .find(|&col| self.shadow[row][col].ascii_character == b' ');
+        let space = match space {
+            // a palavra tem que caber depois do indent
+            Some(space) if space + 1 >= self.wrap.indent => space,
+            _ => return self.wrap_line(),
+        };
+
+        let len = self.width - space - 1;
+        let mut word = [BLANK; MAX_WIDTH];
+        word[..len].copy_from_slice(&self.shadow[row][space + 1..self.width]);
+        self.clear_line_range(row, space + 1, self.width);
+
+        self.wrap_line();
+        for cell in word[..len].iter() {
+            self.write_cell(self.row_position, self.column_position, *cell);
+            self.column_position += 1;
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Writer {
+    pub fn set_word_wrap(&mut self, enabled: bool) {
+        self.wrap.enabled = enabled;
+    }
+
+    pub fn word_wrap(&self) -> bool {
+        self.wrap.enabled
+    }
+
+    // espacos no comeco das linhas de continuacao, limitado a metade da largura
+    pub fn set_hanging_indent(&mut self, indent: usize) {
+        self.wrap.indent = indent.min(self.width / 2);
+    }
+
+    pub fn hanging_indent(&self) -> usize {
+        self.wrap.indent
+    }
+}
+
+// regiao de scroll (margens de cima e de baixo, igual o CSI r do VT100)
+// ex: cabecalho na linha 0 e rodape na 24 parados, só 1..=23 rola
+#[allow(dead_code)]
+impl Writer {
+    // linhas absolutas da tela; limitado a area de texto (a linha de status nunca entra)
+    // regiao com menos de 2 linhas é ignorada
+    pub fn set_scroll_region(&mut self, top: usize, bottom: usize) {
+        let top = top.max(self.first_text_row());
+        let bottom = bottom.min(self.last_text_row());
+        self.scroll_margins = if top < bottom { Some((top, bottom)) } else { None };
+    }
+
+    pub fn reset_scroll_region(&mut self) {
+        self.scroll_margins = None;
+    }
+
+    // (primeira, ultima) linha que faz scroll
+    pub fn scroll_region(&self) -> (usize, usize) {
+        let first = self.first_text_row();
+        let last = self.last_text_row();
+        match self.scroll_margins {
+            // a linha de status pode ter sido reservada depois das margens
+            Some((top, bottom)) if top >= first && bottom <= last && top < bottom => (top, bottom),
+            _ => (first, last),
+        }
+    }
+}
+
+// terminais virtuais: cada um tem seu shadow, cor, cursor e historico
+// o writer carrega o estado de um terminal por vez, os outros ficam guardados em TERMINALS
+pub const TERMINAL_COUNT: usize = 4;
+
+struct TerminalState {
+    column_position: usize,
+    row_position: usize,
+    color_code: ColorCode,
+    shadow: [[ScreenChar; MAX_WIDTH]; MAX_HEIGHT],
+    dirty: [bool; MAX_HEIGHT],
+    ansi: AnsiParser,
+    tab_width: usize,
+    wrap: WordWrap,
+    scroll_margins: Option<(usize, usize)>,
+}
+
+impl TerminalState {
+    const fn new() -> TerminalState {
+        TerminalState {
+            column_position: 0,
+            row_position: DEFAULT_HEIGHT - 1,
+            color_code: DEFAULT_COLOR_CODE,
+            shadow: [[BLANK; MAX_WIDTH]; MAX_HEIGHT],
+            dirty: [true; MAX_HEIGHT],
+            ansi: AnsiParser::new(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            wrap: WordWrap::new(),
+            scroll_margins: None,
+        }
+    }
+}
+
+const NEW_TERMINAL: TerminalState = TerminalState::new();
+
+static TERMINALS: Mutex<[TerminalState; TERMINAL_COUNT]> = Mutex::new([NEW_TERMINAL; TERMINAL_COUNT]);
+
+impl Writer {
+    fn save_terminal(&self, state: &mut TerminalState) {
+        state.column_position = self.column_position;
+        state.row_position = self.row_position;
+        state.color_code = self.color_code;
+        state.shadow = self.shadow;
+        state.dirty = self.dirty;
+        state.ansi = self.ansi;
+        state.tab_width = self.tab_width;
+        state.wrap = self.wrap;
+        state.scroll_margins = self.scroll_margins;
+    }
+
+    fn load_terminal(&mut self, state: &TerminalState) {
+        // a linha de status é a mesma em todos os terminais
+        let status = self.status_row().map(|row| (row, self.shadow[row]));
+        self.column_position = state.column_position;
+        self.row_position = state
+            .row_position
+            .clamp(self.first_text_row(), self.last_text_row());
+        self.color_code = state.color_code;
+        self.shadow = state.shadow;
+        self.dirty = state.dirty;
+        self.ansi = state.ansi;
+        self.tab_width = state.tab_width;
+        self.wrap = state.wrap;
+        self.scroll_margins = state.scroll_margins;
+        if let Some((row, line)) = status {
+            self.shadow[row] = line;
+            self.dirty[row] = true;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn active_terminal(&self) -> usize {
+        self.displayed_terminal
+    }
+
+    // troca o terminal que aparece na tela
+    pub fn switch_terminal(&mut self, terminal: usize) {
+        if terminal >= TERMINAL_COUNT || terminal == self.displayed_terminal {
+            return;
+        }
+        let mut terminals = TERMINALS.lock();
+        self.save_terminal(&mut terminals[self.terminal]);
+        self.load_terminal(&terminals[terminal]);
+        drop(terminals);
+
+        self.terminal = terminal;
+        self.displayed_terminal = terminal;
+        // o historico do terminal anterior nao faz sentido aqui
+        self.scroll_offset = 0;
+        self.dirty = [true; MAX_HEIGHT];
+        self.flush();
+    }
+
+    // roda a closure com o estado de outro terminal carregado (que pode estar em segundo plano)
+    // ex: writer.with_terminal(2, |w| w.write_string("log do driver"));
+    #[allow(dead_code)]
+    pub fn with_terminal<F, R>(&mut self, terminal: usize, f: F) -> R
+    where
+        F: FnOnce(&mut Writer) -> R,
+    {
+        if terminal >= TERMINAL_COUNT || terminal == self.terminal {
+            return f(self);
+        }
+        let previous = self.terminal;
+        {
+            let mut terminals = TERMINALS.lock();
+            self.save_terminal(&mut terminals[previous]);
+            self.load_terminal(&terminals[terminal]);
+        }
+        self.terminal = terminal;
+
+        let result = f(self);
+
+        {
+            let mut terminals = TERMINALS.lock();
+            self.save_terminal(&mut terminals[terminal]);
+            self.load_terminal(&terminals[previous]);
+        }
+        self.terminal = previous;
+        result
+    }
+}
+
+// escrita em posicao fixa (status, relogio, elementos de TUI)
+// nao mexe no cursor principal nem causa scroll, o que passar do fim da linha é cortado
+#[allow(dead_code)]
+impl Writer {
+    pub fn write_at(&mut self, row: usize, col: usize, s: &str, color: Option<ColorCode>) {
+        if row >= self.height {
+            return;
+        }
+        let color_code = color.unwrap_or(self.color_code);
+        for (i, c) in s.chars().enumerate() {
+            let col = col + i;
+            if col >= self.width {
+                break;
+            }
+            let ascii_character = cp437::from_char(c).unwrap_or(0xfe);
+            self.write_cell(row, col, ScreenChar {
+                ascii_character,
+                color_code,
+            });
+        }
+        self.auto_flush();
+    }
+}
+
+// linha de status: uma linha (em cima ou embaixo) que fica fora do scroll
+// o conteudo é desenhado pelo modulo status_bar atraves do set_status_line
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusPosition {
+    Top,
+    Bottom,
+}
+
+impl Writer {
+    fn status_row(&self) -> Option<usize> {
+        match self.status_position {
+            Some(StatusPosition::Top) => Some(0),
+            Some(StatusPosition::Bottom) => Some(self.height - 1),
+            None => None,
+        }
+    }
+
+    // primeira e ultima linha (inclusive) onde o texto normal vai e que fazem scroll
+    fn first_text_row(&self) -> usize {
+        match self.status_position {
+            Some(StatusPosition::Top) => 1,
+            _ => 0,
+        }
+    }
+
+    fn last_text_row(&self) -> usize {
+        match self.status_position {
+            Some(StatusPosition::Bottom) => self.height - 2,
+            _ => self.height - 1,
+        }
+    }
+
+    pub fn text_rows(&self) -> usize {
+        self.last_text_row() - self.first_text_row() + 1
+    }
+}
+
+#[allow(dead_code)]
+impl Writer {
+    // None libera a linha de novo pro texto normal
+    pub fn reserve_status_row(&mut self, position: Option<StatusPosition>) {
+        if self.scroll_offset > 0 {
+            self.scroll_down(self.scroll_offset);
+        }
+        if let Some(row) = self.status_row() {
+            self.clear_row(row);
+        }
+        self.status_position = position;
+        if let Some(row) = self.status_row() {
+            self.clear_row(row);
+        }
+        self.row_position = self
+            .row_position
+            .clamp(self.first_text_row(), self.last_text_row());
+        self.auto_flush();
+    }
+
+    pub fn status_position(&self) -> Option<StatusPosition> {
+        self.status_position
+    }
+
+    // substitui o conteudo da linha de status, o resto da linha é preenchido com espaco
+    pub fn set_status_line(&mut self, text: &str, color_code: ColorCode) {
+        let row = match self.status_row() {
+            Some(row) => row,
+            None => return,
+        };
+        let mut chars = text.chars();
+        for col in 0..self.width {
+            let c = chars.next().unwrap_or(' ');
+            self.write_cell(row, col, ScreenChar {
+                ascii_character: cp437::from_char(c).unwrap_or(0xfe),
+                color_code,
+            });
+        }
+        // diferente do resto, a status aparece mesmo olhando o historico
+        if self.scroll_offset > 0 && self.terminal == self.displayed_terminal {
+            for col in 0..self.width {
+                self.write_screen(row, col, self.shadow[row][col]);
+            }
+        }
+        self.auto_flush();
+    }
+}
+
+// primitivas por celula pro modulo window (e outros que desenham numa area da tela)
+// nao mexem no cursor nem fazem flush, quem chama decide quando
+#[allow(dead_code)]
+impl Writer {
+    // `byte` é o glifo cp437 direto, sem interpretar controle
+    pub fn put_char_at(&mut self, row: usize, col: usize, byte: u8, color_code: ColorCode) {
+        if row >= self.height || col >= self.width {
+            return;
+        }
+        self.write_cell(row, col, ScreenChar {
+            ascii_character: byte,
+            color_code,
+        });
+    }
+
+    // sobe uma linha o retangulo e limpa a ultima linha dele com `color_code`
+    pub fn scroll_rect_up(&mut self, top: usize, left: usize, width: usize, height: usize, color_code: ColorCode) {
+        let bottom = (top + height).min(self.height);
+        let right = (left + width).min(self.width);
+        if top >= bottom || left >= right {
+            return;
+        }
+        for row in top + 1..bottom {
+            let (upper, lower) = self.shadow.split_at_mut(row);
+            upper[row - 1][left..right].copy_from_slice(&lower[0][left..right]);
+            self.dirty[row - 1] = true;
+        }
+        let blank = ScreenChar {
+            ascii_character: b' ',
+            color_code,
+        };
+        for col in left..right {
+            self.write_cell(bottom - 1, col, blank);
+        }
+    }
+
+    pub fn flush_if_auto(&mut self) {
+        self.auto_flush();
+    }
+}
+
+// destaque (linha selecionada, regiao de erro piscando...) sem reescrever o texto
+#[allow(dead_code)]
+impl Writer {
+    // troca só a cor das celulas que ja estao na tela, o texto fica igual
+    // ex: writer.set_attr(5, 0..80, ColorCode::new(Color::Black, Color::LightGray)) destaca a linha 5
+    pub fn set_attr(&mut self, row: usize, cols: Range<usize>, color_code: ColorCode) {
+        if row >= self.height {
+            return;
+        }
+        let end = cols.end.min(self.width);
+        for col in cols.start.min(end)..end {
+            self.shadow[row][col].color_code = color_code;
+        }
+        self.dirty[row] = true;
+        self.auto_flush();
+    }
+}
+
+impl Writer {
+    // usado pelo panic: volta pro terminal visivel, sai do historico, tira a barra de status
+    // e limpa a tela com `color_code`, sem depender de nenhum outro estado
+    pub fn take_over_screen(&mut self, color_code: ColorCode) {
+        self.terminal = self.displayed_terminal;
+        self.scroll_offset = 0;
+        self.status_position = None;
+        self.auto_flush = true;
+        self.ansi = AnsiParser::new();
+        self.color_code = color_code;
+        self.clear_screen();
+    }
+}
+
+// posicao de escrita (linha, coluna) -> editor de linha, prompts, TUI
+// coluna == width() significa linha cheia: o proximo caractere quebra pra linha de baixo
+// push/pop guardam cursor + cor numa pilha, pra desenhar em outro lugar e voltar
+const STATE_STACK_DEPTH: usize = 8;
+
+#[derive(Clone, Copy)]
+struct SavedState {
+    row_position: usize,
+    column_position: usize,
+    color_code: ColorCode,
+}
+
+#[allow(dead_code)]
+impl Writer {
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row_position, self.column_position)
+    }
+
+    // fora da area de texto é limitado pra primeira/ultima linha de texto
+    pub fn set_cursor(&mut self, row: usize, col: usize) {
+        self.row_position = row.clamp(self.first_text_row(), self.last_text_row());
+        self.column_position = col.min(self.width);
+        self.auto_flush();
+    }
+
+    // false se a pilha esta cheia (nada é guardado)
+    pub fn push_state(&mut self) -> bool {
+        if self.state_depth >= STATE_STACK_DEPTH {
+            return false;
+        }
+        self.state_stack[self.state_depth] = SavedState {
+            row_position: self.row_position,
+            column_position: self.column_position,
+            color_code: self.color_code,
+        };
+        self.state_depth += 1;
+        true
+    }
+
+    // false se a pilha esta vazia
+    pub fn pop_state(&mut self) -> bool {
+        if self.state_depth == 0 {
+            return false;
+        }
+        self.state_depth -= 1;
+        let state = self.state_stack[self.state_depth];
+        self.color_code = state.color_code;
+        self.set_cursor(state.row_position, state.column_position);
+        true
+    }
+}
+
+// copia da saida pra outro lugar (serial no qemu -> o log nao se perde no scroll e da pra salvar)
+// a copia recebe o texto ainda com as sequencias ansi, do jeito que foi escrito
+// o console da copia é escrito com o lock do WRITER pego, entao nao pode ser o proprio WRITER
+// (o console::for_each pula ele enquanto for o mirror -> o println! nao sai duas vezes)
+#[allow(dead_code)]
+impl Writer {
+    pub fn set_mirror(&mut self, mirror: Option<ConsoleRef>) {
+        self.mirror = mirror;
+    }
+
+    pub fn mirror(&self) -> Option<ConsoleRef> {
+        self.mirror
+    }
+}
+
+// dump do que esta na tela em texto puro pra um canal de debug (serial, porta 0xE9)
+// pra teste automatico e bug report conseguirem ver exatamente o que aparecia
+static DEBUG_SINK: Mutex<Option<fn(&str)>> = Mutex::new(None);
+
+// None desliga
+#[allow(dead_code)]
+pub fn set_debug_sink(sink: Option<fn(&str)>) {
+    *DEBUG_SINK.lock() = sink;
+}
+
+#[allow(dead_code)]
+impl Writer {
+    // le direto da memoria do VGA (se estiver olhando o historico, sai o historico)
+    // uma linha de texto por linha da tela, sem os espacos do fim
+    // com `attributes` cada linha vem seguida de outra com o byte de cor de cada celula em hex
+    // retorna false se nao tem sink registrado
+    pub fn dump(&self, attributes: bool) -> bool {
+        let sink = match *DEBUG_SINK.lock() {
+            Some(sink) => sink,
+            None => return false,
+        };
+        for row in 0..self.height {
+            let mut line = [0u8; MAX_WIDTH * 4];
+            let mut len = 0;
+            let mut used = 0; // tamanho sem os espacos do fim
+            for col in 0..self.width {
+                let c = match self.read_screen(row, col).ascii_character {
+                    0 => ' ',
+                    byte => cp437::to_char(byte).unwrap_or('?'),
+                };
+                len += c.encode_utf8(&mut line[len..]).len();
+                if c != ' ' {
+                    used = len;
+                }
+            }
+            sink(core::str::from_utf8(&line[..used]).unwrap_or(""));
+            sink("\n");
+
+            if attributes {
+                const HEX: &[u8; 16] = b"0123456789abcdef";
+                let mut hex = [0u8; MAX_WIDTH * 2];
+                for col in 0..self.width {
+                    let attr = self.read_screen(row, col).color_code.0;
+                    hex[col * 2] = HEX[(attr >> 4) as usize];
+                    hex[col * 2 + 1] = HEX[(attr & 0x0f) as usize];
+                }
+                sink(core::str::from_utf8(&hex[..self.width * 2]).unwrap_or(""));
+                sink("\n");
+            }
+        }
+        true
+    }
+}
+
+// foto da tela inteira + cursor, pra um programa de tela cheia (editor, pager) poder
+// tomar conta do display e depois devolver tudo como estava
+pub struct Snapshot {
+    cells: [[ScreenChar; MAX_WIDTH]; MAX_HEIGHT],
+    row_position: usize,
+    column_position: usize,
+    color_code: ColorCode,
+    mode: Mode,
+    status_position: Option<StatusPosition>,
+    cursor_start: u8, // formato/visibilidade do cursor de hardware
+    cursor_end: u8,
+}
+
+#[allow(dead_code)]
+impl Writer {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cells: self.shadow,
+            row_position: self.row_position,
+            column_position: self.column_position,
+            color_code: self.color_code,
+            mode: self.mode,
+            status_position: self.status_position,
+            cursor_start: crtc_read(CRTC_CURSOR_START),
+            cursor_end: crtc_read(CRTC_CURSOR_END),
+        }
+    }
+
+    // se o modo de video foi trocado nesse meio tempo, volta pro modo da foto antes
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        if self.scroll_offset > 0 {
+            self.scroll_down(self.scroll_offset);
+        }
+        self.set_text_mode(snapshot.mode);
+        self.shadow = snapshot.cells;
+        self.row_position = snapshot.row_position;
+        self.column_position = snapshot.column_position;
+        self.color_code = snapshot.color_code;
+        self.status_position = snapshot.status_position;
+        self.ansi = AnsiParser::new();
+        crtc_write(CRTC_CURSOR_START, snapshot.cursor_start);
+        crtc_write(CRTC_CURSOR_END, snapshot.cursor_end);
+        self.refresh();
+    }
+}
+
+// limpar a tela (antes do shell, depois do diagnostico de boot...)
+#[allow(dead_code)]
+impl Writer {
+    // apaga todas as linhas com a cor atual e leva o cursor pro canto superior esquerdo
+    pub fn clear_screen(&mut self) {
+        // se estava olhando o historico, volta pra tela atual antes de limpar
+        if self.scroll_offset > 0 {
+            self.scroll_down(self.scroll_offset);
+        }
+        for row in self.first_text_row()..=self.last_text_row() {
+            self.clear_row(row);
+        }
+        self.row_position = self.first_text_row();
+        self.column_position = 0;
+        self.auto_flush();
+    }
+
+    // volta pras cores padrao, descarta sequencia ansi pela metade e limpa a tela
+    pub fn reset(&mut self) {
+        self.color_code = self.default_color_code;
+        self.ansi = AnsiParser::new();
+        self.clear_screen();
+    }
+}
+
+// modos de texto: 80x25 (fonte 8x16, o padrao), 80x50 (mesmos 400 scanlines com a fonte 8x8)
+// e 90x60 (timing de 720x480 com a fonte 8x8)
+// a fonte 8x16 veio da BIOS e nao tem como pedir de novo, entao é guardada antes de trocar
+use crate::framebuffer;
+use crate::vga_registers::{ModeRegisters, FONT_SIZE};
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Text80x25,
+    Text80x50,
+    Text90x60,
+}
+
+impl Mode {
+    pub fn width(self) -> usize {
+        match self {
+            Mode::Text80x25 | Mode::Text80x50 => 80,
+            Mode::Text90x60 => 90,
+        }
+    }
+
+    pub fn height(self) -> usize {
+        match self {
+            Mode::Text80x25 => 25,
+            Mode::Text80x50 => 50,
+            Mode::Text90x60 => 60,
+        }
+    }
+
+    pub fn registers(self) -> &'static ModeRegisters {
+        match self {
+            Mode::Text80x25 => &vga_registers::TEXT_80X25,
+            Mode::Text80x50 => &vga_registers::TEXT_80X50,
+            Mode::Text90x60 => &vga_registers::TEXT_90X60,
+        }
+    }
+
+    // só o 80x25 usa a fonte original 8x16
+    fn uses_bios_font(self) -> bool {
+        self == Mode::Text80x25
+    }
+}
+
+static SAVED_FONT: Mutex<[u8; FONT_SIZE]> = Mutex::new([0; FONT_SIZE]);
+
+// o que muda no shadow quando o numero de linhas/colunas muda
+struct Resize {
+    first: usize, // primeira linha de texto (igual antes e depois)
+    old_last: usize,
+    new_last: usize,
+    old_width: usize,
+    new_width: usize,
+}
+
+// a fonte 8x8 do framebuffer (mesma ordem cp437) serve pro modo texto tambem
+fn load_font_8x8() {
+    let font = framebuffer::default_font();
+    let mut glyphs = [0; 256 * 8];
+    for (i, glyph) in glyphs.chunks_mut(8).enumerate() {
+        glyph.copy_from_slice(&font.glyph(i)[..8]);
+    }
+    vga_registers::load_font(&glyphs, 8);
+}
+
+impl TerminalState {
+    // diminuindo: ficam as ultimas linhas (as de baixo, perto do cursor), as de cima vao pro historico e o
+    // cursor sobe junto com o texto
+    // aumentando: as linhas/colunas novas ficam em branco
+    fn fit(&mut self, resize: &Resize, scrollback: &mut Scrollback) {
+        let mut shift = 0;
+        if resize.new_last < resize.old_last {
+            shift = resize.old_last - resize.new_last;
+            for row in resize.first..resize.first + shift {
+                scrollback.push(self.shadow[row]);
+            }
+            self.shadow
+                .copy_within(resize.first + shift..=resize.old_last, resize.first);
+            self.row_position = self.row_position.saturating_sub(shift).max(resize.first);
+        }
+        let blank = ScreenChar {
+            ascii_character: b' ',
+            color_code: self.color_code,
+        };
+        for row in resize.old_last + 1 - shift..MAX_HEIGHT {
+            self.shadow[row] = [blank; MAX_WIDTH];
+        }
+        if resize.new_width > resize.old_width {
+            for row in self.shadow.iter_mut() {
+                row[resize.old_width..resize.new_width].fill(blank);
+            }
+        }
+        self.column_position = self.column_position.min(resize.new_width);
+        self.dirty = [true; MAX_HEIGHT];
+        // as margens eram pro tamanho antigo
+        self.scroll_margins = None;
+    }
+}
+
+#[allow(dead_code)]
+impl Writer {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    // registradores do modo atual (o modulo graphics usa pra voltar pro texto)
+    pub fn mode_registers(&self) -> &'static ModeRegisters {
+        self.mode.registers()
+    }
+
+    // troca o modo de video e adapta o texto que ja esta na tela pro tamanho novo
+    pub fn set_text_mode(&mut self, mode: Mode) {
+        if mode == self.mode {
+            return;
+        }
+        if self.mode.uses_bios_font() {
+            vga_registers::save_font(&mut SAVED_FONT.lock());
+        }
+        vga_registers::set_mode(mode.registers());
+        if mode.uses_bios_font() {
+            vga_registers::restore_font(&SAVED_FONT.lock());
+        } else {
+            load_font_8x8();
+        }
+        self.mode = mode;
+        self.resize(mode.width(), mode.height());
+    }
+
+    // adapta o estado do writer e de todos os terminais guardados pro tamanho novo
+    fn resize(&mut self, width: usize, height: usize) {
+        if self.scroll_offset > 0 {
+            self.scroll_down(self.scroll_offset);
+        }
+        // a linha de status de baixo muda de lugar -> tira ela do meio do texto e poe de volta no fim
+        let old_status = self.status_row();
+        let status_line = old_status.map(|row| self.shadow[row]);
+        let resize = Resize {
+            first: self.first_text_row(),
+            old_last: self.last_text_row(),
+            new_last: match self.status_position {
+                Some(StatusPosition::Bottom) => height - 2,
+                _ => height - 1,
+            },
+            old_width: self.width,
+            new_width: width,
+        };
+
+        let mut scrollbacks = SCROLLBACK.lock();
+        let mut terminals = TERMINALS.lock();
+        let mut current = TerminalState::new();
+        self.save_terminal(&mut current);
+        for (i, state) in terminals.iter_mut().enumerate() {
+            let state = if i == self.terminal { &mut current } else { state };
+            if let Some(row) = old_status {
+                state.shadow[row] = [BLANK; MAX_WIDTH];
+            }
+            state.fit(&resize, &mut scrollbacks[i]);
+        }
+        drop(scrollbacks);
+        drop(terminals);
+
+        self.width = width;
+        self.height = height;
+        self.tab_width = self.tab_width.min(width);
+        self.wrap.indent = self.wrap.indent.min(width / 2);
+        self.load_terminal(&current);
+        if let (Some(row), Some(line)) = (self.status_row(), status_line) {
+            self.shadow[row] = line;
+        }
+        self.reset_origin();
+        self.dirty = [true; MAX_HEIGHT];
+        self.flush();
+    }
+}
+
+// glifos customizados: enquanto o plano da fonte esta mapeado o texto em 0xb8000 nao esta acessivel
+// entao passa pelo writer (com o lock pego ninguem escreve na tela) e sem interrupcao no meio
+use crate::cpu::without_interrupts;
+use crate::vga_registers::Glyph;
+
+#[allow(dead_code)]
+impl Writer {
+    // ex: writer.set_glyphs(0x80, &LOGO) e depois escrever os bytes 0x80.. com put_char_at
+    pub fn set_glyphs(&mut self, first: u8, glyphs: &[Glyph]) {
+        without_interrupts(|| vga_registers::write_glyphs(first, glyphs));
+    }
+
+    pub fn glyph(&self, index: u8) -> Glyph {
+        without_interrupts(|| vga_registers::read_glyph(index))
+    }
+}
+
+// cursor de hardware (aquele q fica piscando)
+// é controlado pelo CRTC -> escreve o indice do registrador na porta 0x3D4 e o valor na 0x3D5
+use crate::vga_registers::{self, read_crtc as crtc_read, write_crtc as crtc_write};
+
+const CRTC_CURSOR_START: u8 = 0x0A; // bit 5 desliga o cursor
+const CRTC_CURSOR_END: u8 = 0x0B;
+const CRTC_CURSOR_LOCATION_HIGH: u8 = 0x0E;
+const CRTC_CURSOR_LOCATION_LOW: u8 = 0x0F;
+
+impl Writer {
+    // coloca o cursor de hardware na posicao onde o proximo caractere vai ser escrito
+    fn update_cursor(&self) {
+        // olhando o historico -> cursor fica onde estava
+        if self.scroll_offset > 0 {
+            return;
+        }
+        let row = self.row_position;
+        // quando a linha esta cheia o proximo byte vai pra linha de baixo, mas o cursor fica no fim
+        let col = self.column_position.min(self.width - 1);
+        let position = ((self.origin + row) * self.width + col) as u16;
+
+        crtc_write(CRTC_CURSOR_LOCATION_LOW, (position & 0xff) as u8);
+        crtc_write(CRTC_CURSOR_LOCATION_HIGH, (position >> 8) as u8);
+    }
+
+    // allow(dead_code) -> ainda ninguem usa, mas é api publica pro shell/editor
+    #[allow(dead_code)]
+    // esconde o cursor setando o bit 5 do registrador cursor start
+    pub fn hide_cursor(&mut self) {
+        let start = crtc_read(CRTC_CURSOR_START);
+        crtc_write(CRTC_CURSOR_START, start | 0x20);
+    }
+
+    #[allow(dead_code)]
+    // mostra o cursor como um underline (as duas ultimas scanlines da celula)
+    pub fn show_cursor(&mut self) {
+        self.set_cursor_shape(CursorShape::Underline);
+    }
+}
+
+// formato do cursor: o editor/shell pode usar bloco pra insert e underline pra overwrite (ou ao contrario)
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    Underline,
+    Block,
+    Hidden,
+}
+
+#[allow(dead_code)]
+impl Writer {
+    // as scanlines dependem da altura da fonte do modo atual (16 no 80x25, 8 no 80x50/90x60)
+    pub fn set_cursor_shape(&mut self, shape: CursorShape) {
+        let last = vga_registers::font_height() as u8 - 1;
+        match shape {
+            CursorShape::Underline => self.set_cursor_scanlines(last - 1, last),
+            CursorShape::Block => self.set_cursor_scanlines(0, last),
+            CursorShape::Hidden => self.hide_cursor(),
+        }
+    }
+
+    // scanline inicial e final (inclusive) dentro da celula, a partir de 0 em cima
+    // tambem liga o cursor se estava escondido
+    pub fn set_cursor_scanlines(&mut self, start: u8, end: u8) {
+        let last = vga_registers::font_height() as u8 - 1;
+        let start = start.min(last);
+        let end = end.clamp(start, last);
+        let start_register = crtc_read(CRTC_CURSOR_START);
+        crtc_write(CRTC_CURSOR_START, (start_register & 0xc0) | start);
+        let end_register = crtc_read(CRTC_CURSOR_END);
+        crtc_write(CRTC_CURSOR_END, (end_register & 0xe0) | end);
+        self.update_cursor();
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        crtc_read(CRTC_CURSOR_START) & 0x20 == 0
+    }
+}
+
+// vga text buffer só suporta o code page 437
+// strings rust são utf-8, entao cada char é traduzido pro byte do cp437 que tem o mesmo desenho
+// ascii printável passa direto, acentos/box-drawing/etc passam pela tabela do modulo cp437
+// caso nao tenha glifo, é colocado um ■ (0xfe)
+use crate::cp437;
+
+impl Writer {
+    // converte cada char da string em byte e escreve um a um
+    pub fn write_string(&mut self, s: &str) {
+        if let Some(mirror) = self.mirror {
+            mirror.lock().write_str(s);
+        }
+        for c in s.chars() {
+            if !c.is_ascii() {
+                // nao ascii no meio de uma sequencia ansi -> sequencia invalida, descarta
+                self.ansi.state = AnsiState::Normal;
+                self.put_glyph(cp437::from_char(c).unwrap_or(0xfe));
+                continue;
+            }
+            let byte = c as u8;
+            if self.ansi.state != AnsiState::Normal || byte == ANSI_ESC {
+                self.ansi_byte(byte);
+                continue;
+            }
+            match byte {
+                // ASCII byte printável ou caractere de controle suportado
+                0x20..=0x7e | b'\n' | b'\r' | b'\t' | BACKSPACE => self.put_byte(byte),
+                // não é parte do escopo printável do ASCII
+                _ => self.put_glyph(0xfe),
+            }
+        }
+        self.auto_flush();
+    }
+}
+
+// sequencias de escape ansi: ESC [ parametros ; separados letra_final
+// ex: \x1b[31m -> foreground vermelho, \x1b[2J -> limpa a tela, \x1b[5;10H -> move o cursor
+// sequencia desconhecida é simplesmente descartada (nao aparece nada na tela)
+const ANSI_ESC: u8 = 0x1b;
+const ANSI_MAX_PARAMS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnsiState {
+    Normal,
+    Escape, // recebeu ESC, esperando o [
+    Csi,    // dentro de ESC [ ... lendo os parametros
+}
+
+#[derive(Clone, Copy)]
+struct AnsiParser {
+    state: AnsiState,
+    params: [u16; ANSI_MAX_PARAMS],
+    param_count: usize,
+}
+
+impl AnsiParser {
+    const fn new() -> AnsiParser {
+        AnsiParser {
+            state: AnsiState::Normal,
+            params: [0; ANSI_MAX_PARAMS],
+            param_count: 0,
+        }
+    }
+
+    // parametro que nao veio (ou veio 0) vale `default`
+    fn param_or(&self, index: usize, default: u16) -> u16 {
+        if index < self.param_count && self.params[index] != 0 {
+            self.params[index]
+        } else {
+            default
+        }
+    }
+}
+
+// ordem das cores no ansi é diferente da ordem do VGA
+const ANSI_TO_VGA: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Brown,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightGray,
+];
+
+impl Writer {
+    fn ansi_byte(&mut self, byte: u8) {
+        match self.ansi.state {
+            AnsiState::Normal => self.ansi.state = AnsiState::Escape,
+            AnsiState::Escape => {
+                if byte == b'[' {
+                    self.ansi = AnsiParser::new();
+                    self.ansi.state = AnsiState::Csi;
+                } else {
+                    // só suportamos CSI, o resto é ignorado
+                    self.ansi.state = AnsiState::Normal;
+                }
+            }
+            AnsiState::Csi => match byte {
+                b'0'..=b'9' => {
+                    if self.ansi.param_count == 0 {
+                        self.ansi.param_count = 1;
+                    }
+                    let param = &mut self.ansi.params[self.ansi.param_count - 1];
+                    *param = param.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                }
+                b';' => {
+                    if self.ansi.param_count == 0 {
+                        self.ansi.param_count = 1;
+                    }
+                    // parametros a mais sao ignorados
+                    if self.ansi.param_count < ANSI_MAX_PARAMS {
+                        self.ansi.param_count += 1;
+                    }
+                }
+                // marcadores privados tipo o ? de \x1b[?25l -> ignora mas continua lendo
+                0x3c..=0x3f | 0x20..=0x2f => {}
+                // letra final da sequencia
+                0x40..=0x7e => {
+                    self.ansi.state = AnsiState::Normal;
+                    self.ansi_execute(byte);
+                }
+                // qualquer outra coisa no meio da sequencia -> aborta
+                _ => self.ansi.state = AnsiState::Normal,
+            },
+        }
+    }
+
+    fn ansi_execute(&mut self, command: u8) {
+        let n = self.ansi.param_or(0, 1) as usize;
+        match command {
+            b'm' => self.ansi_sgr(),
+            b'A' => {
+                self.row_position = self
+                    .row_position
+                    .saturating_sub(n)
+                    .max(self.first_text_row())
+            }
+            b'B' => self.row_position = (self.row_position + n).min(self.last_text_row()),
+            b'C' => self.column_position = (self.column_position + n).min(self.width - 1),
+            b'D' => self.column_position = self.column_position.saturating_sub(n),
+            // linha;coluna comecando em 1
+            b'H' | b'f' => {
+                let row = self.ansi.param_or(0, 1) as usize - 1;
+                let col = self.ansi.param_or(1, 1) as usize - 1;
+                self.row_position = (self.first_text_row() + row).min(self.last_text_row());
+                self.column_position = col.min(self.width - 1);
+            }
+            // margens: linha de cima;linha de baixo comecando em 1, sem parametro volta pra tela toda
+            // igual no VT100 o cursor vai pro canto
+            b'r' => {
+                if self.ansi.param_count == 0 {
+                    self.reset_scroll_region();
+                } else {
+                    let first = self.first_text_row();
+                    let top = first + self.ansi.param_or(0, 1) as usize - 1;
+                    let bottom = first + self.ansi.param_or(1, self.text_rows() as u16) as usize - 1;
+                    self.set_scroll_region(top, bottom);
+                }
+                self.row_position = self.first_text_row();
+                self.column_position = 0;
+            }
+            b'J' => match self.ansi.param_or(0, 0) {
+                0 => {
+                    self.clear_line_range(self.row_position, self.column_position, self.width);
+                    for row in self.row_position + 1..=self.last_text_row() {
+                        self.clear_row(row);
+                    }
+                }
+                1 => {
+                    for row in self.first_text_row()..self.row_position {
+                        self.clear_row(row);
+                    }
+                    self.clear_line_range(self.row_position, 0, self.column_position + 1);
+                }
+                2 | 3 => {
+                    for row in self.first_text_row()..=self.last_text_row() {
+                        self.clear_row(row);
+                    }
+                }
+                _ => {}
+            },
+            b'K' => match self.ansi.param_or(0, 0) {
+                0 => self.clear_line_range(self.row_position, self.column_position, self.width),
+                1 => self.clear_line_range(self.row_position, 0, self.column_position + 1),
+                2 => self.clear_row(self.row_position),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    // SGR = select graphic rendition -> cores
+    fn ansi_sgr(&mut self) {
+        if self.ansi.param_count == 0 {
+            self.color_code = self.default_color_code;
+            return;
+        }
+        let default = self.default_color_code.0;
+        for i in 0..self.ansi.param_count {
+            let fg = self.color_code.0 & 0x0f;
+            let bg = self.color_code.0 >> 4;
+            let (fg, bg) = match self.ansi.params[i] {
+                0 => (default & 0x0f, default >> 4),
+                // bold -> no VGA vira a versao clara da cor
+                1 => (fg | 0x08, bg),
+                22 => (fg & 0x07, bg),
+                p @ 30..=37 => (ANSI_TO_VGA[(p - 30) as usize] as u8, bg),
+                39 => (default & 0x0f, bg),
+                p @ 40..=47 => (fg, ANSI_TO_VGA[(p - 40) as usize] as u8),
+                49 => (fg, default >> 4),
+                p @ 90..=97 => (ANSI_TO_VGA[(p - 90) as usize] as u8 | 0x08, bg),
+                p @ 100..=107 => (fg, ANSI_TO_VGA[(p - 100) as usize] as u8 | 0x08),
+                _ => (fg, bg),
+            };
+            self.color_code = ColorCode(bg << 4 | fg);
+        }
+    }
+
+    // limpa as colunas [start, end) de uma linha
+    fn clear_line_range(&mut self, row: usize, start: usize, end: usize) {
+        let blank = ScreenChar {
+            ascii_character: b' ',
+            color_code: self.color_code,
+        };
+        for col in start..end.min(self.width) {
+            self.write_cell(row, col, blank);
+        }
+    }
+}
+
+use crate::console::ConsoleRef;
+use core::fmt;
+use core::ops::Range;
+
+impl fmt::Write for Writer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_string(s);
+        Ok(())
+    }
+}
+
+// writer global -> qualquer modulo pode usar sem precisar criar um Writer novo
+// lazy_static pq o compilador nao consegue resolver referencia pra raw pointer em tempo de compilacao
+// Mutex (spin) pq nao tem threads/bloqueio do s.o., entao fica so tentando ate conseguir o lock
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+lazy_static! {
+    pub static ref WRITER: Mutex<Writer> = Mutex::new(Writer {
+        column_position: 0,
+        row_position: DEFAULT_HEIGHT - 1,
+        color_code: DEFAULT_COLOR_CODE,
+        buffer: unsafe { &mut *(0xb8000 as *mut Buffer) },
+        shadow: [[BLANK; MAX_WIDTH]; MAX_HEIGHT],
+        dirty: [false; MAX_HEIGHT],
+        auto_flush: true,
+        scroll_offset: 0,
+        ansi: AnsiParser::new(),
+        tab_width: DEFAULT_TAB_WIDTH,
+        wrap: WordWrap::new(),
+        scroll_margins: None,
+        state_stack: [SavedState {
+            row_position: 0,
+            column_position: 0,
+            color_code: DEFAULT_COLOR_CODE,
+        }; STATE_STACK_DEPTH],
+        state_depth: 0,
+        default_color_code: DEFAULT_COLOR_CODE,
+        mirror: None,
+        origin: 0,
+        pending_scroll: 0,
+        terminal: 0,
+        displayed_terminal: 0,
+        status_position: None,
+        mode: Mode::Text80x25,
+        width: DEFAULT_WIDTH,
+        height: DEFAULT_HEIGHT,
+    });
+}
+
+// dump classico: offset (8 digitos) + 16 bytes em hex + os mesmos bytes em ascii = 78 colunas
+// 00000010  48 65 6c 6c 6f 20 57 6f  72 6c 64 21 00 00 00 00  |Hello World!....|
+// o endereco real aparece só no cabecalho, senao a linha nao cabe em 80 colunas
+#[allow(dead_code)]
+pub fn hexdump(bytes: &[u8]) {
+    use core::fmt::Write;
+    let mut writer = WRITER.lock();
+    let _ = writeln!(writer, "{:#018x}, {} bytes:", bytes.as_ptr() as usize, bytes.len());
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        let _ = write!(writer, "{:08x} ", line * 16);
+        for i in 0..16 {
+            if i == 8 {
+                writer.write_string(" ");
+            }
+            match chunk.get(i) {
+                Some(byte) => {
+                    let _ = write!(writer, " {:02x}", byte);
+                }
+                None => writer.write_string("   "),
+            }
+        }
+        writer.write_string("  |");
+        for byte in chunk {
+            // o writer transformaria controle em ■ e bytes altos em outros glifos -> ponto, igual o hexdump -C
+            let c = if byte.is_ascii_graphic() || *byte == b' ' { *byte } else { b'.' };
+            writer.write_byte(c);
+        }
+        writer.write_string("|\n");
+    }
+}
+
+// unsafe: [addr, addr + len) tem que estar mapeado e legivel
+#[allow(dead_code)]
+pub unsafe fn hexdump_raw(addr: usize, len: usize) {
+    hexdump(core::slice::from_raw_parts(addr as *const u8, len));
+}
@@ -0,0 +1,326 @@
+// programacao dos registradores da placa VGA (troca de modo de video, acesso a memoria de fonte)
+// tabelas de registradores tiradas dos modos padrao do VGA (mesmos valores que a BIOS usa)
+use x86_64::instructions::port::Port;
+
+const MISC_WRITE: u16 = 0x3C2;
+const MISC_READ: u16 = 0x3CC;
+const SEQ_INDEX: u16 = 0x3C4;
+const SEQ_DATA: u16 = 0x3C5;
+const CRTC_INDEX: u16 = 0x3D4;
+const CRTC_DATA: u16 = 0x3D5;
+const GC_INDEX: u16 = 0x3CE;
+const GC_DATA: u16 = 0x3CF;
+const AC_INDEX: u16 = 0x3C0; // o attribute controller usa a mesma porta pra indice e valor
+const AC_READ: u16 = 0x3C1;
+const INPUT_STATUS_1: u16 = 0x3DA; // ler essa porta reseta o flip-flop do attribute controller
+
+const SEQ_COUNT: usize = 5;
+const CRTC_COUNT: usize = 25;
+const GC_COUNT: usize = 9;
+const AC_COUNT: usize = 21;
+
+// valores de todos os registradores de um modo de video
+pub struct ModeRegisters {
+    pub misc: u8,
+    pub sequencer: [u8; SEQ_COUNT],
+    pub crtc: [u8; CRTC_COUNT],
+    pub graphics: [u8; GC_COUNT],
+    pub attribute: [u8; AC_COUNT],
+}
+
+// 80x25 texto, fonte 8x16 (o modo que o bootloader deixa)
+pub const TEXT_80X25: ModeRegisters = ModeRegisters {
+    misc: 0x67,
+    sequencer: [0x03, 0x00, 0x03, 0x00, 0x02],
+    crtc: [
+        0x5F, 0x4F, 0x50, 0x82, 0x55, 0x81, 0xBF, 0x1F, 0x00, 0x4F, 0x0D, 0x0E, 0x00, 0x00, 0x00,
+        0x50, 0x9C, 0x0E, 0x8F, 0x28, 0x1F, 0x96, 0xB9, 0xA3, 0xFF,
+    ],
+    graphics: [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0E, 0x00, 0xFF],
+    attribute: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x14, 0x07, 0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E,
+        0x3F, 0x0C, 0x00, 0x0F, 0x08, 0x00,
+    ],
+};
+
+// 80x50 texto: mesmos 400 scanlines do 80x25, mas com fonte 8x8 -> o dobro de linhas
+// muda só o max scan line (0x09) e o formato do cursor (0x0A/0x0B)
+pub const TEXT_80X50: ModeRegisters = ModeRegisters {
+    misc: 0x67,
+    sequencer: [0x03, 0x00, 0x03, 0x00, 0x02],
+    crtc: [
+        0x5F, 0x4F, 0x50, 0x82, 0x55, 0x81, 0xBF, 0x1F, 0x00, 0x47, 0x06, 0x07, 0x00, 0x00, 0x00,
+        0x50, 0x9C, 0x0E, 0x8F, 0x28, 0x1F, 0x96, 0xB9, 0xA3, 0xFF,
+    ],
+    graphics: [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0E, 0x00, 0xFF],
+    attribute: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x14, 0x07, 0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E,
+        0x3F, 0x0C, 0x00, 0x0F, 0x08, 0x00,
+    ],
+};
+
+// 90x60 texto: timing de 720x480 (clock de 28MHz, caractere de 8 pontos) com a fonte 8x8
+// misc 0xE7 -> polaridade dos syncs pra 480 linhas, seq 0x01 = 8 pontos por caractere
+// CRTC 0x13 (offset) = 45 -> 90 colunas, 0x12 (fim do display vertical) = 479
+pub const TEXT_90X60: ModeRegisters = ModeRegisters {
+    misc: 0xE7,
+    sequencer: [0x03, 0x01, 0x03, 0x00, 0x02],
+    crtc: [
+        0x6B, 0x59, 0x5A, 0x82, 0x60, 0x8D, 0x0B, 0x3E, 0x00, 0x47, 0x06, 0x07, 0x00, 0x00, 0x00,
+        0x00, 0xEA, 0x0C, 0xDF, 0x2D, 0x08, 0xE8, 0x05, 0xA3, 0xFF,
+    ],
+    graphics: [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0E, 0x00, 0xFF],
+    // pel panning (0x13) = 0 pq o caractere tem 8 pontos (o 8 dos outros modos é pra 9 pontos)
+    attribute: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x14, 0x07, 0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E,
+        0x3F, 0x0C, 0x00, 0x0F, 0x00, 0x00,
+    ],
+};
+
+// 320x200 com 256 cores (mode 13h) -> um byte por pixel a partir de 0xA0000
+#[allow(dead_code)]
+pub const MODE_13H: ModeRegisters = ModeRegisters {
+    misc: 0x63,
+    sequencer: [0x03, 0x01, 0x0F, 0x00, 0x0E],
+    crtc: [
+        0x5F, 0x4F, 0x50, 0x82, 0x54, 0x80, 0xBF, 0x1F, 0x00, 0x41, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x9C, 0x0E, 0x8F, 0x28, 0x40, 0x96, 0xB9, 0xA3, 0xFF,
+    ],
+    graphics: [0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x05, 0x0F, 0xFF],
+    attribute: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+        0x0F, 0x41, 0x00, 0x0F, 0x00, 0x00,
+    ],
+};
+
+fn outb(port: u16, value: u8) {
+    unsafe { Port::new(port).write(value) }
+}
+
+fn inb(port: u16) -> u8 {
+    unsafe { Port::new(port).read() }
+}
+
+pub fn read_sequencer(index: u8) -> u8 {
+    outb(SEQ_INDEX, index);
+    inb(SEQ_DATA)
+}
+
+pub fn write_sequencer(index: u8, value: u8) {
+    outb(SEQ_INDEX, index);
+    outb(SEQ_DATA, value);
+}
+
+pub fn read_crtc(index: u8) -> u8 {
+    outb(CRTC_INDEX, index);
+    inb(CRTC_DATA)
+}
+
+pub fn write_crtc(index: u8, value: u8) {
+    outb(CRTC_INDEX, index);
+    outb(CRTC_DATA, value);
+}
+
+pub fn read_graphics(index: u8) -> u8 {
+    outb(GC_INDEX, index);
+    inb(GC_DATA)
+}
+
+pub fn write_graphics(index: u8, value: u8) {
+    outb(GC_INDEX, index);
+    outb(GC_DATA, value);
+}
+
+// bit 5 do indice (0x20) liga a tela de novo depois de mexer na paleta do attribute controller
+pub fn read_attribute(index: u8) -> u8 {
+    inb(INPUT_STATUS_1);
+    outb(AC_INDEX, index | 0x20);
+    let value = inb(AC_READ);
+    inb(INPUT_STATUS_1);
+    value
+}
+
+pub fn write_attribute(index: u8, value: u8) {
+    inb(INPUT_STATUS_1);
+    outb(AC_INDEX, index | 0x20);
+    outb(AC_INDEX, value);
+}
+
+// bit 3 do attribute mode control (0x10): 1 -> bit 7 do atributo faz o texto piscar
+// 0 -> bit 7 vira o bit de intensidade do background (16 cores de fundo)
+const AC_MODE_CONTROL: u8 = 0x10;
+const AC_BLINK_ENABLE: u8 = 0x08;
+
+pub fn set_blink(enabled: bool) {
+    let mode = read_attribute(AC_MODE_CONTROL);
+    let mode = if enabled {
+        mode | AC_BLINK_ENABLE
+    } else {
+        mode & !AC_BLINK_ENABLE
+    };
+    write_attribute(AC_MODE_CONTROL, mode);
+}
+
+pub fn blink_enabled() -> bool {
+    read_attribute(AC_MODE_CONTROL) & AC_BLINK_ENABLE != 0
+}
+
+pub fn read_misc() -> u8 {
+    inb(MISC_READ)
+}
+
+pub fn write_misc(value: u8) {
+    outb(MISC_WRITE, value);
+}
+
+// tem placa vga? sem placa a leitura da porta volta 0xff
+// escreve no registrador do cursor (0x0F) e le de volta pra confirmar
+pub fn present() -> bool {
+    if read_misc() == 0xff {
+        return false;
+    }
+    let saved = read_crtc(0x0F);
+    write_crtc(0x0F, 0x5A);
+    let ok = read_crtc(0x0F) == 0x5A;
+    write_crtc(0x0F, saved);
+    ok
+}
+
+// escreve todos os registradores de um modo
+pub fn set_mode(mode: &ModeRegisters) {
+    write_misc(mode.misc);
+
+    for (i, value) in mode.sequencer.iter().enumerate() {
+        write_sequencer(i as u8, *value);
+    }
+
+    // os registradores 0-7 do CRTC sao protegidos contra escrita pelo bit 7 do registrador 0x11
+    write_crtc(0x03, read_crtc(0x03) | 0x80);
+    write_crtc(0x11, read_crtc(0x11) & !0x80);
+    for (i, value) in mode.crtc.iter().enumerate() {
+        let value = match i {
+            0x03 => *value | 0x80,
+            0x11 => *value & !0x80,
+            _ => *value,
+        };
+        write_crtc(i as u8, value);
+    }
+
+    for (i, value) in mode.graphics.iter().enumerate() {
+        write_graphics(i as u8, *value);
+    }
+
+    for (i, value) in mode.attribute.iter().enumerate() {
+        inb(INPUT_STATUS_1);
+        outb(AC_INDEX, i as u8);
+        outb(AC_INDEX, *value);
+    }
+    // trava a paleta e liga a tela de novo
+    inb(INPUT_STATUS_1);
+    outb(AC_INDEX, 0x20);
+}
+
+// a fonte do modo texto fica no plano 2 da memoria do VGA, 32 bytes por caractere
+pub const FONT_CHARS: usize = 256;
+pub const FONT_SLOT_SIZE: usize = 32;
+pub const FONT_SIZE: usize = FONT_CHARS * FONT_SLOT_SIZE;
+
+const PLANE_WINDOW: usize = 0xA0000;
+
+// deixa o plano 2 acessivel em 0xA0000 durante a closure e depois volta os registradores como estavam
+// no modo texto a memoria é intercalada (odd/even) e o plano 2 nao aparece pra cpu
+pub fn with_font_plane<F, R>(f: F) -> R
+where
+    F: FnOnce(*mut u8) -> R,
+{
+    let seq_map_mask = read_sequencer(0x02);
+    let seq_memory_mode = read_sequencer(0x04);
+    let gc_read_map = read_graphics(0x04);
+    let gc_mode = read_graphics(0x05);
+    let gc_misc = read_graphics(0x06);
+
+    write_sequencer(0x02, 0x04); // escreve só no plano 2
+    write_sequencer(0x04, 0x06); // desliga odd/even
+    write_graphics(0x04, 0x02); // le do plano 2
+    write_graphics(0x05, 0x00); // desliga odd/even
+    write_graphics(0x06, 0x04); // mapeia 0xA0000-0xAFFFF, modo grafico desligado
+
+    let result = f(PLANE_WINDOW as *mut u8);
+
+    write_sequencer(0x02, seq_map_mask);
+    write_sequencer(0x04, seq_memory_mode);
+    write_graphics(0x04, gc_read_map);
+    write_graphics(0x05, gc_mode);
+    write_graphics(0x06, gc_misc);
+    result
+}
+
+// copia a fonte atual pra `font`
+pub fn save_font(font: &mut [u8; FONT_SIZE]) {
+    with_font_plane(|plane| {
+        for (i, byte) in font.iter_mut().enumerate() {
+            *byte = unsafe { core::ptr::read_volatile(plane.add(i)) };
+        }
+    });
+}
+
+pub fn restore_font(font: &[u8; FONT_SIZE]) {
+    with_font_plane(|plane| {
+        for (i, byte) in font.iter().enumerate() {
+            unsafe { core::ptr::write_volatile(plane.add(i), *byte) };
+        }
+    });
+}
+
+// carrega uma fonte de `height` linhas por caractere (glifos um atras do outro, um byte por linha)
+// o resto de cada slot de 32 bytes fica zerado
+pub fn load_font(glyphs: &[u8], height: usize) {
+    let height = height.min(FONT_SLOT_SIZE);
+    with_font_plane(|plane| {
+        for char in 0..FONT_CHARS {
+            for line in 0..FONT_SLOT_SIZE {
+                let byte = if line < height {
+                    glyphs.get(char * height + line).copied().unwrap_or(0)
+                } else {
+                    0
+                };
+                unsafe { core::ptr::write_volatile(plane.add(char * FONT_SLOT_SIZE + line), byte) };
+            }
+        }
+    });
+}
+
+// altura da fonte do modo atual (max scan line + 1)
+pub fn font_height() -> usize {
+    (read_crtc(0x09) & 0x1f) as usize + 1
+}
+
+// glifos 8x16 customizados (logo, simbolos de medidor...) por cima da fonte atual
+// cada bitmap é uma linha por byte, bit 7 = pixel mais a esquerda
+// nos modos com fonte 8x8 só as 8 primeiras linhas aparecem
+pub const GLYPH_HEIGHT: usize = 16;
+
+pub type Glyph = [u8; GLYPH_HEIGHT];
+
+// escreve `glyphs` a partir do caractere `first` (o que passar do 255 é ignorado)
+pub fn write_glyphs(first: u8, glyphs: &[Glyph]) {
+    with_font_plane(|plane| {
+        for (i, glyph) in glyphs.iter().take(FONT_CHARS - first as usize).enumerate() {
+            let slot = (first as usize + i) * FONT_SLOT_SIZE;
+            for (line, byte) in glyph.iter().enumerate() {
+                unsafe { core::ptr::write_volatile(plane.add(slot + line), *byte) };
+            }
+        }
+    });
+}
+
+pub fn read_glyph(index: u8) -> Glyph {
+    let mut glyph = [0; GLYPH_HEIGHT];
+    with_font_plane(|plane| {
+        let slot = index as usize * FONT_SLOT_SIZE;
+        for (line, byte) in glyph.iter_mut().enumerate() {
+            *byte = unsafe { core::ptr::read_volatile(plane.add(slot + line)) };
+        }
+    });
+    glyph
+}
@@ -0,0 +1,583 @@
+// regioes do espaco de enderecamento (VMA): o que esta mapeado onde, com que flags e de onde vem a memoria
+// quem precisa de um pedaco de memoria virtual (heap, stacks, mmap, segmentos de ELF) pede pro
+// find_free_region/reserve em vez de inventar um endereco fixo
+// com kaslr o reserve sorteia o lugar dentro da janela (find_place) em vez de pegar o primeiro buraco
+// a lista é um array ordenado sem heap: o proprio heap é colocado por aqui e o page fault consulta
+// a lista sem poder alocar
+// regioes sob demanda (reserve_lazy) sao mapeadas pagina a pagina pelo page fault, no primeiro acesso
+// e paginas copy-on-write (clone_region_cow) só sao copiadas na primeira escrita
+// por enquanto só existe o espaco do kernel; cada processo vai ter o seu AddressSpace
+use crate::allocator::slab::{SlabBox, SlabCache};
+use crate::interrupts::{self, FaultAction, PageFault};
+use crate::memory::{self, frame, mapper};
+use core::fmt;
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
+use x86_64::registers::control::{Cr0, Cr0Flags};
+use x86_64::structures::paging::{Page, PageTableFlags, PhysFrame, Size4KiB};
+use x86_64::{PhysAddr, VirtAddr};
+
+const MAX_REGIONS: usize = 128;
+
+// bit da entrada que a cpu ignora: pagina compartilhada copy-on-write (sem WRITABLE ate a primeira escrita)
+pub const COW: PageTableFlags = PageTableFlags::BIT_9;
+
+// janela do kernel pras regioes dinamicas (longe do kernel e do mapeamento fisico do bootloader)
+pub const KERNEL_WINDOW_START: u64 = 0x4000_0000_0000;
+pub const KERNEL_WINDOW_END: u64 = 0x7fff_0000_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backing {
+    Anonymous,                      // frames quaisquer, zerados
+    #[allow(dead_code)]
+    File { id: usize, offset: u64 }, // conteudo de um arquivo (ainda nao tem vfs)
+    Mmio { phys: PhysAddr },         // registradores/memoria de dispositivo a partir de `phys`
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+    pub start: VirtAddr,
+    pub len: u64,
+    pub flags: PageTableFlags,
+    pub backing: Backing,
+    pub name: &'static str,
+    pub lazy: bool, // anonima sem frames: cada pagina é mapeada no primeiro acesso (page fault)
+    #[allow(dead_code)]
+    pub mmap: bool, // veio do vm::mmap -> só essas o munmap aceita tirar
+}
+
+impl Region {
+    pub fn end(&self) -> VirtAddr {
+        self.start + self.len
+    }
+
+    pub fn contains(&self, addr: VirtAddr) -> bool {
+        addr >= self.start && addr < self.end()
+    }
+
+    fn overlaps(&self, start: VirtAddr, end: VirtAddr) -> bool {
+        start < self.end() && self.start < end
+    }
+}
+
+// 400000000000-400000100000 rwx- anon heap
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flag = |bit, c| if self.flags.contains(bit) { c } else { '-' };
+        write!(
+            f,
+            "{:012x}-{:012x} r{}{}{} ",
+            self.start.as_u64(),
+            self.end().as_u64(),
+            flag(PageTableFlags::WRITABLE, 'w'),
+            if self.flags.contains(PageTableFlags::NO_EXECUTE) { '-' } else { 'x' },
+            flag(PageTableFlags::USER_ACCESSIBLE, 'u'),
+        )?;
+        match self.backing {
+            Backing::Anonymous if self.lazy => write!(f, "anon sob demanda {}", self.name),
+            Backing::Anonymous => write!(f, "anon {}", self.name),
+            Backing::File { id, offset } => write!(f, "file {}+{:#x} {}", id, offset, self.name),
+            Backing::Mmio { phys } => write!(f, "mmio {:#x} {}", phys.as_u64(), self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Overlap,    // encosta numa regiao que já existe
+    OutOfRange, // fora da janela do espaco
+    Unaligned,  // inicio ou tamanho fora de pagina
+    NoSpace,    // nenhum buraco grande o bastante (ou a lista encheu)
+    Unsupported, // operacao que nao vale pra esse tipo de regiao (ex: copy-on-write de mmio)
+    Map(mapper::Error),
+}
+
+impl From<mapper::Error> for Error {
+    fn from(error: mapper::Error) -> Error {
+        Error::Map(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Overlap => f.write_str("sobrepoe outra regiao"),
+            Error::OutOfRange => f.write_str("fora do espaco de enderecamento"),
+            Error::Unaligned => f.write_str("nao alinhado em pagina"),
+            Error::NoSpace => f.write_str("sem espaco virtual"),
+            Error::Unsupported => f.write_str("nao suportado pra essa regiao"),
+            Error::Map(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+fn align_up(addr: u64, align: u64) -> u64 {
+    (addr + align - 1) & !(align - 1)
+}
+
+// as regioes saem de um slab (cada mmap/stack/munmap cria e solta uma) -> o slabinfo mostra quantas tem
+static REGIONS: SlabCache<Region> = SlabCache::new("vm region");
+
+pub struct AddressSpace {
+    regions: heapless::Vec<SlabBox<Region>, MAX_REGIONS>, // ordenadas pelo inicio, sem sobreposicao
+    window_start: VirtAddr,
+    window_end: VirtAddr,
+}
+
+impl AddressSpace {
+    pub const fn new(window_start: VirtAddr, window_end: VirtAddr) -> AddressSpace {
+        AddressSpace {
+            regions: heapless::Vec::new(),
+            window_start,
+            window_end,
+        }
+    }
+
+    pub fn insert(&mut self, region: Region) -> Result<(), Error> {
+        if !region.start.is_aligned(4096u64) || region.len == 0 || !region.len.is_multiple_of(4096) {
+            return Err(Error::Unaligned);
+        }
+        if region.start < self.window_start || region.end() > self.window_end {
+            return Err(Error::OutOfRange);
+        }
+        if self.regions.iter().any(|other| other.overlaps(region.start, region.end())) {
+            return Err(Error::Overlap);
+        }
+        if self.regions.is_full() {
+            return Err(Error::NoSpace);
+        }
+        let region = REGIONS.alloc(region).ok_or(Error::Map(mapper::Error::OutOfFrames))?;
+        let index = self.regions.partition_point(|other| other.start < region.start);
+        self.regions.insert(index, region).map_err(|_| Error::NoSpace)
+    }
+
+    // tira a regiao que comeca em `start` (quem chamou cuida das paginas)
+    pub fn remove(&mut self, start: VirtAddr) -> Option<Region> {
+        let index = self.regions.iter().position(|region| region.start == start)?;
+        Some(*self.regions.remove(index))
+    }
+
+    pub fn find(&self, addr: VirtAddr) -> Option<&Region> {
+        let index = self.regions.partition_point(|region| region.start <= addr);
+        self.regions[..index].last().map(|region| &**region).filter(|region| region.contains(addr))
+    }
+
+    // primeiro buraco de `size` bytes com o inicio alinhado em `align` (potencia de 2, >= 4096)
+    pub fn find_free_region(&self, size: u64, align: u64) -> Option<VirtAddr> {
+        let size = align_up(size, 4096);
+        let align = align.max(4096);
+        let mut candidate = align_up(self.window_start.as_u64(), align);
+        for region in &self.regions {
+            if candidate + size <= region.start.as_u64() {
+                break;
+            }
+            candidate = candidate.max(align_up(region.end().as_u64(), align));
+        }
+        if candidate + size <= self.window_end.as_u64() {
+            Some(VirtAddr::new(candidate))
+        } else {
+            None
+        }
+    }
+
+    // acha um buraco e já registra a regiao nele; devolve o inicio
+    // onde o reserve coloca: sorteado na janela com kaslr (algumas tentativas), senao o primeiro livre
+    pub fn find_place(&self, size: u64, align: u64) -> Option<VirtAddr> {
+        let size = align_up(size, 4096);
+        let align = align.max(4096);
+        let first = align_up(self.window_start.as_u64(), align);
+        let last = self.window_end.as_u64().checked_sub(size)?;
+        if crate::kaslr::enabled() && last >= first {
+            for _ in 0..16 {
+                let start = VirtAddr::new(first + crate::kaslr::slide(last - first, align));
+                if !self.regions.iter().any(|region| region.overlaps(start, start + size)) {
+                    return Some(start);
+                }
+            }
+        }
+        self.find_free_region(size, align)
+    }
+
+    pub fn reserve(
+        &mut self,
+        size: u64,
+        align: u64,
+        flags: PageTableFlags,
+        backing: Backing,
+        name: &'static str,
+    ) -> Result<VirtAddr, Error> {
+        let start = self.find_place(size, align).ok_or(Error::NoSpace)?;
+        self.insert(Region {
+            start,
+            len: align_up(size, 4096),
+            flags,
+            backing,
+            name,
+            lazy: false,
+            mmap: false,
+        })?;
+        Ok(start)
+    }
+
+    // regiao anonima que nao custa nada ate ser usada: o page fault mapeia uma pagina zerada por vez
+    pub fn reserve_lazy(
+        &mut self,
+        size: u64,
+        align: u64,
+        flags: PageTableFlags,
+        name: &'static str,
+    ) -> Result<VirtAddr, Error> {
+        let start = self.find_place(size, align).ok_or(Error::NoSpace)?;
+        self.insert(Region {
+            start,
+            len: align_up(size, 4096),
+            flags,
+            backing: Backing::Anonymous,
+            name,
+            lazy: true,
+            mmap: false,
+        })?;
+        Ok(start)
+    }
+
+    // tira a regiao e desmapeia o que estiver mapeado nela; frames de regiao anonima voltam pro alocador
+    pub fn unmap_region(&mut self, start: VirtAddr) -> Result<Region, Error> {
+        let region = self.remove(start).ok_or(Error::OutOfRange)?;
+        let first = Page::<Size4KiB>::containing_address(region.start);
+        let pages = Page::range(first, first + region.len / 4096);
+        memory::with_mapper(|mapper| {
+            for page in pages {
+                if let Ok(frame) = mapper.unmap(page) {
+                    // frame compartilhado (copy-on-write) só volta quando o ultimo dono soltar
+                    if region.backing == Backing::Anonymous {
+                        frame::release(frame);
+                    }
+                }
+            }
+        });
+        Ok(region)
+    }
+
+    // copia uma regiao anonima sem copiar memoria: as duas passam a apontar pros mesmos frames, só leitura
+    // e marcadas COW; a primeira escrita em qualquer lado da page fault e ganha uma copia só dela
+    // (é o que o fork vai fazer com o espaco inteiro). devolve o inicio da copia
+    // só paginas de 4 KiB (regiao com pagina grande, tipo o heap -> Unsupported). se falhar no meio desfaz tudo
+    pub fn clone_region_cow(&mut self, start: VirtAddr, name: &'static str) -> Result<VirtAddr, Error> {
+        let region = **self.regions.iter().find(|region| region.start == start).ok_or(Error::OutOfRange)?;
+        if region.backing != Backing::Anonymous {
+            return Err(Error::Unsupported);
+        }
+        let first = Page::<Size4KiB>::containing_address(region.start);
+        let pages = region.len / 4096;
+        let huge = region.flags.contains(PageTableFlags::HUGE_PAGE)
+            || memory::with_mapper(|mapper| {
+                (0..pages).any(|index| {
+                    mapper
+                        .translate((first + index).start_address())
+                        .is_some_and(|(_, flags)| flags.contains(PageTableFlags::HUGE_PAGE))
+                })
+            });
+        if huge {
+            return Err(Error::Unsupported);
+        }
+        let copy_start = self.find_place(region.len, 4096).ok_or(Error::NoSpace)?;
+        self.insert(Region {
+            start: copy_start,
+            name,
+            mmap: false, // a copia é de quem pediu, nao do mmap
+            ..region
+        })?;
+        let copy_first = Page::<Size4KiB>::containing_address(copy_start);
+        let result = memory::with_mapper(|mapper| {
+            for index in 0..pages {
+                let page = first + index;
+                // pagina sob demanda que ninguem usou: cada lado vai ganhar a sua zerada
+                let Some((phys, flags)) = mapper.translate(page.start_address()) else {
+                    continue;
+                };
+                let frame = PhysFrame::containing_address(phys);
+                let shared = if flags.contains(PageTableFlags::WRITABLE) {
+                    (flags - PageTableFlags::WRITABLE - PageTableFlags::HUGE_PAGE) | COW
+                } else {
+                    flags - PageTableFlags::HUGE_PAGE
+                };
+                let copied = unsafe {
+                    mapper.map_page(copy_first + index, frame, shared).and_then(|()| {
+                        mapper.update_flags(page, shared).inspect_err(|_| {
+                            let _ = mapper.unmap(copy_first + index);
+                        })
+                    })
+                };
+                if let Err(error) = copied {
+                    undo_clone(mapper, first, copy_first, index);
+                    return Err(Error::from(error));
+                }
+                frame::add_ref(frame);
+            }
+            Ok(copy_start)
+        });
+        if result.is_err() {
+            self.remove(copy_start);
+        }
+        result
+    }
+
+    pub fn regions(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter().map(|region| &**region)
+    }
+}
+
+// mmap/munmap: memoria anonima no espaco atual (por enquanto só o do kernel; depois vira syscall)
+// por padrao é sob demanda: cada pagina ganha um frame zerado no primeiro acesso
+// ex: let buffer = vm::mmap(64 * 1024, vm::PROT_READ | vm::PROT_WRITE, 0)?; ... vm::munmap(buffer, 64 * 1024)?;
+#[allow(dead_code)]
+pub const PROT_NONE: u32 = 0;
+#[allow(dead_code)]
+pub const PROT_READ: u32 = 1 << 0;
+#[allow(dead_code)]
+pub const PROT_WRITE: u32 = 1 << 1;
+#[allow(dead_code)]
+pub const PROT_EXEC: u32 = 1 << 2;
+// mapeia tudo já no mmap em vez de esperar o page fault (ex: memoria usada com interrupcao desligada)
+#[allow(dead_code)]
+pub const MAP_POPULATE: u32 = 1 << 0;
+
+// PROT_* -> flags da tabela; escrita + execucao nao (W^X)
+#[allow(dead_code)]
+fn prot_flags(prot: u32) -> Result<PageTableFlags, Error> {
+    if prot & !(PROT_READ | PROT_WRITE | PROT_EXEC) != 0 || prot & (PROT_WRITE | PROT_EXEC) == PROT_WRITE | PROT_EXEC {
+        return Err(Error::Unsupported);
+    }
+    let mut flags = PageTableFlags::empty();
+    if prot != PROT_NONE {
+        flags |= PageTableFlags::PRESENT; // no x86 nao existe pagina presente sem leitura
+    }
+    if prot & PROT_WRITE != 0 {
+        flags |= PageTableFlags::WRITABLE;
+    }
+    if prot & PROT_EXEC == 0 {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    Ok(flags)
+}
+
+#[allow(dead_code)]
+pub fn mmap(len: u64, prot: u32, flags: u32) -> Result<VirtAddr, Error> {
+    if len == 0 || flags & !MAP_POPULATE != 0 {
+        return Err(Error::Unsupported);
+    }
+    let page_flags = prot_flags(prot)?;
+    let start = with_kernel(|space| {
+        let start = space.find_place(len, 4096).ok_or(Error::NoSpace)?;
+        space.insert(Region {
+            start,
+            len: align_up(len, 4096),
+            flags: page_flags,
+            backing: Backing::Anonymous,
+            name: "mmap",
+            lazy: true,
+            mmap: true,
+        })?;
+        Ok::<_, Error>(start)
+    })?;
+    if flags & MAP_POPULATE != 0 && prot != PROT_NONE {
+        if let Err(error) = populate(start, align_up(len, 4096), page_flags) {
+            let _ = munmap(start, len);
+            return Err(error);
+        }
+    }
+    Ok(start)
+}
+
+#[allow(dead_code)]
+fn populate(start: VirtAddr, len: u64, flags: PageTableFlags) -> Result<(), Error> {
+    let first = Page::<Size4KiB>::containing_address(start);
+    for page in Page::range(first, first + len / 4096) {
+        let frame = frame::allocate_frame().ok_or(Error::Map(mapper::Error::OutOfFrames))?;
+        unsafe { core::ptr::write_bytes(memory::phys_to_virt(frame.start_address()).as_mut_ptr::<u8>(), 0, 4096) };
+        memory::with_mapper(|mapper| unsafe { mapper.map_page(page, frame, flags) }).inspect_err(|_| {
+            frame::deallocate_frame(frame);
+        })?;
+    }
+    Ok(())
+}
+
+// só a regiao inteira que o mmap devolveu (pedaco do meio ainda nao -> Unsupported)
+#[allow(dead_code)]
+pub fn munmap(start: VirtAddr, len: u64) -> Result<(), Error> {
+    with_kernel(|space| {
+        let region = space.find(start).copied().ok_or(Error::OutOfRange)?;
+        if region.start != start || region.len != align_up(len, 4096) || !region.mmap {
+            return Err(Error::Unsupported);
+        }
+        space.unmap_region(start).map(|_| ())
+    })
+}
+
+// desfaz as `done` primeiras paginas de um clone_region_cow: tira a copia e solta a referencia dela
+// a original volta a ter escrita se ficou com um dono só (tinha escrita antes, ou o outro lado de um COW
+// antigo já copiou -> o mesmo que o page fault faria); com mais donos continua COW
+fn undo_clone(mapper: &mut memory::mapper::Mapper, first: Page, copy_first: Page, done: u64) {
+    for index in 0..done {
+        let Ok(frame) = mapper.unmap(copy_first + index) else {
+            continue;
+        };
+        frame::release(frame);
+        let page = first + index;
+        if let Some((_, flags)) = mapper.translate(page.start_address()) {
+            if flags.contains(COW) && frame::ref_count(frame) == 1 {
+                let _ = unsafe { mapper.update_flags(page, (flags - COW) | PageTableFlags::WRITABLE) };
+            }
+        }
+    }
+}
+
+static KERNEL: Mutex<AddressSpace> = Mutex::new(AddressSpace::new(
+    VirtAddr::new_truncate(KERNEL_WINDOW_START),
+    VirtAddr::new_truncate(KERNEL_WINDOW_END),
+));
+
+// o lock é pego sem interrupcao (o page fault vai consultar)
+pub fn with_kernel<R, F: FnOnce(&mut AddressSpace) -> R>(f: F) -> R {
+    crate::cpu::without_interrupts(|| f(&mut KERNEL.lock()))
+}
+
+// de dentro de um handler: None se o espaco esta ocupado (o fault veio de dentro do lock)
+pub fn try_with_kernel<R, F: FnOnce(&mut AddressSpace) -> R>(f: F) -> Option<R> {
+    KERNEL.try_lock().map(|mut space| f(&mut space))
+}
+
+// registra o que o bootloader já mapeou dentro da janela (o mapeamento da memoria fisica, se cair nela)
+// pra ninguem receber esse pedaco; depois do memory::init e antes de qualquer reserve
+pub fn init() {
+    let offset = crate::memory::physical_memory_offset();
+    let physical_end = crate::memory::memory_map()
+        .iter()
+        .map(|region| region.range.end_addr())
+        .max()
+        .unwrap_or(0);
+    let region = Region {
+        start: offset,
+        len: align_up(physical_end, 4096),
+        flags: PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE,
+        backing: Backing::Mmio { phys: PhysAddr::new(0) },
+        name: "memoria fisica",
+        lazy: false,
+        mmap: false,
+    };
+    // fora da janela é o caso normal -> nada a fazer
+    let _ = with_kernel(|space| space.insert(region));
+    init_cpu();
+    interrupts::set_page_fault_policy(Some(handle_page_fault));
+}
+
+// CR0.WP: sem ele o ring 0 escreve em pagina só leitura sem fault nenhum e o copy-on-write nao funciona
+// (em cada cpu; as APs chamam no ap_entry)
+// EFER.NXE: o bit NX das tabelas (W^X) só funciona com ele; nas APs o trampolim já liga, aqui é garantia
+pub fn init_cpu() {
+    unsafe { Cr0::update(|flags| flags.insert(Cr0Flags::WRITE_PROTECT)) };
+    if crate::cpu::features().nx {
+        unsafe { crate::cpu::msr::enable_efer(crate::cpu::msr::EFER_NXE) };
+    }
+}
+
+static DEMAND_FAULTS: AtomicU64 = AtomicU64::new(0);
+static COW_COPIES: AtomicU64 = AtomicU64::new(0);
+
+// paginas mapeadas pelo page fault desde o boot
+pub fn demand_faults() -> u64 {
+    DEMAND_FAULTS.load(Ordering::Relaxed)
+}
+
+// frames copiados por escrita em pagina COW desde o boot
+pub fn cow_copies() -> u64 {
+    COW_COPIES.load(Ordering::Relaxed)
+}
+
+// policy do page fault:
+// - acesso a pagina nao presente dentro de uma regiao sob demanda -> frame novo zerado
+// - escrita numa pagina COW -> copia do frame (ou só libera a escrita, se ninguem mais usa ele)
+// qualquer outro fault (protecao, fora de regiao, lock ocupado) continua fatal
+fn handle_page_fault(fault: &PageFault) -> FaultAction {
+    if fault.is_protection_violation() {
+        return if fault.is_write() { copy_on_write(fault.address) } else { FaultAction::Fatal };
+    }
+    let region = try_with_kernel(|space| space.find(fault.address).copied()).flatten();
+    match region {
+        // sem PRESENT (mmap com PROT_NONE) o acesso é erro mesmo
+        Some(region)
+            if region.lazy
+                && region.backing == Backing::Anonymous
+                && region.flags.contains(PageTableFlags::PRESENT) =>
+        {
+            map_zeroed(&region, fault.address)
+        }
+        _ => FaultAction::Fatal,
+    }
+}
+
+fn map_zeroed(region: &Region, address: VirtAddr) -> FaultAction {
+    let Some(frame) = frame::allocate_frame() else {
+        return FaultAction::Fatal;
+    };
+    unsafe { core::ptr::write_bytes(memory::phys_to_virt(frame.start_address()).as_mut_ptr::<u8>(), 0, 4096) };
+    let page = Page::<Size4KiB>::containing_address(address);
+    let mapped = memory::mapper::try_with_mapper(|mapper| unsafe { mapper.map_page(page, frame, region.flags) });
+    match mapped {
+        Some(Ok(())) => {
+            DEMAND_FAULTS.fetch_add(1, Ordering::Relaxed);
+            FaultAction::Retry
+        }
+        // outra cpu mapeou a mesma pagina primeiro -> usa a dela
+        Some(Err(mapper::Error::AlreadyMapped)) => {
+            frame::deallocate_frame(frame);
+            FaultAction::Retry
+        }
+        _ => {
+            frame::deallocate_frame(frame);
+            FaultAction::Fatal
+        }
+    }
+}
+
+fn copy_on_write(address: VirtAddr) -> FaultAction {
+    let page = Page::<Size4KiB>::containing_address(address);
+    let copied = memory::mapper::try_with_mapper(|mapper| {
+        let (phys, flags) = mapper.translate(page.start_address())?;
+        if !flags.contains(COW) {
+            return None;
+        }
+        let frame = PhysFrame::containing_address(phys);
+        let writable = (flags - COW) | PageTableFlags::WRITABLE;
+        // o outro lado já copiou (ou saiu) -> o frame é só nosso
+        if frame::ref_count(frame) == 1 {
+            return unsafe { mapper.update_flags(page, writable) }.ok();
+        }
+        let copy = frame::allocate_frame()?;
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                memory::phys_to_virt(phys).as_ptr::<u8>(),
+                memory::phys_to_virt(copy.start_address()).as_mut_ptr::<u8>(),
+                4096,
+            );
+        }
+        // se nao der pra trocar, a copia volta pro alocador e a pagina velha (COW) fica como estava
+        if mapper.unmap(page).is_err() {
+            frame::deallocate_frame(copy);
+            return None;
+        }
+        if unsafe { mapper.map_page(page, copy, writable) }.is_err() {
+            frame::deallocate_frame(copy);
+            // a tabela do caminho ainda existe (acabou de sair dela) -> remapear nao precisa alocar
+            let _ = unsafe { mapper.map_page(page, frame, flags) };
+            return None;
+        }
+        frame::release(frame);
+        COW_COPIES.fetch_add(1, Ordering::Relaxed);
+        Some(())
+    });
+    match copied {
+        Some(Some(())) => FaultAction::Retry,
+        _ => FaultAction::Fatal,
+    }
+}
@@ -0,0 +1,118 @@
+// janela: um retangulo da tela com cursor, cor e scroll proprios
+// ex: log embaixo e shell em cima, cada um escrevendo na sua janela sem brigar pelo cursor global
+use crate::cp437;
+use crate::vga_buffer::{ColorCode, WRITER};
+use core::fmt;
+
+#[allow(dead_code)]
+pub struct Window {
+    top: usize,
+    left: usize,
+    width: usize,
+    height: usize,
+    row: usize, // cursor relativo ao canto da janela
+    col: usize,
+    color_code: ColorCode,
+}
+
+#[allow(dead_code)]
+impl Window {
+    // a janela é cortada pra caber na tela; tamanho minimo 1x1
+    pub fn new(top: usize, left: usize, width: usize, height: usize, color_code: ColorCode) -> Window {
+        let (screen_width, screen_height) = {
+            let writer = WRITER.lock();
+            (writer.width(), writer.height())
+        };
+        let top = top.min(screen_height - 1);
+        let left = left.min(screen_width - 1);
+        Window {
+            top,
+            left,
+            width: width.clamp(1, screen_width - left),
+            height: height.clamp(1, screen_height - top),
+            row: 0,
+            col: 0,
+            color_code,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn set_color(&mut self, color_code: ColorCode) {
+        self.color_code = color_code;
+    }
+
+    pub fn color(&self) -> ColorCode {
+        self.color_code
+    }
+
+    // (linha, coluna) dentro da janela
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    pub fn set_cursor(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.height - 1);
+        self.col = col.min(self.width - 1);
+    }
+
+    pub fn clear(&mut self) {
+        let mut writer = WRITER.lock();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                writer.put_char_at(self.top + row, self.left + col, b' ', self.color_code);
+            }
+        }
+        writer.flush_if_auto();
+        self.row = 0;
+        self.col = 0;
+    }
+
+    pub fn write_string(&mut self, s: &str) {
+        let mut writer = WRITER.lock();
+        for c in s.chars() {
+            match c {
+                '\n' => self.new_line(&mut writer),
+                '\r' => self.col = 0,
+                '\u{8}' => {
+                    if self.col > 0 {
+                        self.col -= 1;
+                        writer.put_char_at(self.top + self.row, self.left + self.col, b' ', self.color_code);
+                    }
+                }
+                c => {
+                    if self.col >= self.width {
+                        self.new_line(&mut writer);
+                    }
+                    let byte = cp437::from_char(c).unwrap_or(0xfe);
+                    writer.put_char_at(self.top + self.row, self.left + self.col, byte, self.color_code);
+                    self.col += 1;
+                }
+            }
+        }
+        writer.flush_if_auto();
+    }
+
+    // na ultima linha da janela, só a janela faz scroll
+    fn new_line(&mut self, writer: &mut crate::vga_buffer::Writer) {
+        self.col = 0;
+        if self.row + 1 < self.height {
+            self.row += 1;
+        } else {
+            writer.scroll_rect_up(self.top, self.left, self.width, self.height, self.color_code);
+        }
+    }
+}
+
+impl fmt::Write for Window {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_string(s);
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+// receptor XMODEM(-CRC) pela serial: manda um binario pro kernel sem refazer a imagem do disco
+// no host: `sx -k arquivo < /dev/pts/N > /dev/pts/N` (ou o "send xmodem" do minicom/picocom)
+// aceita blocos de 128 (SOH) e de 1024 bytes (STX, XMODEM-1K)
+use crate::time::Instant;
+use core::fmt;
+use core::time::Duration;
+use spin::Mutex;
+
+const SOH: u8 = 0x01;
+const STX: u8 = 0x02;
+const EOT: u8 = 0x04;
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+const CAN: u8 = 0x18;
+const CRC_MODE: u8 = b'C'; // pede o modo com crc-16 em vez do checksum de 1 byte
+
+const MAX_BLOCK: usize = 1024;
+const MAX_ERRORS: usize = 10;
+const START_RETRIES: usize = 10; // um 'C' por segundo, depois desiste
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    NoPort,         // a COM nao existe ou nao respondeu
+    Timeout,        // o outro lado parou de mandar
+    Cancelled,      // recebeu CAN CAN
+    TooLarge,       // nao cabe no buffer
+    TooManyErrors,  // muito bloco com crc errado seguido
+    OutOfSequence,  // numero de bloco que nao era o esperado
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Error::NoPort => "porta serial indisponivel",
+            Error::Timeout => "tempo esgotado",
+            Error::Cancelled => "cancelado pelo remetente",
+            Error::TooLarge => "arquivo maior que o buffer",
+            Error::TooManyErrors => "erros demais na transmissao",
+            Error::OutOfSequence => "bloco fora de sequencia",
+        };
+        f.write_str(message)
+    }
+}
+
+// buffer fixo pro shell (64 KiB no .bss); quem tiver o proprio buffer usa receive() direto
+pub const PAYLOAD_SIZE: usize = 64 * 1024;
+
+pub struct Payload {
+    pub bytes: [u8; PAYLOAD_SIZE],
+    pub len: usize,
+}
+
+pub static PAYLOAD: Mutex<Payload> = Mutex::new(Payload {
+    bytes: [0; PAYLOAD_SIZE],
+    len: 0,
+});
+
+struct Link {
+    number: usize,
+}
+
+impl Link {
+    // a COM1 tem a interrupcao de rx ligada -> os bytes podem estar no buffer do driver e nao na fifo
+    fn try_read(&self) -> Option<u8> {
+        if self.number == 1 {
+            return crate::serial::try_read();
+        }
+        crate::serial::port(self.number)?.lock().try_receive()
+    }
+
+    // espera ate `seconds` pelo relogio (time::Instant), nao por numero de leituras
+    fn read(&self, seconds: u32) -> Option<u8> {
+        let deadline = Instant::now() + Duration::from_secs(seconds.into());
+        loop {
+            if let Some(byte) = self.try_read() {
+                return Some(byte);
+            }
+            if deadline.has_passed() {
+                return None;
+            }
+            crate::cpu::spin_hint();
+        }
+    }
+
+    fn send(&self, byte: u8) {
+        if let Some(port) = crate::serial::port(self.number) {
+            port.lock().send(byte);
+        }
+    }
+
+    // numero do bloco, complemento, e depois dados + crc em `block`; None se parou no meio
+    fn read_block(&self, block: &mut [u8]) -> Option<(u8, u8)> {
+        let number = self.read(1)?;
+        let complement = self.read(1)?;
+        for byte in block.iter_mut() {
+            *byte = self.read(1)?;
+        }
+        Some((number, complement))
+    }
+
+    // espera a linha ficar quieta (joga fora o resto de um bloco ruim)
+    fn purge(&self) {
+        while self.read(1).is_some() {}
+    }
+
+    fn cancel(&self) {
+        for _ in 0..3 {
+            self.send(CAN);
+        }
+    }
+}
+
+// CRC-16/XMODEM (polinomio 0x1021, comeca em 0)
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+// recebe pela COMn direto em `dest`; retorna quantos bytes chegaram
+// o ultimo bloco vem completado com 0x1A (o protocolo nao manda o tamanho real)
+// -> o tamanho é arredondado pra cima em blocos de 128/1024
+pub fn receive(number: usize, dest: &mut [u8]) -> Result<usize, Error> {
+    match crate::serial::port(number) {
+        Some(port) if port.lock().is_present() => {}
+        _ => return Err(Error::NoPort),
+    }
+    let link = Link { number };
+
+    // pede o inicio ate o remetente responder
+    let mut first = None;
+    for _ in 0..START_RETRIES {
+        link.send(CRC_MODE);
+        if let Some(byte) = link.read(1) {
+            first = Some(byte);
+            break;
+        }
+    }
+    let mut header = match first {
+        Some(byte) => byte,
+        None => return Err(Error::Timeout),
+    };
+
+    let mut expected: u8 = 1;
+    let mut len = 0;
+    let mut errors = 0;
+    let mut block = [0u8; MAX_BLOCK + 2];
+    loop {
+        let size = match header {
+            SOH => 128,
+            STX => MAX_BLOCK,
+            EOT => {
+                link.send(ACK);
+                return Ok(len);
+            }
+            CAN => {
+                if link.read(1) == Some(CAN) {
+                    return Err(Error::Cancelled);
+                }
+                0
+            }
+            _ => 0,
+        };
+
+        let received = if size > 0 {
+            link.read_block(&mut block[..size + 2])
+        } else {
+            None
+        };
+
+        match received {
+            Some((number, complement)) if number == !complement => {
+                let crc = u16::from_be_bytes([block[size], block[size + 1]]);
+                if crc != crc16(&block[..size]) {
+                    errors += 1;
+                    link.send(NAK);
+                } else if number == expected.wrapping_sub(1) {
+                    // o ACK anterior se perdeu e o bloco veio de novo
+                    link.send(ACK);
+                } else if number != expected {
+                    link.cancel();
+                    return Err(Error::OutOfSequence);
+                } else if len + size > dest.len() {
+                    link.cancel();
+                    return Err(Error::TooLarge);
+                } else {
+                    dest[len..len + size].copy_from_slice(&block[..size]);
+                    len += size;
+                    expected = expected.wrapping_add(1);
+                    errors = 0;
+                    link.send(ACK);
+                }
+            }
+            _ => {
+                // header lixo, bloco incompleto ou numero corrompido
+                errors += 1;
+                link.purge();
+                link.send(NAK);
+            }
+        }
+
+        if errors >= MAX_ERRORS {
+            link.cancel();
+            return Err(Error::TooManyErrors);
+        }
+        header = match link.read(10) {
+            Some(byte) => byte,
+            None => {
+                link.cancel();
+                return Err(Error::Timeout);
+            }
+        };
+    }
+}